@@ -32,7 +32,7 @@ impl SpellRange {
         let start: u32 = self.start.base10_parse()?;
         let end: u32 = self.end.base10_parse()?;
 
-        if start >= end {
+        if start > end {
             Err(syn::Error::new(self.start.span(), "invalid range"))
         } else {
             Ok(start..=end)
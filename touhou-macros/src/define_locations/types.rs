@@ -943,7 +943,7 @@ impl StageLocations {
             }
         } else {
             quote! {
-                T: crate::memory::traits::StageData<#game>
+                T: crate::memory::traits::StageData<#game> + crate::memory::traits::ECLTimeline<#game>
             }
         };
 
@@ -1190,7 +1190,8 @@ impl GameLocations {
             }
         } else {
             quote! {
-                T: crate::memory::traits::RunData<#game>
+                T: crate::memory::traits::RunData<#game>,
+                T::StageState: crate::memory::traits::ECLTimeline<#game>
             }
         };
 
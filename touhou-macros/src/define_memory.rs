@@ -1,5 +1,5 @@
 use proc_macro2::TokenStream;
-use quote::{quote, quote_spanned};
+use quote::{format_ident, quote, quote_spanned};
 use syn::parse::{Parse, ParseStream};
 use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
@@ -10,6 +10,299 @@ mod kw {
     syn::custom_keyword!(snapshot);
     syn::custom_keyword!(access);
     syn::custom_keyword!(game);
+    syn::custom_keyword!(versions);
+    syn::custom_keyword!(signatures);
+}
+
+/// The first element of an address chain.
+#[derive(Debug)]
+enum ChainBase {
+    /// A fixed address.
+    Address(LitInt),
+    /// An address resolved by scanning for a named signature when attaching to the process.
+    Signature(Ident),
+}
+
+impl ChainBase {
+    fn describe(&self) -> String {
+        match self {
+            Self::Address(addr) => format!("{:#010x}", addr.base10_parse::<u32>().unwrap()),
+            Self::Signature(name) => format!("<{}>", name),
+        }
+    }
+}
+
+impl quote::ToTokens for ChainBase {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self {
+            Self::Address(addr) => addr.to_tokens(tokens),
+            // only the offsets for the detected version get evaluated, and those signatures are always resolved
+            Self::Signature(name) => tokens.extend(
+                quote! { #name.expect("signature was not resolved for this version").get() },
+            ),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct AddressChain {
+    base: ChainBase,
+    offsets: Vec<LitInt>,
+}
+
+impl Parse for AddressChain {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let base = if input.peek(Ident) {
+            ChainBase::Signature(input.parse()?)
+        } else {
+            ChainBase::Address(input.parse()?)
+        };
+
+        let offsets = if input.is_empty() {
+            Vec::new()
+        } else {
+            input.parse::<Token![,]>()?;
+            Punctuated::<LitInt, Token![,]>::parse_terminated(input)?
+                .into_iter()
+                .collect()
+        };
+
+        Ok(Self { base, offsets })
+    }
+}
+
+impl AddressChain {
+    fn describe(&self) -> String {
+        let base = self.base.describe();
+        let offsets = self
+            .offsets
+            .iter()
+            .map(LitInt::base10_parse)
+            .collect::<Result<Vec<u32>>>()
+            .unwrap();
+        match offsets.len() {
+            0 => format!("address `{}`", base),
+            1 => format!("address `(*{}) + {:#04x}`", base, offsets[0]),
+            _ => {
+                let rest = offsets
+                    .iter()
+                    .map(|offset| format!("{:#04x}", offset))
+                    .collect::<Vec<_>>()
+                    .join(" => ");
+                format!("address chain `{} => {}`", base, rest)
+            }
+        }
+    }
+
+    fn signature(&self) -> Option<&Ident> {
+        match &self.base {
+            ChainBase::Signature(name) => Some(name),
+            ChainBase::Address(_) => None,
+        }
+    }
+
+    fn to_expr(&self, uses_signature: bool) -> TokenStream {
+        let base = &self.base;
+        let offsets = &self.offsets;
+
+        if uses_signature {
+            quote! { vec![#base #(, #offsets)*] }
+        } else {
+            quote! { &[#base #(, #offsets)*] }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct SignatureDef {
+    name: Ident,
+    _eq: Token![=],
+    pattern: LitStr,
+    offset: Option<(Token![+], LitInt)>,
+}
+
+impl Parse for SignatureDef {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Ok(Self {
+            name: input.parse()?,
+            _eq: input.parse()?,
+            pattern: input.parse()?,
+            offset: if input.peek(Token![+]) {
+                Some((input.parse()?, input.parse()?))
+            } else {
+                None
+            },
+        })
+    }
+}
+
+impl SignatureDef {
+    fn parse_pattern(&self) -> Result<Vec<Option<u8>>> {
+        let pattern = self
+            .pattern
+            .value()
+            .split_whitespace()
+            .map(|byte| match byte {
+                "?" | "??" => Ok(None),
+                _ if byte.len() == 2 => u8::from_str_radix(byte, 16).map(Some).map_err(|_| {
+                    syn::Error::new(
+                        self.pattern.span(),
+                        format!("invalid signature byte '{}'", byte),
+                    )
+                }),
+                _ => Err(syn::Error::new(
+                    self.pattern.span(),
+                    format!("invalid signature byte '{}'", byte),
+                )),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if pattern.is_empty() {
+            Err(syn::Error::new(
+                self.pattern.span(),
+                "signature pattern is empty",
+            ))
+        } else {
+            Ok(pattern)
+        }
+    }
+
+    /// Resolve this signature, if any of the fields read from the detected game version use it.
+    fn resolve_stmt(
+        &self,
+        fields: &[(Vec<Attribute>, MemoryField)],
+        version_type: Option<&Ident>,
+    ) -> TokenStream {
+        let name = &self.name;
+        let offset = self
+            .offset
+            .as_ref()
+            .map(|(_, offset)| quote! { #offset })
+            .unwrap_or_else(|| quote! { 0 });
+        let pattern = self
+            .parse_pattern()
+            .unwrap()
+            .into_iter()
+            .map(|byte| match byte {
+                Some(byte) => quote! { Some(#byte) },
+                None => quote! { None },
+            });
+
+        let resolve = quote! {
+            handle.resolve_signature(
+                &arch,
+                &touhou_process::Signature::new(vec![#(#pattern),*]),
+                image.clone(),
+                #offset,
+            )
+        };
+
+        let mut used_by = Vec::new();
+        let mut always_used = false;
+        for (_, field) in fields {
+            match &field.offsets {
+                FieldOffsets::Fixed { offsets, .. } => {
+                    always_used |= offsets.signature() == Some(name);
+                }
+                FieldOffsets::Versioned { versions, .. } => used_by.extend(
+                    versions
+                        .iter()
+                        .filter(|entry| entry.offsets.signature() == Some(name))
+                        .map(|entry| &entry.version),
+                ),
+            }
+        }
+
+        let value = if always_used {
+            quote! { Some(#resolve?) }
+        } else if used_by.is_empty() {
+            quote! { None }
+        } else {
+            quote! {
+                matches!(version, #(#version_type::#used_by)|*)
+                    .then(|| #resolve)
+                    .transpose()?
+            }
+        };
+
+        quote! {
+            #[allow(unused_variables)]
+            let #name: Option<std::num::NonZeroUsize> = #value;
+        }
+    }
+}
+
+#[derive(Debug)]
+struct VersionDef {
+    attrs: Vec<Attribute>,
+    name: Ident,
+    _eq: Token![=],
+    timestamp: LitInt,
+}
+
+impl Parse for VersionDef {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Ok(Self {
+            attrs: input.call(Attribute::parse_outer)?,
+            name: input.parse()?,
+            _eq: input.parse()?,
+            timestamp: input.parse()?,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct VersionOffsets {
+    version: Ident,
+    _colon: Token![:],
+    _bracket: token::Bracket,
+    offsets: AddressChain,
+}
+
+impl Parse for VersionOffsets {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let content;
+
+        Ok(Self {
+            version: input.parse()?,
+            _colon: input.parse()?,
+            _bracket: bracketed!(content in input),
+            offsets: content.parse()?,
+        })
+    }
+}
+
+#[derive(Debug)]
+enum FieldOffsets {
+    Fixed {
+        _bracket: token::Bracket,
+        offsets: AddressChain,
+    },
+    Versioned {
+        _brace: token::Brace,
+        versions: Punctuated<VersionOffsets, Token![,]>,
+    },
+}
+
+impl Parse for FieldOffsets {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let content;
+        let lookahead = input.lookahead1();
+
+        if lookahead.peek(token::Bracket) {
+            Ok(Self::Fixed {
+                _bracket: bracketed!(content in input),
+                offsets: content.parse()?,
+            })
+        } else if lookahead.peek(token::Brace) {
+            Ok(Self::Versioned {
+                _brace: braced!(content in input),
+                versions: content.parse_terminated(VersionOffsets::parse, Token![,])?,
+            })
+        } else {
+            Err(lookahead.error())
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -18,53 +311,121 @@ struct MemoryField {
     _colon: Token![:],
     elem_type: Type,
     _at: Token![@],
-    _bracket: token::Bracket,
-    offsets: Punctuated<LitInt, Token![,]>,
+    offsets: FieldOffsets,
 }
 
 impl Parse for MemoryField {
     fn parse(input: ParseStream) -> Result<Self> {
-        let content;
-
         Ok(Self {
             name: input.parse()?,
             _colon: input.parse()?,
             elem_type: input.parse()?,
             _at: input.parse()?,
-            _bracket: bracketed!(content in input),
-            offsets: content.parse_terminated(LitInt::parse, Token![,])?,
+            offsets: input.parse()?,
         })
     }
 }
 
 impl MemoryField {
     fn format_offset_docs(&self) -> String {
-        let offsets = self
-            .offsets
+        match &self.offsets {
+            FieldOffsets::Fixed { offsets, .. } if offsets.offsets.len() <= 1 => {
+                format!("This value is located at {}.", offsets.describe())
+            }
+            FieldOffsets::Fixed { offsets, .. } => {
+                format!("This value is found via {}", offsets.describe())
+            }
+            FieldOffsets::Versioned { versions, .. } => {
+                let mut ret = String::from("This value's location depends on the game version:\n");
+                for entry in versions {
+                    ret.push_str(&format!(
+                        "- `{}`: {}\n",
+                        entry.version,
+                        entry.offsets.describe()
+                    ));
+                }
+                ret
+            }
+        }
+    }
+
+    fn address_chains(&self) -> Vec<&AddressChain> {
+        match &self.offsets {
+            FieldOffsets::Fixed { offsets, .. } => vec![offsets],
+            FieldOffsets::Versioned { versions, .. } => {
+                versions.iter().map(|entry| &entry.offsets).collect()
+            }
+        }
+    }
+
+    /// Whether any of this field's addresses need to be resolved when attaching to the process.
+    ///
+    /// These addresses aren't known at compile time, so their fields use [`DataItem`](touhou_process::DataItem) instead of `FixedData`.
+    fn uses_signature(&self) -> bool {
+        self.address_chains()
             .iter()
-            .map(LitInt::base10_parse)
-            .collect::<Result<Vec<u32>>>()
-            .unwrap();
-        match offsets.len() {
-            0 => String::new(),
-            1 => format!("This value is located at address `{:#010x}`.", offsets[0]),
-            2 => format!(
-                "This value is located at address `(*{:#010x}) + {:#04x}`.",
-                offsets[0], offsets[1]
-            ),
-            _ => {
-                let (first, rest) = offsets.split_first().unwrap();
-                let rest = rest
-                    .iter()
-                    .map(|offset| format!("{:#04x}", offset))
-                    .collect::<Vec<_>>()
-                    .join(" => ");
-                format!(
-                    "This value is found via address chain `{:#010x} => {}`",
-                    first, rest
-                )
+            .any(|chain| chain.signature().is_some())
+    }
+
+    /// Check that every signature referenced by this field is defined.
+    fn check_signatures(&self, defined: &[SignatureDef]) -> Result<()> {
+        for name in self
+            .address_chains()
+            .iter()
+            .filter_map(|chain| chain.signature())
+        {
+            if !defined.iter().any(|def| def.name == *name) {
+                return Err(syn::Error::new(name.span(), "unknown signature"));
             }
         }
+
+        Ok(())
+    }
+
+    /// Check that per-version offsets are given for exactly the versions defined for the game.
+    fn check_versions(
+        &self,
+        defined: Option<&(Vec<Attribute>, Ident, Vec<VersionDef>)>,
+    ) -> Result<()> {
+        let FieldOffsets::Versioned { versions, .. } = &self.offsets else {
+            return Ok(());
+        };
+
+        let Some((_, _, defined)) = defined else {
+            return Err(syn::Error::new(
+                self.name.span(),
+                "per-version offsets given without a `versions` definition",
+            ));
+        };
+
+        for (i, entry) in versions.iter().enumerate() {
+            if !defined.iter().any(|def| def.name == entry.version) {
+                return Err(syn::Error::new(entry.version.span(), "unknown version"));
+            }
+
+            if versions
+                .iter()
+                .take(i)
+                .any(|prev| prev.version == entry.version)
+            {
+                return Err(syn::Error::new(
+                    entry.version.span(),
+                    "multiple offsets given for version",
+                ));
+            }
+        }
+
+        if let Some(missing) = defined
+            .iter()
+            .find(|def| !versions.iter().any(|entry| entry.version == def.name))
+        {
+            return Err(syn::Error::new(
+                self.name.span(),
+                format!("no offsets given for version {}", missing.name),
+            ));
+        }
+
+        Ok(())
     }
 
     fn snapshot_field_def(&self, attrs: &[Attribute]) -> TokenStream {
@@ -96,40 +457,83 @@ impl MemoryField {
         let elem_type = &self.elem_type;
         let offset_docs = self.format_offset_docs();
         let span = name.span();
+        let item_type = if self.uses_signature() {
+            quote! { DataItem }
+        } else {
+            quote! { FixedData }
+        };
 
         quote_spanned! {span=>
             #(#attrs)*
             ///
             #[doc = #offset_docs]
-            #name: touhou_process::FixedData<#elem_type, touhou_process::LittleEndian<4>>
+            #name: touhou_process::#item_type<#elem_type, touhou_process::LittleEndian<4>>
         }
     }
 
-    fn access_create_expr(&self) -> TokenStream {
+    fn access_create_expr(&self, version_type: Option<&Ident>) -> TokenStream {
         let name = &self.name;
-        let offsets = self.offsets.iter();
         let span = self.elem_type.span();
+        let uses_signature = self.uses_signature();
+        let new_item = if uses_signature {
+            quote! { new_data_item_offsets }
+        } else {
+            quote! { new_fixed_item }
+        };
+
+        match &self.offsets {
+            FieldOffsets::Fixed { offsets, .. } => {
+                let offsets = offsets.to_expr(uses_signature);
+                quote_spanned!(span=> #name: handle.#new_item(#offsets))
+            }
+            FieldOffsets::Versioned { versions, .. } => {
+                let arms = versions.iter().map(|entry| {
+                    let version = &entry.version;
+                    let offsets = entry.offsets.to_expr(uses_signature);
+                    quote! { #version_type::#version => #offsets }
+                });
 
-        quote_spanned!(span=> #name: handle.new_fixed_item(&[#(#offsets),*]))
+                quote_spanned! {span=>
+                    #name: handle.#new_item(match version {
+                        #(#arms),*
+                    })
+                }
+            }
+        }
     }
 
     fn access_fn(&self, attrs: &[Attribute], game: &Ident) -> TokenStream {
         let name = &self.name;
+        let name_str = name.to_string();
         let elem_type = &self.elem_type;
         let offset_docs = self.format_offset_docs();
         let span = elem_type.span();
+        // signature-based fields always have a non-empty address chain
+        let unwrap_read = self
+            .uses_signature()
+            .then(|| quote! { .map(Option::unwrap) });
 
         quote_spanned! {span=>
             #(#attrs)*
             ///
             #[doc = #offset_docs]
             pub fn #name(&self) -> Result<#elem_type, crate::memory::MemoryReadError<#game>> {
-                use crate::memory::MemoryReadError;
-                self.#name.read().map_err(MemoryReadError::from)
+                use crate::memory::{MemoryReadError, MemorySource};
+                match &self.0 {
+                    MemorySource::Process(fields) => fields.#name.read_with(&fields.cache)#unwrap_read.map_err(MemoryReadError::from),
+                    #[cfg(feature = "replay")]
+                    MemorySource::Replay(frame) => frame.get(#name_str),
+                }
             }
         }
     }
 
+    fn record_expr(&self) -> TokenStream {
+        let name = &self.name;
+        let name_str = name.to_string();
+        quote! { frame.insert(#name_str, self.#name()); }
+    }
+
     fn wrapper_access_fn(&self, attrs: &[Attribute], game: &Ident) -> TokenStream {
         let name = &self.name;
         let elem_type = &self.elem_type;
@@ -140,7 +544,7 @@ impl MemoryField {
             ///
             #[doc = #offset_docs]
             pub fn #name(&mut self) -> Result<Option<#elem_type>, crate::memory::MemoryReadError<#game>> {
-                self.0.access().map(|inner| inner.#name()).transpose()
+                self.0.access_uncached().map(|inner| inner.#name()).transpose()
             }
         }
     }
@@ -171,9 +575,23 @@ enum MemoryDefElement {
         _eq: Token![=],
         name: Ident,
     },
+    Versions {
+        attrs: Vec<Attribute>,
+        _kw: kw::versions,
+        _eq: Token![=],
+        name: Ident,
+        _brace: token::Brace,
+        versions: Punctuated<VersionDef, Token![,]>,
+    },
+    Signatures {
+        _kw: kw::signatures,
+        _eq: Token![=],
+        _brace: token::Brace,
+        signatures: Punctuated<SignatureDef, Token![,]>,
+    },
     Field {
         attrs: Vec<Attribute>,
-        field: MemoryField,
+        field: Box<MemoryField>,
     },
 }
 
@@ -209,10 +627,30 @@ impl Parse for MemoryDefElement {
                 _eq: input.parse()?,
                 name: input.parse()?,
             })
+        } else if lookahead.peek(kw::versions) {
+            let content;
+
+            Ok(Self::Versions {
+                attrs,
+                _kw: input.parse()?,
+                _eq: input.parse()?,
+                name: input.parse()?,
+                _brace: braced!(content in input),
+                versions: content.parse_terminated(VersionDef::parse, Token![,])?,
+            })
+        } else if lookahead.peek(kw::signatures) {
+            let content;
+
+            Ok(Self::Signatures {
+                _kw: input.parse()?,
+                _eq: input.parse()?,
+                _brace: braced!(content in input),
+                signatures: content.parse_terminated(SignatureDef::parse, Token![,])?,
+            })
         } else {
             Ok(Self::Field {
                 attrs,
-                field: input.parse()?,
+                field: Box::new(input.parse()?),
             })
         }
     }
@@ -247,6 +685,8 @@ pub struct MemoryDef {
     access_name: (Vec<Attribute>, Ident),
     process_names: Vec<LitStr>,
     game_type: Ident,
+    versions: Option<(Vec<Attribute>, Ident, Vec<VersionDef>)>,
+    signatures: Vec<SignatureDef>,
     fields: Vec<(Vec<Attribute>, MemoryField)>,
 }
 
@@ -261,12 +701,14 @@ impl Parse for MemoryDef {
         let mut snapshot_name = None;
         let mut access_name = None;
         let mut game_type = None;
+        let mut versions = None;
+        let mut signatures: Vec<SignatureDef> = Vec::new();
         let mut process_names = Vec::new();
         let mut fields = Vec::new();
 
         for elem in elems {
             match elem {
-                MemoryDefElement::Field { attrs, field } => fields.push((attrs, field)),
+                MemoryDefElement::Field { attrs, field } => fields.push((attrs, *field)),
                 MemoryDefElement::ProcessName { name, .. } => process_names.push(name),
                 MemoryDefElement::SnapshotType { attrs, name, .. } => {
                     if snapshot_name.is_none() {
@@ -295,6 +737,38 @@ impl Parse for MemoryDef {
                         return Err(syn::Error::new(name.span(), "multiple game types given"));
                     }
                 }
+                MemoryDefElement::Versions {
+                    attrs,
+                    name,
+                    versions: defs,
+                    ..
+                } => {
+                    if versions.is_some() {
+                        return Err(syn::Error::new(
+                            name.span(),
+                            "multiple version definitions given",
+                        ));
+                    } else if defs.is_empty() {
+                        return Err(syn::Error::new(name.span(), "no versions given"));
+                    }
+
+                    versions = Some((attrs, name, defs.into_iter().collect()));
+                }
+                MemoryDefElement::Signatures {
+                    signatures: defs, ..
+                } => {
+                    for def in defs {
+                        def.parse_pattern()?;
+                        if signatures.iter().any(|prev| prev.name == def.name) {
+                            return Err(syn::Error::new(
+                                def.name.span(),
+                                "multiple signatures given with the same name",
+                            ));
+                        }
+
+                        signatures.push(def);
+                    }
+                }
             }
         }
 
@@ -302,6 +776,11 @@ impl Parse for MemoryDef {
             return Err(input.error("no process names given"));
         }
 
+        for (_, field) in &fields {
+            field.check_versions(versions.as_ref())?;
+            field.check_signatures(&signatures)?;
+        }
+
         Ok(Self {
             attrs: main_attrs,
             name,
@@ -309,12 +788,68 @@ impl Parse for MemoryDef {
             access_name: access_name.ok_or_else(|| input.error("no access type name given"))?,
             game_type: game_type.ok_or_else(|| input.error("no game type given"))?,
             process_names,
+            versions,
+            signatures,
             fields,
         })
     }
 }
 
 impl MemoryDef {
+    fn define_version_enum(&self) -> Option<TokenStream> {
+        let game = &self.game_type;
+        let (attrs, name, versions) = self.versions.as_ref()?;
+        let variants = versions.iter().map(|def| {
+            let attrs = &def.attrs;
+            let name = &def.name;
+            quote! { #(#attrs)* #name }
+        });
+        let to_timestamp = versions.iter().map(|def| {
+            let variant = &def.name;
+            let timestamp = &def.timestamp;
+            quote! { Self::#variant => #timestamp }
+        });
+        let from_timestamp = versions.iter().map(|def| {
+            let variant = &def.name;
+            let timestamp = &def.timestamp;
+            quote! { #timestamp => Some(Self::#variant) }
+        });
+
+        Some(quote! {
+            #(#attrs)*
+            #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+            pub enum #name {
+                #(#variants),*
+            }
+
+            #[automatically_derived]
+            impl #name {
+                /// The PE header timestamp of this version's executable.
+                pub const fn pe_timestamp(self) -> u32 {
+                    match self {
+                        #(#to_timestamp),*
+                    }
+                }
+
+                pub const fn from_pe_timestamp(timestamp: u32) -> Option<Self> {
+                    match timestamp {
+                        #(#from_timestamp,)*
+                        _ => None,
+                    }
+                }
+
+                /// Determine which version of the game an executable file is.
+                pub fn detect<P: AsRef<std::path::Path>>(executable: P) -> std::io::Result<Self> {
+                    use crate::types::Game;
+                    let timestamp = crate::process::pe_timestamp(executable)?;
+                    Self::from_pe_timestamp(timestamp).ok_or_else(|| {
+                        crate::memory::UnknownGameVersion::new(#game::GAME_ID, timestamp).into()
+                    })
+                }
+            }
+        })
+    }
+
     fn define_snapshot_struct(&self) -> Option<TokenStream> {
         let field_defs = self
             .fields
@@ -344,14 +879,17 @@ impl MemoryDef {
             .fields
             .iter()
             .map(|(attrs, field)| field.access_field_def(attrs));
+        let version_type = self.versions.as_ref().map(|(_, name, _)| name);
         let field_create = self
             .fields
             .iter()
-            .map(|(_, field)| field.access_create_expr());
+            .map(|(_, field)| field.access_create_expr(version_type));
         let field_access = self
             .fields
             .iter()
             .map(|(attrs, field)| field.access_fn(attrs, game));
+        let field_record = self.fields.iter().map(|(_, field)| field.record_expr());
+        let fields_name = format_ident!("{}Fields", access_name);
         let (first_name, other_names) = self.process_names.split_first().unwrap();
 
         let snapshot_create = self.snapshot_name.as_ref().map(|(_, snapshot_name)| {
@@ -369,23 +907,156 @@ impl MemoryDef {
             }
         });
 
+        let version_field_init = version_type.map(|_| quote! { version, });
+        let version_param = version_type.map(|version_type| quote! { version: #version_type, });
+        let signature_names: Vec<_> = self.signatures.iter().map(|sig| &sig.name).collect();
+        let (version_field, version_detect, version_access) = match version_type {
+            Some(version_type) => (
+                Some(quote! { version: #version_type, }),
+                Some(quote! {
+                    let version = #version_type::detect(&executable)?;
+                }),
+                Some(quote! {
+                    /// The detected version of the attached game.
+                    ///
+                    /// Replayed recordings have no associated version.
+                    pub fn version(&self) -> Option<#version_type> {
+                        use crate::memory::MemorySource;
+                        match &self.0 {
+                            MemorySource::Process(fields) => Some(fields.version),
+                            #[cfg(feature = "replay")]
+                            MemorySource::Replay(_) => None,
+                        }
+                    }
+                }),
+            ),
+            None => (None, None, None),
+        };
+
+        let find_executable = (version_type.is_some() || !self.signatures.is_empty()).then(|| {
+            quote! {
+                let executable = crate::process::executable_path(proc).ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::NotFound, "could not find game executable")
+                })?;
+            }
+        });
+
+        let resolve_fn = (!self.signatures.is_empty()).then(|| {
+            let resolve_stmts = self
+                .signatures
+                .iter()
+                .map(|sig| sig.resolve_stmt(&self.fields, version_type));
+
+            quote! {
+                /// Resolve the signatures used by the given game version within the game's executable image,
+                /// then set up accessors for each field.
+                ///
+                /// Signatures that the version's layout doesn't use are skipped, so they don't need to be present.
+                fn resolve(
+                    handle: touhou_process::ProcessHandle,
+                    #version_param
+                    image: std::ops::Range<usize>,
+                ) -> std::io::Result<Self> {
+                    let arch = touhou_process::LittleEndian::<4>::new();
+                    #(#resolve_stmts)*
+
+                    Ok(Self::new(
+                        handle,
+                        #version_field_init
+                        #(#signature_names),*
+                    ))
+                }
+            }
+        });
+
+        let create_fields = if self.signatures.is_empty() {
+            quote! {
+                let fields = #fields_name::new(handle, #version_field_init);
+            }
+        } else {
+            quote! {
+                let image = executable
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .map(|name| handle.find_module(name))
+                    .transpose()?
+                    .flatten()
+                    .ok_or_else(|| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::NotFound,
+                            "could not find game executable in process memory",
+                        )
+                    })?;
+
+                let fields = #fields_name::resolve(handle, #version_field_init image)?;
+            }
+        };
+
         quote! {
-            #(#access_attrs)*
-            pub struct #access_name {
+            struct #fields_name {
+                cache: touhou_process::ReadCache,
+                #version_field
                 #(#field_defs),*
             }
 
+            impl #fields_name {
+                /// Set up accessors for each field, given the detected game version and resolved signature addresses.
+                #[allow(unused_variables)]
+                fn new(
+                    handle: touhou_process::ProcessHandle,
+                    #version_param
+                    #(#signature_names: Option<std::num::NonZeroUsize>),*
+                ) -> Self {
+                    Self {
+                        cache: touhou_process::ReadCache::new(handle),
+                        #version_field_init
+                        #(#field_create),*
+                    }
+                }
+
+                #resolve_fn
+            }
+
+            #(#access_attrs)*
+            pub struct #access_name(crate::memory::MemorySource<#fields_name>);
+
             #[automatically_derived]
             impl ProcessAttached for #access_name {
-                fn from_pid(pid: u32) -> std::io::Result<Self> {
-                    touhou_process::Pid::from(pid).try_into_process_handle().map(|handle| Self {
-                        #(#field_create),*
-                    })
+                fn from_process(proc: &sysinfo::Process) -> std::io::Result<Self> {
+                    use crate::memory::MemorySource;
+                    use sysinfo::{PidExt, ProcessExt};
+
+                    #find_executable
+                    #version_detect
+                    let handle = touhou_process::Pid::from(proc.pid().as_u32()).try_into_process_handle()?;
+                    #create_fields
+
+                    Ok(Self(MemorySource::Process(fields)))
+                }
+
+                fn clear_cache(&mut self) {
+                    use crate::memory::MemorySource;
+                    match &mut self.0 {
+                        MemorySource::Process(fields) => fields.cache.clear(),
+                        #[cfg(feature = "replay")]
+                        MemorySource::Replay(_) => {}
+                    }
                 }
 
                 fn is_attachable_process(proc: &sysinfo::Process) -> bool {
-                    let exe = <sysinfo::Process as sysinfo::ProcessExt>::exe(proc);
-                    exe.file_stem().and_then(|s| s.to_str()).is_some_and(|name| name.starts_with(#first_name) #(|| name.starts_with(#other_names))*)
+                    crate::process::is_game_process(proc, &[#first_name #(, #other_names)*])
+                }
+
+                #[cfg(feature = "replay")]
+                fn record_frame(&self) -> crate::memory::replay::MemoryFrame {
+                    let mut frame = crate::memory::replay::MemoryFrame::new();
+                    #(#field_record)*
+                    frame
+                }
+
+                #[cfg(feature = "replay")]
+                fn from_frame(frame: crate::memory::replay::MemoryFrame) -> Self {
+                    Self(crate::memory::MemorySource::Replay(frame))
                 }
             }
 
@@ -393,6 +1064,8 @@ impl MemoryDef {
             impl #access_name {
                 #(#field_access)*
 
+                #version_access
+
                 #snapshot_create
             }
         }
@@ -411,8 +1084,9 @@ impl MemoryDef {
 
         let snapshot_access = self.snapshot_name.as_ref().map(|(_, snapshot_name)| {
             quote! {
+                /// Read every field at once from the current state of the attached process.
                 pub fn read_snapshot(&mut self) -> Result<#snapshot_name, crate::memory::MemoryReadError<#game>> {
-                    self.0.access().and_then(|inner| inner.read_snapshot())
+                    self.0.access_uncached().and_then(|inner| inner.read_snapshot())
                 }
             }
         });
@@ -441,6 +1115,30 @@ impl MemoryDef {
                     Attached::from_pid(pid).map(Self).map_err(MemoryReadError::from)
                 }
 
+                /// Replay a sequence of recorded memory frames.
+                #[cfg(feature = "replay")]
+                pub fn from_frames<I: IntoIterator<Item = crate::memory::replay::MemoryFrame>>(frames: I) -> Self {
+                    Self(Attached::from_frames(frames))
+                }
+
+                /// Replay a memory recording file.
+                #[cfg(feature = "replay")]
+                pub fn from_recording<P: AsRef<std::path::Path>>(path: P) -> Result<Self, crate::memory::MemoryReadError<#game>> {
+                    crate::memory::replay::read_frames_from_file(path).map(Self::from_frames).map_err(crate::memory::MemoryReadError::from)
+                }
+
+                /// Start recording memory values to a file each time this process's memory is advanced to a new poll.
+                #[cfg(feature = "replay")]
+                pub fn start_recording<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), crate::memory::MemoryReadError<#game>> {
+                    self.0.start_recording(path).map_err(crate::memory::MemoryReadError::from)
+                }
+
+                /// Stop recording memory values.
+                #[cfg(feature = "replay")]
+                pub fn finish_recording(&mut self) -> Result<(), crate::memory::MemoryReadError<#game>> {
+                    self.0.finish_recording().map_err(crate::memory::MemoryReadError::from)
+                }
+
                 pub fn is_running(&mut self) -> bool {
                     self.0.is_running()
                 }
@@ -449,21 +1147,39 @@ impl MemoryDef {
                     self.0.pid()
                 }
 
+                /// Attach a new instance to the same process, or copy this instance's replay state.
+                pub fn try_clone(&self) -> Result<Self, crate::memory::MemoryReadError<#game>> {
+                    use crate::memory::MemoryReadError;
+                    self.0.try_clone().map(Self).map_err(MemoryReadError::from)
+                }
+
+                /// Move on to a new poll of this process's memory.
+                ///
+                /// When replaying a recording, this steps to the next recorded frame.
+                pub fn advance(&mut self) -> bool {
+                    self.0.advance()
+                }
+
                 pub fn access(&mut self) -> Option<&#access_name> {
                     self.0.access()
                 }
 
+                /// Get the path to the attached process's executable.
+                pub fn executable_path(&self) -> Option<std::path::PathBuf> {
+                    self.0.executable_path()
+                }
+
+                /// Get the locations of the score file, replays, and configuration used by the attached process.
+                pub fn game_paths(&self) -> Option<crate::process::GamePaths> {
+                    use crate::types::Game;
+                    self.0.game_paths(#game::GAME_ID)
+                }
+
                 #(#field_access)*
 
                 #snapshot_access
             }
 
-            impl Clone for #name {
-                fn clone(&self) -> Self {
-                    Self::from_pid(self.pid()).unwrap()
-                }
-            }
-
             impl crate::memory::GameMemory<#game> for #name {
                 type MemoryAccess = #access_name;
 
@@ -475,15 +1191,31 @@ impl MemoryDef {
                     self.0.pid()
                 }
 
+                fn advance(&mut self) -> bool {
+                    self.0.advance()
+                }
+
                 fn access(&mut self) -> Option<&#access_name> {
                     self.0.access()
                 }
+
+                fn executable_path(&self) -> Option<std::path::PathBuf> {
+                    self.0.executable_path()
+                }
+
+                fn game_paths(&self) -> Option<crate::process::GamePaths> {
+                    use crate::types::Game;
+                    self.0.game_paths(#game::GAME_ID)
+                }
             }
         }
     }
 
     pub fn into_defines(self) -> TokenStream {
         let mut ret = self.define_access_struct();
+        if let Some(tokens) = self.define_version_enum() {
+            ret.extend(tokens)
+        }
         if let Some(tokens) = self.define_snapshot_struct() {
             ret.extend(tokens)
         }
@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use crate::{private, ProcessHandle, ReadMemory};
+
+/// The size and alignment of the blocks of memory that are read and cached at once.
+///
+/// This matches the page size on every platform the games run on, so a block is either entirely
+/// readable or not at all.
+const BLOCK_SIZE: usize = 0x1000;
+
+/// Caches blocks of another process's memory, so that repeated reads from nearby addresses
+/// only need to read from the process once.
+///
+/// This is intended to be used over a single poll of a game's state: pointers that are shared
+/// between several pointer chains only get read once, and fields that are close together in memory
+/// get read in bulk. Call [`clear`](ReadCache::clear) before each poll so that changes made by the
+/// other process become visible.
+#[derive(Debug)]
+pub struct ReadCache {
+    handle: ProcessHandle,
+    blocks: Mutex<HashMap<usize, Option<Box<[u8]>>>>,
+    reads: AtomicUsize,
+}
+
+impl ReadCache {
+    pub fn new(handle: ProcessHandle) -> Self {
+        Self {
+            handle,
+            blocks: Mutex::new(HashMap::new()),
+            reads: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn handle(&self) -> ProcessHandle {
+        self.handle
+    }
+
+    /// Discard all cached memory, so that later reads see the current state of the process.
+    pub fn clear(&mut self) {
+        self.blocks
+            .get_mut()
+            .expect("could not lock read cache")
+            .clear();
+    }
+
+    /// The number of times this cache has read from the other process.
+    pub fn read_count(&self) -> usize {
+        self.reads.load(Ordering::Relaxed)
+    }
+
+    fn read_uncached(&self, addr: NonZeroUsize, dest: &mut [u8]) -> std::io::Result<()> {
+        self.reads.fetch_add(1, Ordering::Relaxed);
+        self.handle.read_bytes(addr, dest)
+    }
+
+    fn read_block(&self, start: usize) -> Option<Box<[u8]>> {
+        let addr = NonZeroUsize::new(start)?;
+        let mut block = vec![0; BLOCK_SIZE].into_boxed_slice();
+        self.read_uncached(addr, &mut block).ok().map(|_| block)
+    }
+}
+
+impl private::Sealed for ReadCache {}
+
+impl ReadMemory for ReadCache {
+    fn read_bytes(&self, addr: NonZeroUsize, dest: &mut [u8]) -> std::io::Result<()> {
+        if addr.get().checked_add(dest.len()).is_none() {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidInput,
+                "read extends past end of address space",
+            ));
+        }
+
+        let mut blocks = self.blocks.lock().expect("could not lock read cache");
+        let mut pos = addr.get();
+        let mut written = 0;
+
+        while written < dest.len() {
+            let start = pos & !(BLOCK_SIZE - 1);
+            let block = blocks
+                .entry(start)
+                .or_insert_with(|| self.read_block(start));
+
+            if let Some(block) = block {
+                let offset = pos - start;
+                let len = (BLOCK_SIZE - offset).min(dest.len() - written);
+                dest[written..(written + len)].copy_from_slice(&block[offset..(offset + len)]);
+                written += len;
+                pos += len;
+            } else {
+                // let the process report the actual error for unreadable memory
+                drop(blocks);
+                return self.read_uncached(addr, dest);
+            }
+        }
+
+        Ok(())
+    }
+}
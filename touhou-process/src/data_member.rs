@@ -3,7 +3,7 @@ use std::num::NonZeroUsize;
 
 use bytemuck::{AnyBitPattern, CheckedBitPattern};
 
-use crate::{Architecture, ProcessHandle};
+use crate::{get_offset, Architecture, ProcessHandle, ReadMemory};
 
 #[derive(Debug, Copy, Clone)]
 pub struct FixedData<T: ?Sized + 'static, A: Architecture> {
@@ -23,10 +23,21 @@ impl<T: ?Sized + 'static, A: Architecture> FixedData<T, A> {
         }
     }
 
+    /// The pointer chain followed to find this item.
+    pub fn offsets(&self) -> &[usize] {
+        self.offsets
+    }
+
     pub fn get_address(&self) -> std::io::Result<NonZeroUsize> {
-        self.handle
-            .get_offset(&self.arch, self.offsets)
-            .map(Option::unwrap)
+        self.get_address_with(&self.handle)
+    }
+
+    /// Follow this item's pointer chain using the given reader (for example, a [`ReadCache`](crate::ReadCache)).
+    pub fn get_address_with<R: ReadMemory + ?Sized>(
+        &self,
+        reader: &R,
+    ) -> std::io::Result<NonZeroUsize> {
+        get_offset(reader, &self.arch, self.offsets).map(Option::unwrap)
     }
 
     /// Unsafely read this item into a mutable reference.
@@ -49,8 +60,15 @@ impl<T: ?Sized + 'static, A: Architecture + Default> FixedData<T, A> {
 
 impl<T: CheckedBitPattern, A: Architecture> FixedData<T, A> {
     pub fn read_checked(&self) -> std::io::Result<Option<T>> {
-        self.get_address()
-            .and_then(|addr| self.handle.read_checked(addr))
+        self.read_checked_with(&self.handle)
+    }
+
+    pub fn read_checked_with<R: ReadMemory + ?Sized>(
+        &self,
+        reader: &R,
+    ) -> std::io::Result<Option<T>> {
+        self.get_address_with(reader)
+            .and_then(|addr| reader.read_checked(addr))
     }
 }
 
@@ -66,7 +84,12 @@ impl<T: AnyBitPattern, A: Architecture> FixedData<T, A> {
     }
 
     pub fn read(&self) -> std::io::Result<T> {
-        self.get_address().and_then(|addr| self.handle.read(addr))
+        self.read_with(&self.handle)
+    }
+
+    pub fn read_with<R: ReadMemory + ?Sized>(&self, reader: &R) -> std::io::Result<T> {
+        self.get_address_with(reader)
+            .and_then(|addr| reader.read(addr))
     }
 }
 
@@ -101,8 +124,21 @@ impl<T: ?Sized + 'static, A: Architecture> DataItem<T, A> {
         }
     }
 
+    /// The pointer chain followed to find this item.
+    pub fn offsets(&self) -> &[usize] {
+        &self.offsets
+    }
+
     pub fn get_address(&self) -> std::io::Result<Option<NonZeroUsize>> {
-        self.handle.get_offset(&self.arch, &self.offsets)
+        self.get_address_with(&self.handle)
+    }
+
+    /// Follow this item's pointer chain using the given reader (for example, a [`ReadCache`](crate::ReadCache)).
+    pub fn get_address_with<R: ReadMemory + ?Sized>(
+        &self,
+        reader: &R,
+    ) -> std::io::Result<Option<NonZeroUsize>> {
+        get_offset(reader, &self.arch, &self.offsets)
     }
 
     /// Unsafely read this item into a mutable reference.
@@ -133,8 +169,15 @@ impl<T: ?Sized + 'static, A: Architecture + Default> DataItem<T, A> {
 
 impl<T: CheckedBitPattern, A: Architecture> DataItem<T, A> {
     pub fn read_checked(&self) -> std::io::Result<Option<T>> {
-        self.get_address()?
-            .and_then(|addr| self.handle.read_checked(addr).transpose())
+        self.read_checked_with(&self.handle)
+    }
+
+    pub fn read_checked_with<R: ReadMemory + ?Sized>(
+        &self,
+        reader: &R,
+    ) -> std::io::Result<Option<T>> {
+        self.get_address_with(reader)?
+            .and_then(|addr| reader.read_checked(addr).transpose())
             .transpose()
     }
 }
@@ -157,8 +200,12 @@ impl<T: AnyBitPattern, A: Architecture> DataItem<T, A> {
     }
 
     pub fn read(&self) -> std::io::Result<Option<T>> {
-        self.get_address()?
-            .map(|addr| self.handle.read(addr))
+        self.read_with(&self.handle)
+    }
+
+    pub fn read_with<R: ReadMemory + ?Sized>(&self, reader: &R) -> std::io::Result<Option<T>> {
+        self.get_address_with(reader)?
+            .map(|addr| reader.read(addr))
             .transpose()
     }
 }
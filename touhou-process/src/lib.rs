@@ -5,15 +5,25 @@ use std::num::NonZeroUsize;
 
 use bytemuck::{AnyBitPattern, CheckedBitPattern};
 
+mod cache;
 mod data_member;
+mod signature;
 
+#[doc(inline)]
+pub use cache::ReadCache;
 #[doc(inline)]
 pub use data_member::{DataItem, FixedData};
+#[doc(inline)]
+pub use signature::{ParseSignatureError, Signature};
 
 #[cfg(windows)]
 #[path = "windows.rs"]
 mod platform;
 
+#[cfg(target_os = "linux")]
+#[path = "linux.rs"]
+mod platform;
+
 mod private {
     pub trait Sealed {}
 }
@@ -62,7 +72,14 @@ impl TryFrom<std::process::Child> for ProcessHandle {
     }
 }
 
-impl ProcessHandle {
+/// Something that can read memory from another process's address space.
+///
+/// This is implemented both by [`ProcessHandle`], which reads directly from the other process,
+/// and [`ReadCache`], which reads through a cache of memory that has already been read.
+pub trait ReadMemory: private::Sealed {
+    /// Read bytes starting at `addr` from another process's address space.
+    fn read_bytes(&self, addr: NonZeroUsize, dest: &mut [u8]) -> std::io::Result<()>;
+
     /// Unsafely read memory at `addr` within another process's address space.
     ///
     /// # Safety
@@ -70,17 +87,17 @@ impl ProcessHandle {
     /// This is effectively a raw pointer read but across processes, and as such
     /// many of the same rules need to be followed. In particular, the caller
     /// must ensure that the read value is valid for type `T`.
-    unsafe fn read_unsafe<T: ?Sized>(
-        &self,
-        addr: NonZeroUsize,
-        dest: &mut T,
-    ) -> std::io::Result<()> {
-        platform::read_unsafe(self.0, addr, dest)
+    unsafe fn read_unsafe<T: ?Sized>(&self, addr: NonZeroUsize, dest: &mut T) -> std::io::Result<()> {
+        let sz = std::mem::size_of_val(dest);
+        self.read_bytes(
+            addr,
+            std::slice::from_raw_parts_mut((dest as *mut T).cast::<u8>(), sz),
+        )
     }
 
     /// Safely read memory from another process's address space into a mutable reference.
     ///
-    /// This is like [`read_unsafe`], but can be called safely because `T` is bound by [`AnyBitPattern`].
+    /// This is like [`read_unsafe`](ReadMemory::read_unsafe), but can be called safely because `T` is bound by [`AnyBitPattern`].
     fn read_into<T: AnyBitPattern>(&self, addr: NonZeroUsize, dest: &mut T) -> std::io::Result<()> {
         // SAFETY: The trait bound ensures that any bit pattern is valid for T.
         unsafe { self.read_unsafe(addr, dest) }
@@ -88,7 +105,7 @@ impl ProcessHandle {
 
     /// Safely read multiple items from another process's address space.
     ///
-    /// Much like [`read_into`], this can be called safely thanks to the [`AnyBitPattern`] bound on T.
+    /// Much like [`read_into`](ReadMemory::read_into), this can be called safely thanks to the [`AnyBitPattern`] bound on T.
     fn read_into_slice<T: AnyBitPattern>(
         &self,
         addr: NonZeroUsize,
@@ -100,7 +117,7 @@ impl ProcessHandle {
 
     /// Safely read a value from another process's address space.
     ///
-    /// This is a convenience method around [`read_into`] that reads into a temporary value,
+    /// This is a convenience method around [`read_into`](ReadMemory::read_into) that reads into a temporary value,
     /// then returns the newly read value.
     fn read<T: AnyBitPattern>(&self, addr: NonZeroUsize) -> std::io::Result<T> {
         let mut ret = T::zeroed();
@@ -124,40 +141,53 @@ impl ProcessHandle {
             Ok(None)
         }
     }
+}
 
-    /// Get an actual memory location by following a list of offsets.
-    fn get_offset<A: Architecture>(
-        &self,
-        arch: &A,
-        offsets: impl IntoIterator<Item = impl Borrow<usize>>,
-    ) -> std::io::Result<Option<NonZeroUsize>> {
-        let mut offsets = offsets.into_iter().map(|x| *x.borrow());
-        let mut address = match offsets.next().map(NonZeroUsize::new) {
-            Some(Some(address)) => address,
-            Some(None) => {
-                return Err(std::io::Error::new(
-                    ErrorKind::InvalidData,
-                    "attempted to get offset from null pointer",
-                ));
-            }
-            None => return Ok(None),
-        };
-
-        for offset in offsets {
-            address = arch
-                .read_pointer(self, address)?
-                .ok_or_else(|| {
-                    std::io::Error::new(ErrorKind::InvalidData, "encountered null pointer")
-                })?
-                .checked_add(offset)
-                .ok_or_else(|| {
-                    std::io::Error::new(ErrorKind::InvalidData, "address calculation overflowed")
-                })?;
+impl private::Sealed for ProcessHandle {}
+
+impl ReadMemory for ProcessHandle {
+    fn read_bytes(&self, addr: NonZeroUsize, dest: &mut [u8]) -> std::io::Result<()> {
+        // SAFETY: any bit pattern is valid for a byte slice.
+        unsafe { platform::read_unsafe(self.0, addr, dest) }
+    }
+
+    unsafe fn read_unsafe<T: ?Sized>(&self, addr: NonZeroUsize, dest: &mut T) -> std::io::Result<()> {
+        platform::read_unsafe(self.0, addr, dest)
+    }
+}
+
+/// Get an actual memory location by following a list of offsets.
+fn get_offset<R: ReadMemory + ?Sized, A: Architecture>(
+    reader: &R,
+    arch: &A,
+    offsets: impl IntoIterator<Item = impl Borrow<usize>>,
+) -> std::io::Result<Option<NonZeroUsize>> {
+    let mut offsets = offsets.into_iter().map(|x| *x.borrow());
+    let mut address = match offsets.next().map(NonZeroUsize::new) {
+        Some(Some(address)) => address,
+        Some(None) => {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                "attempted to get offset from null pointer",
+            ));
         }
+        None => return Ok(None),
+    };
 
-        Ok(Some(address))
+    for offset in offsets {
+        address = arch
+            .read_pointer(reader, address)?
+            .ok_or_else(|| std::io::Error::new(ErrorKind::InvalidData, "encountered null pointer"))?
+            .checked_add(offset)
+            .ok_or_else(|| {
+                std::io::Error::new(ErrorKind::InvalidData, "address calculation overflowed")
+            })?;
     }
 
+    Ok(Some(address))
+}
+
+impl ProcessHandle {
     pub fn new_fixed_item<T: ?Sized, A: Architecture + Default>(
         &self,
         offsets: &'static [usize],
@@ -213,8 +243,8 @@ macro_rules! impl_architectures {
 
         impl Architecture for LittleEndian<1> {
             #[inline(always)]
-            fn read_pointer(&self, handle: &ProcessHandle, addr: NonZeroUsize) -> std::io::Result<Option<NonZeroUsize>> {
-                handle.read::<u8>(addr).map(|x| x as usize).map(NonZeroUsize::new)
+            fn read_pointer<R: ReadMemory + ?Sized>(&self, reader: &R, addr: NonZeroUsize) -> std::io::Result<Option<NonZeroUsize>> {
+                reader.read::<u8>(addr).map(|x| x as usize).map(NonZeroUsize::new)
             }
         }
 
@@ -232,8 +262,8 @@ macro_rules! impl_architectures {
 
         impl Architecture for BigEndian<1> {
             #[inline(always)]
-            fn read_pointer(&self, handle: &ProcessHandle, addr: NonZeroUsize) -> std::io::Result<Option<NonZeroUsize>> {
-                handle.read::<u8>(addr).map(|x| x as usize).map(NonZeroUsize::new)
+            fn read_pointer<R: ReadMemory + ?Sized>(&self, reader: &R, addr: NonZeroUsize) -> std::io::Result<Option<NonZeroUsize>> {
+                reader.read::<u8>(addr).map(|x| x as usize).map(NonZeroUsize::new)
             }
         }
 
@@ -251,8 +281,8 @@ macro_rules! impl_architectures {
 
         impl Architecture for NativeEndian<1> {
             #[inline(always)]
-            fn read_pointer(&self, handle: &ProcessHandle, addr: NonZeroUsize) -> std::io::Result<Option<NonZeroUsize>> {
-                handle.read::<u8>(addr).map(|x| x as usize).map(NonZeroUsize::new)
+            fn read_pointer<R: ReadMemory + ?Sized>(&self, reader: &R, addr: NonZeroUsize) -> std::io::Result<Option<NonZeroUsize>> {
+                reader.read::<u8>(addr).map(|x| x as usize).map(NonZeroUsize::new)
             }
         }
 
@@ -271,8 +301,8 @@ macro_rules! impl_architectures {
 
             impl Architecture for LittleEndian<$size> {
                 #[inline(always)]
-                fn read_pointer(&self, handle: &ProcessHandle, addr: NonZeroUsize) -> std::io::Result<Option<NonZeroUsize>> {
-                    handle.read::<[u8; $size]>(addr).map(<$temp_type>::from_le_bytes).map(|x| x as usize).map(NonZeroUsize::new)
+                fn read_pointer<R: ReadMemory + ?Sized>(&self, reader: &R, addr: NonZeroUsize) -> std::io::Result<Option<NonZeroUsize>> {
+                    reader.read::<[u8; $size]>(addr).map(<$temp_type>::from_le_bytes).map(|x| x as usize).map(NonZeroUsize::new)
                 }
             }
 
@@ -290,8 +320,8 @@ macro_rules! impl_architectures {
 
             impl Architecture for BigEndian<$size> {
                 #[inline(always)]
-                fn read_pointer(&self, handle: &ProcessHandle, addr: NonZeroUsize) -> std::io::Result<Option<NonZeroUsize>> {
-                    handle.read::<[u8; $size]>(addr).map(<$temp_type>::from_be_bytes).map(|x| x as usize).map(NonZeroUsize::new)
+                fn read_pointer<R: ReadMemory + ?Sized>(&self, reader: &R, addr: NonZeroUsize) -> std::io::Result<Option<NonZeroUsize>> {
+                    reader.read::<[u8; $size]>(addr).map(<$temp_type>::from_be_bytes).map(|x| x as usize).map(NonZeroUsize::new)
                 }
             }
 
@@ -309,8 +339,8 @@ macro_rules! impl_architectures {
 
             impl Architecture for NativeEndian<$size> {
                 #[inline(always)]
-                fn read_pointer(&self, handle: &ProcessHandle, addr: NonZeroUsize) -> std::io::Result<Option<NonZeroUsize>> {
-                    handle.read::<[u8; $size]>(addr).map(<$temp_type>::from_ne_bytes).map(|x| x as usize).map(NonZeroUsize::new)
+                fn read_pointer<R: ReadMemory + ?Sized>(&self, reader: &R, addr: NonZeroUsize) -> std::io::Result<Option<NonZeroUsize>> {
+                    reader.read::<[u8; $size]>(addr).map(<$temp_type>::from_ne_bytes).map(|x| x as usize).map(NonZeroUsize::new)
                 }
             }
         )*
@@ -336,9 +366,9 @@ pub struct NativeEndian<const WIDTH: usize>(PhantomData<[u8; WIDTH]>);
 impl<const WIDTH: usize> private::Sealed for NativeEndian<WIDTH> {}
 
 pub trait Architecture: private::Sealed {
-    fn read_pointer(
+    fn read_pointer<R: ReadMemory + ?Sized>(
         &self,
-        handle: &ProcessHandle,
+        reader: &R,
         addr: NonZeroUsize,
     ) -> std::io::Result<Option<NonZeroUsize>>;
 }
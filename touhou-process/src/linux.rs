@@ -0,0 +1,277 @@
+use core::ffi::c_void;
+use std::fs::File;
+use std::io::ErrorKind;
+use std::num::NonZeroUsize;
+use std::ops::Range;
+use std::os::unix::fs::FileExt;
+use std::path::PathBuf;
+
+use super::ProcessHandle as WrappedHandle;
+
+pub(crate) type Pid = libc::pid_t;
+
+/// Linux doesn't have a separate concept of process handles, so we just hang on to the PID.
+pub(crate) type ProcessHandle = libc::pid_t;
+
+fn proc_path(pid: Pid) -> PathBuf {
+    PathBuf::from(format!("/proc/{}", pid))
+}
+
+pub(crate) fn try_into_process_handle(pid: Pid) -> std::io::Result<ProcessHandle> {
+    if proc_path(pid).is_dir() {
+        Ok(pid)
+    } else {
+        Err(std::io::Error::new(
+            ErrorKind::NotFound,
+            format!("no process with PID {}", pid),
+        ))
+    }
+}
+
+pub(crate) fn pid_from_u32(value: u32) -> Pid {
+    value as Pid
+}
+
+pub(crate) fn pid_to_u32(value: Pid) -> u32 {
+    value as u32
+}
+
+/// Fallback for kernels without `process_vm_readv` support: read through `/proc/<pid>/mem` instead.
+fn read_proc_mem(
+    handle: ProcessHandle,
+    addr: NonZeroUsize,
+    dest: &mut [u8],
+) -> std::io::Result<()> {
+    File::open(proc_path(handle).join("mem"))?.read_exact_at(dest, addr.get() as u64)
+}
+
+pub(crate) unsafe fn read_unsafe<T: ?Sized>(
+    handle: ProcessHandle,
+    addr: NonZeroUsize,
+    dest: &mut T,
+) -> std::io::Result<()> {
+    let sz = std::mem::size_of_val(dest);
+    let dest = (dest as *mut T).cast::<c_void>();
+
+    if sz == 0 {
+        return Ok(());
+    }
+
+    let local = libc::iovec {
+        iov_base: dest,
+        iov_len: sz,
+    };
+
+    let remote = libc::iovec {
+        iov_base: addr.get() as *mut c_void,
+        iov_len: sz,
+    };
+
+    let n = libc::process_vm_readv(handle, &local, 1, &remote, 1, 0);
+    if n < 0 {
+        let err = std::io::Error::last_os_error();
+        if err.raw_os_error() == Some(libc::ENOSYS) {
+            read_proc_mem(
+                handle,
+                addr,
+                std::slice::from_raw_parts_mut(dest.cast::<u8>(), sz),
+            )
+        } else {
+            Err(err)
+        }
+    } else if (n as usize) < sz {
+        Err(std::io::Error::new(
+            ErrorKind::UnexpectedEof,
+            format!("only read {} of {} bytes from process {}", n, sz, handle),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Parse a line from `/proc/<pid>/maps`, returning the mapped address range and path (if any).
+fn parse_mapping(line: &str) -> Option<(Range<usize>, &str)> {
+    let mut parts = line.splitn(6, ' ');
+    let (start, end) = parts.next()?.split_once('-')?;
+    let path = parts.nth(4).map(str::trim_start).unwrap_or("");
+
+    Some((
+        usize::from_str_radix(start, 16).ok()?..usize::from_str_radix(end, 16).ok()?,
+        path,
+    ))
+}
+
+/// Find a module by looking for file-backed mappings with a matching file name.
+///
+/// This also works for Windows executables running under Wine, which maps PE images from their files.
+pub(crate) fn find_module(
+    handle: ProcessHandle,
+    name: &str,
+) -> std::io::Result<Option<Range<usize>>> {
+    let maps = std::fs::read_to_string(proc_path(handle).join("maps"))?;
+
+    Ok(maps
+        .lines()
+        .filter_map(parse_mapping)
+        .filter(|(_, path)| {
+            path.rsplit(['/', '\\'])
+                .next()
+                .is_some_and(|file_name| file_name.eq_ignore_ascii_case(name))
+        })
+        .map(|(range, _)| range)
+        .reduce(|a, b| a.start.min(b.start)..a.end.max(b.end)))
+}
+
+impl WrappedHandle {
+    pub fn from_child(child: std::process::Child) -> std::io::Result<Self> {
+        Ok(Self(pid_from_u32(child.id())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use crate::{LittleEndian, Pid, ProcessHandle, ReadCache, ReadMemory, Signature};
+
+    #[repr(C)]
+    struct TestData {
+        header: u32,
+        values: [u16; 4],
+        next: &'static u64,
+    }
+
+    static TARGET: u64 = 0x0123_4567_89AB_CDEF;
+
+    /// Stands in for an instruction that loads a global address, for signature scanning tests.
+    static CODE: [u8; 12] = {
+        let addr = (0x0123_4567usize).to_le_bytes();
+        [
+            0xF3, 0x5A, 0xC3, 0x91, addr[0], addr[1], addr[2], addr[3], 0x2E, 0xB7, 0x64, 0x1D,
+        ]
+    };
+
+    static TEST_DATA: TestData = TestData {
+        header: 0xDEAD_BEEF,
+        values: [1, 2, 3, 4],
+        next: &TARGET,
+    };
+
+    /// Runs a test against a forked copy of this process, which shares the same address space layout.
+    fn with_child<F: FnOnce(ProcessHandle) + std::panic::UnwindSafe>(f: F) {
+        // SAFETY: the child only ever calls async-signal-safe functions before it is killed.
+        let pid = unsafe { libc::fork() };
+        assert!(pid >= 0, "fork failed: {}", std::io::Error::last_os_error());
+
+        if pid == 0 {
+            loop {
+                unsafe { libc::pause() };
+            }
+        }
+
+        let result = std::panic::catch_unwind(|| {
+            f(Pid::from(pid as u32).try_into_process_handle().unwrap())
+        });
+
+        unsafe {
+            libc::kill(pid, libc::SIGKILL);
+            libc::waitpid(pid, std::ptr::null_mut(), 0);
+        }
+
+        if let Err(panic) = result {
+            std::panic::resume_unwind(panic);
+        }
+    }
+
+    #[test]
+    fn test_read_child_memory() {
+        with_child(|handle| {
+            let addr = NonZeroUsize::new(&TEST_DATA as *const TestData as usize).unwrap();
+            assert_eq!(handle.read::<u32>(addr).unwrap(), 0xDEAD_BEEF);
+
+            let mut values = [0u16; 4];
+            handle
+                .read_into_slice(addr.checked_add(4).unwrap(), &mut values)
+                .unwrap();
+            assert_eq!(values, [1, 2, 3, 4]);
+        });
+    }
+
+    #[test]
+    fn test_follow_pointer_chain() {
+        with_child(|handle| {
+            let offsets: &'static [usize] =
+                Box::leak(Box::new([std::ptr::addr_of!(TEST_DATA.next) as usize, 0]));
+
+            let item = handle.new_fixed_item::<u64, LittleEndian<8>>(offsets);
+            assert_eq!(item.read().unwrap(), TARGET);
+        });
+    }
+
+    #[test]
+    fn test_cached_reads() {
+        with_child(|handle| {
+            let base = std::ptr::addr_of!(TEST_DATA) as usize;
+            let header: &'static [usize] = Box::leak(Box::new([base]));
+            let target: &'static [usize] =
+                Box::leak(Box::new([std::ptr::addr_of!(TEST_DATA.next) as usize, 0]));
+
+            let header = handle.new_fixed_item::<u32, LittleEndian<8>>(header);
+            let target = handle.new_fixed_item::<u64, LittleEndian<8>>(target);
+            let mut cache = ReadCache::new(handle);
+
+            for _ in 0..2 {
+                assert_eq!(header.read_with(&cache).unwrap(), 0xDEAD_BEEF);
+                assert_eq!(target.read_with(&cache).unwrap(), TARGET);
+
+                let mut values = [0u16; 4];
+                cache
+                    .read_into_slice(NonZeroUsize::new(base + 4).unwrap(), &mut values)
+                    .unwrap();
+                assert_eq!(values, [1, 2, 3, 4]);
+            }
+
+            // one read for the page holding the test data, and at most two more for the target
+            let reads = cache.read_count();
+            assert!(reads <= 3, "made {} reads", reads);
+
+            cache.clear();
+            assert_eq!(header.read_with(&cache).unwrap(), 0xDEAD_BEEF);
+            assert_eq!(cache.read_count(), reads + 1);
+        });
+    }
+
+    #[test]
+    fn test_scan_module() {
+        let exe = std::env::current_exe().unwrap();
+        let name = exe.file_name().unwrap().to_str().unwrap();
+
+        with_child(|handle| {
+            let module = handle.find_module(name).unwrap().unwrap();
+            let code = std::hint::black_box(&CODE) as *const _ as usize;
+            assert!(module.contains(&code));
+
+            let signature: Signature = "F3 5A C3 91 ?? ?? ?? ?? 2E B7 64 1D".parse().unwrap();
+            assert_eq!(
+                handle.scan(&signature, module.clone()).unwrap(),
+                NonZeroUsize::new(code)
+            );
+            assert_eq!(
+                handle
+                    .resolve_signature(&LittleEndian::<4>::new(), &signature, module, 4)
+                    .unwrap()
+                    .get(),
+                0x0123_4567
+            );
+        });
+    }
+
+    #[test]
+    fn test_missing_process() {
+        assert!(
+            Pid::from(i32::MAX as u32)
+                .try_into_process_handle()
+                .is_err()
+        );
+    }
+}
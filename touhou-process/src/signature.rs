@@ -0,0 +1,212 @@
+use std::fmt::Display;
+use std::io::ErrorKind;
+use std::num::NonZeroUsize;
+use std::ops::Range;
+use std::str::FromStr;
+
+use crate::{Architecture, ProcessHandle, ReadMemory};
+
+/// How much memory to read at a time when scanning a process's address space.
+const SCAN_CHUNK_SIZE: usize = 0x10000;
+
+/// A byte pattern to search for in memory, where some bytes may be wildcards.
+///
+/// Signatures are usually written as whitespace-separated hex bytes, with `??` marking bytes
+/// that can take any value (for example, `"8B 0D ?? ?? ?? ?? 85 C9"`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Signature(Vec<Option<u8>>);
+
+impl Signature {
+    /// Create a signature from a list of bytes, where `None` matches any byte.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pattern` is empty.
+    pub fn new(pattern: Vec<Option<u8>>) -> Self {
+        assert!(!pattern.is_empty(), "signature patterns cannot be empty");
+        Self(pattern)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn matches_at(&self, data: &[u8]) -> bool {
+        self.0
+            .iter()
+            .zip(data)
+            .all(|(pattern, byte)| pattern.is_none_or(|pattern| pattern == *byte))
+    }
+
+    /// Find the offset of the first match for this signature within a buffer.
+    pub fn find(&self, haystack: &[u8]) -> Option<usize> {
+        haystack
+            .windows(self.0.len())
+            .position(|window| self.matches_at(window))
+    }
+}
+
+impl FromStr for Signature {
+    type Err = ParseSignatureError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let pattern = s
+            .split_whitespace()
+            .map(|byte| match byte {
+                "?" | "??" => Ok(None),
+                _ if byte.len() == 2 => u8::from_str_radix(byte, 16)
+                    .map(Some)
+                    .map_err(|_| ParseSignatureError::InvalidByte(byte.to_string())),
+                _ => Err(ParseSignatureError::InvalidByte(byte.to_string())),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if pattern.is_empty() {
+            Err(ParseSignatureError::Empty)
+        } else {
+            Ok(Self(pattern))
+        }
+    }
+}
+
+impl Display for Signature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, byte) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+
+            match byte {
+                Some(byte) => write!(f, "{:02X}", byte)?,
+                None => f.write_str("??")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseSignatureError {
+    Empty,
+    InvalidByte(String),
+}
+
+impl std::error::Error for ParseSignatureError {}
+
+impl Display for ParseSignatureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => f.write_str("signature pattern is empty"),
+            Self::InvalidByte(byte) => write!(f, "invalid signature byte '{}'", byte),
+        }
+    }
+}
+
+impl From<ParseSignatureError> for std::io::Error {
+    fn from(value: ParseSignatureError) -> Self {
+        std::io::Error::new(ErrorKind::InvalidInput, value)
+    }
+}
+
+impl ProcessHandle {
+    /// Find the first address within `region` where `signature` matches another process's memory.
+    ///
+    /// The region is read in chunks, so this can be used to scan entire module images.
+    pub fn scan(
+        &self,
+        signature: &Signature,
+        region: Range<usize>,
+    ) -> std::io::Result<Option<NonZeroUsize>> {
+        let overlap = signature.len() - 1;
+        let mut buf = vec![0u8; SCAN_CHUNK_SIZE + overlap];
+        let mut start = region.start;
+
+        while start < region.end {
+            let len = (region.end - start).min(buf.len());
+            let addr = NonZeroUsize::new(start).ok_or_else(|| {
+                std::io::Error::new(ErrorKind::InvalidInput, "attempted to scan null page")
+            })?;
+
+            self.read_bytes(addr, &mut buf[..len])?;
+            if let Some(offset) = signature.find(&buf[..len]) {
+                return Ok(NonZeroUsize::new(start + offset));
+            }
+
+            if len < buf.len() {
+                break;
+            }
+
+            start += SCAN_CHUNK_SIZE;
+        }
+
+        Ok(None)
+    }
+
+    /// Resolve an address embedded in code by scanning for a signature.
+    ///
+    /// This finds the first match for `signature` within `region`, then reads the pointer located
+    /// `offset` bytes into the match (for example, the operand of a `mov` instruction that
+    /// loads a global variable).
+    pub fn resolve_signature<A: Architecture>(
+        &self,
+        arch: &A,
+        signature: &Signature,
+        region: Range<usize>,
+        offset: usize,
+    ) -> std::io::Result<NonZeroUsize> {
+        let found = self.scan(signature, region)?.ok_or_else(|| {
+            std::io::Error::new(
+                ErrorKind::NotFound,
+                format!("could not find signature {}", signature),
+            )
+        })?;
+
+        let addr = found.checked_add(offset).ok_or_else(|| {
+            std::io::Error::new(ErrorKind::InvalidData, "address calculation overflowed")
+        })?;
+
+        arch.read_pointer(self, addr)?.ok_or_else(|| {
+            std::io::Error::new(ErrorKind::InvalidData, "signature resolved to null pointer")
+        })
+    }
+
+    /// Find the range of addresses a module (i.e. an executable or DLL) is mapped to.
+    ///
+    /// Module names are compared case-insensitively, without any leading directories.
+    pub fn find_module(&self, name: &str) -> std::io::Result<Option<Range<usize>>> {
+        crate::platform::find_module(self.0, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_signature() {
+        let signature: Signature = "8B 0d ?? ? 85".parse().unwrap();
+        assert_eq!(
+            signature,
+            Signature::new(vec![Some(0x8B), Some(0x0D), None, None, Some(0x85)])
+        );
+        assert_eq!(signature.to_string(), "8B 0D ?? ?? 85");
+
+        assert_eq!("".parse::<Signature>(), Err(ParseSignatureError::Empty));
+        assert!("8B 0D1".parse::<Signature>().is_err());
+        assert!("8B GG".parse::<Signature>().is_err());
+    }
+
+    #[test]
+    fn find_in_buffer() {
+        let haystack = [0x00, 0x8B, 0x0D, 0x8B, 0x0D, 0x78, 0x62, 0x85, 0xC9];
+        let signature: Signature = "8B 0D ?? ?? 85 C9".parse().unwrap();
+
+        assert_eq!(signature.find(&haystack), Some(3));
+        assert_eq!(signature.find(&haystack[..8]), None);
+    }
+}
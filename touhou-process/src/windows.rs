@@ -1,9 +1,13 @@
 use core::ffi::c_void;
 use std::num::NonZeroUsize;
+use std::ops::Range;
 use std::os::windows::io::AsRawHandle;
 mod windows {
-    pub(crate) use windows::Win32::Foundation::HANDLE;
+    pub(crate) use windows::Win32::Foundation::{HANDLE, HINSTANCE};
     pub(crate) use windows::Win32::System::Diagnostics::Debug::ReadProcessMemory;
+    pub(crate) use windows::Win32::System::ProcessStatus::{
+        K32EnumProcessModules, K32GetModuleBaseNameW, K32GetModuleInformation, MODULEINFO,
+    };
     pub(crate) use windows::Win32::System::Threading::{
         OpenProcess, PROCESS_CREATE_THREAD, PROCESS_QUERY_INFORMATION, PROCESS_VM_OPERATION,
         PROCESS_VM_READ, PROCESS_VM_WRITE,
@@ -56,6 +60,54 @@ pub(crate) unsafe fn read_unsafe<T: ?Sized>(
     }
 }
 
+pub(crate) fn find_module(
+    handle: ProcessHandle,
+    name: &str,
+) -> std::io::Result<Option<Range<usize>>> {
+    let mut modules = [windows::HINSTANCE::default(); 1024];
+    let mut needed = 0u32;
+
+    unsafe {
+        if windows::K32EnumProcessModules(
+            handle,
+            modules.as_mut_ptr(),
+            std::mem::size_of_val(&modules) as u32,
+            &mut needed,
+        ) == false
+        {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+
+    let count = (needed as usize / std::mem::size_of::<windows::HINSTANCE>()).min(modules.len());
+    let mut name_buf = [0u16; 260];
+
+    for module in &modules[..count] {
+        let len = unsafe { windows::K32GetModuleBaseNameW(handle, *module, &mut name_buf) };
+        if !String::from_utf16_lossy(&name_buf[..len as usize]).eq_ignore_ascii_case(name) {
+            continue;
+        }
+
+        let mut info = windows::MODULEINFO::default();
+        unsafe {
+            if windows::K32GetModuleInformation(
+                handle,
+                *module,
+                &mut info,
+                std::mem::size_of::<windows::MODULEINFO>() as u32,
+            ) == false
+            {
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+
+        let base = info.lpBaseOfDll as usize;
+        return Ok(Some(base..(base + info.SizeOfImage as usize)));
+    }
+
+    Ok(None)
+}
+
 impl WrappedHandle {
     pub fn from_child(child: std::process::Child) -> std::io::Result<Self> {
         Ok(Self(windows::HANDLE(child.as_raw_handle() as isize)))
@@ -1,6 +1,5 @@
 use std::collections::HashMap;
 use std::io::{Cursor, Read};
-use std::marker::PhantomData;
 use std::path::Path;
 use std::time::{Duration, SystemTime};
 
@@ -8,8 +7,10 @@ use serde::{Deserialize, Serialize};
 use sqlx::{Acquire, Sqlite};
 use time::OffsetDateTime;
 use tokio::fs;
+use touhou::score::ScoreFile;
+use touhou::types::{Difficulty, ShotType, SpellCard, Stage};
 
-use crate::types::{Difficulty, Game, ScoreFile, ShotType, SpellCard, Stage};
+use crate::game::{GameInstance, ScoreGame};
 
 mod row_types;
 
@@ -37,16 +38,16 @@ impl CardAttemptInfo {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct UpdateEvent<G: Game> {
+pub struct UpdateEvent<G: ScoreGame> {
     timestamp: OffsetDateTime,
     shot_type: ShotType<G>,
-    difficulty: Difficulty,
-    stage: Stage,
+    difficulty: Difficulty<G>,
+    stage: Stage<G>,
     practice_no: Option<u32>,
     attempted_cards: HashMap<SpellCard<G>, CardAttemptInfo>,
 }
 
-impl<G: Game> UpdateEvent<G> {
+impl<G: ScoreGame> UpdateEvent<G> {
     fn cmp_key(&self) -> (OffsetDateTime, PracticeSnapshotKey<G>) {
         (
             self.timestamp,
@@ -62,11 +63,11 @@ impl<G: Game> UpdateEvent<G> {
         self.shot_type
     }
 
-    pub fn stage(&self) -> Stage {
+    pub fn stage(&self) -> Stage<G> {
         self.stage
     }
 
-    pub fn difficulty(&self) -> Difficulty {
+    pub fn difficulty(&self) -> Difficulty<G> {
         self.difficulty
     }
 
@@ -89,36 +90,39 @@ impl<G: Game> UpdateEvent<G> {
     }
 }
 
-impl<G: Game> PartialEq for UpdateEvent<G> {
+impl<G: ScoreGame> PartialEq for UpdateEvent<G> {
     fn eq(&self, other: &Self) -> bool {
         self.cmp_key() == other.cmp_key()
     }
 }
 
-impl<G: Game> Eq for UpdateEvent<G> {}
+impl<G: ScoreGame> Eq for UpdateEvent<G> {}
 
-impl<G: Game> PartialOrd for UpdateEvent<G> {
+impl<G: ScoreGame> PartialOrd for UpdateEvent<G> {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl<G: Game> Ord for UpdateEvent<G> {
+impl<G: ScoreGame> Ord for UpdateEvent<G> {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         self.cmp_key().cmp(&other.cmp_key())
     }
 }
 
+/// Previous and current practice counts, along with the cards attempted during a practice session.
+type AttemptGroup<G> = (u32, u32, HashMap<SpellCard<G>, CardAttemptInfo>);
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct FileSnapshot<G: Game> {
+pub struct FileSnapshot<G: ScoreGame> {
     timestamp: OffsetDateTime,
     cards: HashMap<CardSnapshotKey<G>, CardSnapshot<G>>,
     practices: HashMap<PracticeSnapshotKey<G>, PracticeSnapshot<G>>,
 }
 
-impl<G: Game> FileSnapshot<G> {
+impl<G: ScoreGame> FileSnapshot<G> {
     pub fn new<R: Read>(
-        game: &G,
+        game: &GameInstance<G>,
         timestamp: OffsetDateTime,
         src: R,
     ) -> Result<Self, anyhow::Error> {
@@ -163,9 +167,9 @@ impl<G: Game> FileSnapshot<G> {
 
     pub fn get_practice(
         &self,
-        difficulty: Difficulty,
+        difficulty: Difficulty<G>,
         shot_type: ShotType<G>,
-        stage: Stage,
+        stage: Stage<G>,
     ) -> Option<&PracticeSnapshot<G>> {
         self.practices.get(&(difficulty, shot_type, stage))
     }
@@ -204,10 +208,8 @@ impl<G: Game> FileSnapshot<G> {
             .map(|(k, v)| (*k, (v.attempts, v.captures)))
             .collect();
 
-        let mut grouped_card_attempts: HashMap<
-            PracticeSnapshotKey<G>,
-            (u32, u32, HashMap<SpellCard<G>, CardAttemptInfo>),
-        > = HashMap::new();
+        let mut grouped_card_attempts: HashMap<PracticeSnapshotKey<G>, AttemptGroup<G>> =
+            HashMap::new();
 
         for (key, new_card) in other.cards.iter() {
             let (prev_attempts, prev_captures) = prev_card_attempts
@@ -274,13 +276,13 @@ impl<G: Game> FileSnapshot<G> {
 }
 
 #[derive(Debug)]
-pub struct Update<'a, G: Game> {
+pub struct Update<'a, G: ScoreGame> {
     prev: FileSnapshot<G>,
     cur: &'a FileSnapshot<G>,
     events: Vec<UpdateEvent<G>>,
 }
 
-impl<'a, G: Game> Update<'a, G> {
+impl<'a, G: ScoreGame> Update<'a, G> {
     pub fn events(&self) -> impl Iterator<Item = &UpdateEvent<G>> {
         self.events.iter()
     }
@@ -295,11 +297,11 @@ impl<'a, G: Game> Update<'a, G> {
 }
 
 #[derive(Debug)]
-pub struct UpdateStream<G: Game> {
+pub struct UpdateStream<G: ScoreGame> {
     cur_snapshot: FileSnapshot<G>,
 }
 
-impl<G: Game> UpdateStream<G> {
+impl<G: ScoreGame> UpdateStream<G> {
     pub fn new(snapshot: FileSnapshot<G>) -> Self {
         Self {
             cur_snapshot: snapshot,
@@ -328,21 +330,19 @@ impl<G: Game> UpdateStream<G> {
 }
 
 #[derive(Debug)]
-pub struct SnapshotStream<G: Game> {
-    game: G,
+pub struct SnapshotStream<G: ScoreGame> {
+    game: GameInstance<G>,
     last_modified: SystemTime,
-    phantom: PhantomData<G>,
 }
 
-impl<G: Game> SnapshotStream<G> {
-    pub async fn new(game: G) -> Result<Self, anyhow::Error> {
+impl<G: ScoreGame> SnapshotStream<G> {
+    pub async fn new(game: GameInstance<G>) -> Result<Self, anyhow::Error> {
         fs::metadata(game.score_path())
             .await?
             .modified()
             .map(|last_modified| Self {
                 game,
                 last_modified,
-                phantom: PhantomData,
             })
             .map_err(|e| e.into())
     }
@@ -0,0 +1,170 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, Sqlite};
+use time::OffsetDateTime;
+use touhou::score::{PracticeRecord, SpellCardRecord};
+use touhou::types::{Difficulty, Game, GameValue, ShotType, SpellCard, Stage};
+
+pub type CardSnapshotKey<G> = (SpellCard<G>, ShotType<G>);
+pub type PracticeSnapshotKey<G> = (Difficulty<G>, ShotType<G>, Stage<G>);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct CardSnapshot<G: Game> {
+    pub timestamp: OffsetDateTime,
+    pub card: SpellCard<G>,
+    pub shot_type: ShotType<G>,
+    pub captures: u32,
+    pub attempts: u32,
+    pub max_bonus: u32,
+}
+
+impl<G: Game> CardSnapshot<G> {
+    pub fn from_score_data<'a, T: SpellCardRecord<G>>(
+        timestamp: OffsetDateTime,
+        data: &'a T,
+    ) -> impl Iterator<Item = Self> + 'a {
+        data.shot_types().iter().map(move |shot_type| Self {
+            timestamp,
+            card: data.card(),
+            shot_type: *shot_type,
+            captures: data.captures(shot_type),
+            attempts: data.attempts(shot_type),
+            max_bonus: data.max_bonus(shot_type),
+        })
+    }
+
+    pub fn key(&self) -> CardSnapshotKey<G> {
+        (self.card, self.shot_type)
+    }
+
+    pub fn card_id(&self) -> u32 {
+        self.card.id()
+    }
+
+    pub fn card_name(&self) -> &'static str {
+        self.card.name()
+    }
+
+    pub fn difficulty(&self) -> Difficulty<G> {
+        self.card.difficulty()
+    }
+
+    pub fn stage(&self) -> Stage<G> {
+        self.card.stage()
+    }
+
+    pub async fn insert<'c, E>(&self, conn: E) -> Result<(), sqlx::Error>
+    where
+        E: Executor<'c, Database = Sqlite>,
+    {
+        let card_id = self.card.id();
+        let shot_type = self.shot_type.raw_id();
+        let game = G::GAME_ID.number();
+
+        sqlx::query!(
+            "
+            INSERT INTO spellcards (ts, card_id, shot_type, game, captures, attempts, max_bonus)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            ",
+            self.timestamp,
+            card_id,
+            shot_type,
+            game,
+            self.captures,
+            self.attempts,
+            self.max_bonus
+        )
+        .execute(conn)
+        .await
+        .map(|_| ())
+    }
+
+    /// Get the earliest snapshot of a card taken at or after the given time, if any.
+    pub async fn get_first_snapshot_after<'c, E>(
+        conn: E,
+        card: SpellCard<G>,
+        shot_type: ShotType<G>,
+        after: OffsetDateTime,
+    ) -> Result<Option<Self>, sqlx::Error>
+    where
+        E: Executor<'c, Database = Sqlite>,
+    {
+        sqlx::query_as::<_, (OffsetDateTime, u32, u32, u32)>(
+            "
+            SELECT ts, captures, attempts, max_bonus FROM spellcards
+            WHERE game = ? AND card_id = ? AND shot_type = ? AND ts >= ?
+            ORDER BY ts ASC
+            LIMIT 1
+            ",
+        )
+        .bind(G::GAME_ID.number())
+        .bind(card.id())
+        .bind(shot_type.raw_id())
+        .bind(after)
+        .fetch_optional(conn)
+        .await
+        .map(|row| {
+            row.map(|(timestamp, captures, attempts, max_bonus)| Self {
+                timestamp,
+                card,
+                shot_type,
+                captures,
+                attempts,
+                max_bonus,
+            })
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PracticeSnapshot<G: Game> {
+    pub timestamp: OffsetDateTime,
+    pub difficulty: Difficulty<G>,
+    pub shot_type: ShotType<G>,
+    pub stage: Stage<G>,
+    pub attempts: u32,
+    pub high_score: u32,
+}
+
+impl<G: Game> PracticeSnapshot<G> {
+    pub fn from_score_data<T: PracticeRecord<G>>(timestamp: OffsetDateTime, data: &T) -> Self {
+        Self {
+            timestamp,
+            difficulty: data.difficulty(),
+            shot_type: data.shot_type(),
+            stage: data.stage(),
+            attempts: data.attempts(),
+            high_score: data.high_score(),
+        }
+    }
+
+    pub fn key(&self) -> PracticeSnapshotKey<G> {
+        (self.difficulty, self.shot_type, self.stage)
+    }
+
+    pub async fn insert<'c, E>(&self, conn: E) -> Result<(), sqlx::Error>
+    where
+        E: Executor<'c, Database = Sqlite>,
+    {
+        let difficulty = self.difficulty.raw_id();
+        let shot_type = self.shot_type.raw_id();
+        let game = G::GAME_ID.number();
+        let stage = self.stage.raw_id();
+
+        sqlx::query!(
+            "
+            INSERT INTO practices (ts, difficulty, shot_type, game, stage, attempts, high_score)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            ",
+            self.timestamp,
+            difficulty,
+            shot_type,
+            game,
+            stage,
+            self.attempts,
+            self.high_score
+        )
+        .execute(conn)
+        .await
+        .map(|_| ())
+    }
+}
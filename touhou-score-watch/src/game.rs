@@ -0,0 +1,89 @@
+use std::io::{self, Read};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use sysinfo::{ProcessRefreshKind, System, SystemExt};
+use tokio::time::interval;
+use touhou::process::{find_game_process, GamePaths};
+use touhou::score::HasScoreFile;
+use touhou::{Touhou10, Touhou15, Touhou7, Touhou8};
+
+/// A game with a score file that can be located and read.
+pub trait ScoreGame: HasScoreFile {}
+
+impl ScoreGame for Touhou7 {}
+impl ScoreGame for Touhou8 {}
+impl ScoreGame for Touhou10 {}
+
+/// Snapshots are not keyed by play mode, so only Legacy mode records are watched.
+impl ScoreGame for Touhou15 {}
+
+/// The locations of the files used by a running game.
+#[derive(Debug, Clone)]
+pub struct GameInstance<G: ScoreGame> {
+    paths: GamePaths,
+    score_path: PathBuf,
+    phantom: PhantomData<G>,
+}
+
+impl<G: ScoreGame> GameInstance<G> {
+    fn find(system: &System) -> Option<Self> {
+        find_game_process(system, G::GAME_ID)
+            .and_then(|proc| GamePaths::from_process(G::GAME_ID, proc))
+            .map(|paths| Self {
+                score_path: paths.score_file(),
+                paths,
+                phantom: PhantomData,
+            })
+    }
+
+    pub fn paths(&self) -> &GamePaths {
+        &self.paths
+    }
+
+    pub fn install_dir(&self) -> &Path {
+        self.paths.install_dir()
+    }
+
+    pub fn score_path(&self) -> &Path {
+        &self.score_path
+    }
+
+    pub fn load_score_file<R: Read>(&self, src: R) -> io::Result<G::ScoreFile> {
+        G::load_score_file(src)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Touhou {
+    Touhou7(GameInstance<Touhou7>),
+    Touhou8(GameInstance<Touhou8>),
+    Touhou10(GameInstance<Touhou10>),
+    Touhou15(GameInstance<Touhou15>),
+}
+
+impl Touhou {
+    fn find(system: &System) -> Option<Self> {
+        GameInstance::find(system)
+            .map(Self::Touhou7)
+            .or_else(|| GameInstance::find(system).map(Self::Touhou8))
+            .or_else(|| GameInstance::find(system).map(Self::Touhou10))
+            .or_else(|| GameInstance::find(system).map(Self::Touhou15))
+    }
+
+    /// Wait until a supported game is running.
+    pub async fn wait_for_game() -> Self {
+        let mut system = System::new();
+        let mut interval = interval(Duration::from_millis(1000));
+
+        loop {
+            interval.tick().await;
+            system.refresh_processes_specifics(ProcessRefreshKind::new());
+
+            if let Some(game) = Self::find(&system) {
+                return game;
+            }
+        }
+    }
+}
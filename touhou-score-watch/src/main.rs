@@ -4,21 +4,14 @@ use std::time::Duration;
 use sqlx::sqlite::SqlitePool;
 use tokio::sync::oneshot;
 use tokio::time::interval;
-use types::Game;
 
-pub mod crypt;
 pub mod db;
-pub mod decompress;
-pub mod types;
-
-pub mod score_file;
-pub mod th07;
-pub mod th18;
+pub mod game;
 
 use db::{CardAttemptInfo, CardSnapshot, SnapshotStream, UpdateStream};
-use types::Touhou;
+use game::{GameInstance, ScoreGame, Touhou};
 
-pub async fn display_card_stats<G: Game>(
+pub async fn display_card_stats<G: ScoreGame>(
     pool: &SqlitePool,
     snapshot: &CardSnapshot<G>,
     attempt_info: Option<&CardAttemptInfo>,
@@ -75,45 +68,37 @@ pub async fn display_card_stats<G: Game>(
     Ok(())
 }
 
-#[tokio::main]
-async fn main() -> Result<(), anyhow::Error> {
-    let pool =
-        SqlitePool::connect(&env::var("DATABASE_URL").unwrap_or(String::from("sqlite:touhou.db")))
-            .await?;
-
-    let (exit_tx, mut exit_rx) = oneshot::channel();
-    let mut ctrl_c_handle = tokio::spawn(async move {
-        tokio::signal::ctrl_c().await.unwrap();
-        exit_tx.send(()).unwrap();
-    });
-
-    print!("Waiting for Touhou... ");
-
-    let game = Touhou::wait_for_game().await;
+pub async fn watch_game<G: ScoreGame>(
+    pool: &SqlitePool,
+    game: GameInstance<G>,
+    exit_rx: &mut oneshot::Receiver<()>,
+) -> anyhow::Result<()> {
     println!(
-        "detected {} ({})\nScore file should be at {}\n",
-        game.game_id().numbered_name(),
-        game.game_id().full_name(),
-        game.score_path().display()
+        "detected {} ({}) at {}\nScore file should be at {}\nReplays should be in {}\n",
+        G::numbered_name(),
+        G::full_title(),
+        game.install_dir().display(),
+        game.score_path().display(),
+        game.paths().replay_dir().display()
     );
 
     let mut snap_stream = tokio::select! {
         s = SnapshotStream::new(game) => s?,
-        _ = &mut exit_rx => {
+        _ = &mut *exit_rx => {
             return Ok(())
         }
     };
 
     let prev_snapshot = snap_stream.read_snapshot_data().await?;
     for card_snapshot in prev_snapshot.iter_cards() {
-        display_card_stats(&pool, card_snapshot, None).await?;
+        display_card_stats(pool, card_snapshot, None).await?;
     }
-    prev_snapshot.insert(&pool).await?;
+    prev_snapshot.insert(pool).await?;
 
     let mut interval = interval(Duration::from_millis(1000));
     let mut update_stream = UpdateStream::new(prev_snapshot);
 
-    while !ctrl_c_handle.is_finished() {
+    loop {
         interval.tick().await;
 
         let f = async {
@@ -123,7 +108,7 @@ async fn main() -> Result<(), anyhow::Error> {
 
         let new_snapshot = tokio::select! {
             s = f => s?,
-            _ = &mut ctrl_c_handle => {
+            _ = &mut *exit_rx => {
                 println!("Ctrl-C received, exiting...");
                 break;
             }
@@ -150,12 +135,43 @@ async fn main() -> Result<(), anyhow::Error> {
                         .cur_snapshot()
                         .get_card(event.shot_type(), card_id)
                         .unwrap();
-                    display_card_stats(&pool, new_card_snapshot, Some(attempt_info)).await?;
+                    display_card_stats(pool, new_card_snapshot, Some(attempt_info)).await?;
                 }
             }
 
-            update_stream.cur_snapshot().insert(&pool).await?;
+            update_stream.cur_snapshot().insert(pool).await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), anyhow::Error> {
+    let pool =
+        SqlitePool::connect(&env::var("DATABASE_URL").unwrap_or(String::from("sqlite:touhou.db")))
+            .await?;
+
+    let (exit_tx, mut exit_rx) = oneshot::channel();
+    tokio::spawn(async move {
+        tokio::signal::ctrl_c().await.unwrap();
+        exit_tx.send(()).unwrap();
+    });
+
+    print!("Waiting for Touhou... ");
+
+    let game = tokio::select! {
+        game = Touhou::wait_for_game() => game,
+        _ = &mut exit_rx => {
+            return Ok(())
         }
+    };
+
+    match game {
+        Touhou::Touhou7(game) => watch_game(&pool, game, &mut exit_rx).await?,
+        Touhou::Touhou8(game) => watch_game(&pool, game, &mut exit_rx).await?,
+        Touhou::Touhou10(game) => watch_game(&pool, game, &mut exit_rx).await?,
+        Touhou::Touhou15(game) => watch_game(&pool, game, &mut exit_rx).await?,
     }
 
     pool.close().await;
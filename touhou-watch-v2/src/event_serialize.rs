@@ -1,9 +1,13 @@
+use std::collections::HashMap;
+
 use serde::Serialize;
 use touhou::types::{GameId, GameValue};
-use touhou::Location;
+use touhou::{Location, Touhou10, Touhou7, Touhou8};
 
-use super::set_track::{Attempt, Metrics, SetKey};
+use super::set_track::{ActiveGame, Attempt, Metrics, RunHistory, SetKey};
 use super::TrackedGame;
+use crate::persist::{GameSession, SessionFile};
+use crate::time::EventTime;
 use crate::watcher::GameReader;
 
 #[derive(Debug, Clone, Copy, Serialize)]
@@ -94,6 +98,154 @@ impl SetInfo {
             .iter_attempts()
             .map(|(k, v)| Self::new(k, v))
     }
+
+    /// Get aggregated practice data for a single game, or for all tracked games.
+    pub fn get_all_sets(
+        metrics: &Metrics,
+        game_id: Option<GameId>,
+    ) -> Result<Vec<SetInfo>, &'static str> {
+        match game_id {
+            None => Ok(Self::get_sets::<Touhou7>(metrics)
+                .chain(Self::get_sets::<Touhou8>(metrics))
+                .chain(Self::get_sets::<Touhou10>(metrics))
+                .collect()),
+            Some(GameId::PCB) => Ok(Self::get_sets::<Touhou7>(metrics).collect()),
+            Some(GameId::IN) => Ok(Self::get_sets::<Touhou8>(metrics).collect()),
+            Some(GameId::MoF) => Ok(Self::get_sets::<Touhou10>(metrics).collect()),
+            Some(_) => Err("game not supported for tracking"),
+        }
+    }
+
+    pub fn get_session_sets<G: TrackedGame>(session: &GameSession<G>) -> Vec<SetInfo> {
+        let mut sets: HashMap<SetKey<G>, Vec<Attempt>> = HashMap::new();
+        for (key, attempt) in session.attempts() {
+            sets.entry(*key).or_default().push(*attempt);
+        }
+
+        let mut sets: Vec<_> = sets.into_iter().collect();
+        sets.sort_unstable_by_key(|(k, _)| *k);
+        sets.into_iter().map(|(k, v)| Self::new(&k, &v)).collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct RunAttemptInfo {
+    location: SerializedLocation,
+    attempt: Attempt,
+}
+
+impl RunAttemptInfo {
+    pub fn new<G: TrackedGame>(key: &SetKey<G>, attempt: &Attempt) -> Self {
+        Self {
+            location: SerializedLocation::new(key.location()),
+            attempt: *attempt,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunInfo {
+    game: SerializedGameId,
+    shot_type: NamedValue<u16>,
+    difficulty: NamedValue<u16>,
+    start_time: EventTime,
+    end_time: EventTime,
+    cleared: bool,
+    attempts: Vec<RunAttemptInfo>,
+}
+
+impl RunInfo {
+    pub fn new<G: TrackedGame>(run: &RunHistory<G>) -> Self {
+        Self {
+            game: SerializedGameId::new(G::GAME_ID),
+            shot_type: run.shot().unwrap().into(),
+            difficulty: run.difficulty().unwrap().into(),
+            start_time: run.start_time().timestamp(),
+            end_time: run.end_time().timestamp(),
+            cleared: run.cleared(),
+            attempts: run
+                .attempts()
+                .iter()
+                .map(|(key, attempt)| RunAttemptInfo::new(key, attempt))
+                .collect(),
+        }
+    }
+
+    pub fn start_time(&self) -> EventTime {
+        self.start_time
+    }
+
+    pub fn get_session_runs<G: TrackedGame>(
+        session: &GameSession<G>,
+    ) -> impl Iterator<Item = RunInfo> + '_ {
+        session.runs().iter().map(Self::new)
+    }
+}
+
+/// The state of a game that is currently in progress.
+#[derive(Debug, Clone, Serialize)]
+pub struct CurrentRunInfo {
+    game: SerializedGameId,
+    shot_type: NamedValue<u16>,
+    difficulty: NamedValue<u16>,
+    start_time: EventTime,
+    location: Option<SerializedLocation>,
+    misses: u32,
+    bombs: u32,
+    attempts: Vec<RunAttemptInfo>,
+}
+
+impl CurrentRunInfo {
+    pub fn new<G: TrackedGame>(game: &ActiveGame<G>) -> Self {
+        Self {
+            game: SerializedGameId::new(G::GAME_ID),
+            shot_type: game.shot().unwrap().into(),
+            difficulty: game.difficulty().unwrap().into(),
+            start_time: game.start_time().timestamp(),
+            location: game.cur_location().map(SerializedLocation::new),
+            misses: game.misses(),
+            bombs: game.bombs(),
+            attempts: game
+                .attempts()
+                .iter()
+                .map(|(key, attempt)| RunAttemptInfo::new(key, attempt))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionInfo {
+    id: String,
+    current: bool,
+    attempts: usize,
+    runs: usize,
+}
+
+impl SessionInfo {
+    pub fn new(session: &SessionFile, current: bool) -> Self {
+        let data = session.data();
+
+        Self {
+            id: session.id().to_string(),
+            current,
+            attempts: data.th07().attempts().len()
+                + data.th08().attempts().len()
+                + data.th10().attempts().len(),
+            runs: data.th07().runs().len() + data.th08().runs().len() + data.th10().runs().len(),
+        }
+    }
+
+    pub fn get_sessions(metrics: &Metrics) -> Vec<SessionInfo> {
+        let n_sessions = metrics.sessions().len();
+        metrics
+            .sessions()
+            .iter()
+            .enumerate()
+            .filter(|(i, session)| *i + 1 == n_sessions || !session.is_empty())
+            .map(|(i, session)| Self::new(session, i + 1 == n_sessions))
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize)]
@@ -6,7 +6,8 @@ use set_track::Metrics;
 use tauri::Window;
 
 mod event_serialize;
-// mod persist;
+mod persist;
+mod server;
 mod set_track;
 mod th07;
 mod th08;
@@ -14,7 +15,8 @@ mod th10;
 mod time;
 mod watcher;
 
-use event_serialize::SetInfo;
+use event_serialize::{RunInfo, SessionInfo, SetInfo};
+use server::EventServer;
 use set_track::LocationInfo;
 use touhou::types::{GameId, SpellCardInfo};
 use touhou::{AllIterable, Touhou10, Touhou7, Touhou8};
@@ -53,19 +55,73 @@ fn start_watcher(window: Window) {
 fn get_practice_data(game_id: Option<GameId>) -> Result<Vec<SetInfo>, &'static str> {
     let metrics = Metrics::get();
     let lock = metrics.lock();
+    SetInfo::get_all_sets(&lock, game_id)
+}
+
+#[tauri::command]
+fn list_sessions() -> Vec<SessionInfo> {
+    let metrics = Metrics::get();
+    let lock = metrics.lock();
+    SessionInfo::get_sessions(&lock)
+}
+
+#[tauri::command]
+fn get_session_practice_data(
+    session_id: String,
+    game_id: Option<GameId>,
+) -> Result<Vec<SetInfo>, &'static str> {
+    let metrics = Metrics::get();
+    let lock = metrics.lock();
+    let data = lock.session(&session_id).ok_or("session not found")?.data();
 
     match game_id {
-        None => Ok(SetInfo::get_sets::<Touhou7>(&lock)
-            .chain(SetInfo::get_sets::<Touhou8>(&lock))
-            .chain(SetInfo::get_sets::<Touhou10>(&lock))
-            .collect()),
-        Some(GameId::PCB) => Ok(SetInfo::get_sets::<Touhou7>(&lock).collect()),
-        Some(GameId::IN) => Ok(SetInfo::get_sets::<Touhou8>(&lock).collect()),
-        Some(GameId::MoF) => Ok(SetInfo::get_sets::<Touhou10>(&lock).collect()),
+        None => {
+            let mut ret = SetInfo::get_session_sets(data.th07());
+            ret.extend(SetInfo::get_session_sets(data.th08()));
+            ret.extend(SetInfo::get_session_sets(data.th10()));
+            Ok(ret)
+        }
+        Some(GameId::PCB) => Ok(SetInfo::get_session_sets(data.th07())),
+        Some(GameId::IN) => Ok(SetInfo::get_session_sets(data.th08())),
+        Some(GameId::MoF) => Ok(SetInfo::get_session_sets(data.th10())),
         Some(_) => Err("game not supported for tracking"),
     }
 }
 
+#[tauri::command]
+fn get_session_runs(
+    session_id: String,
+    game_id: Option<GameId>,
+) -> Result<Vec<RunInfo>, &'static str> {
+    let metrics = Metrics::get();
+    let lock = metrics.lock();
+    let data = lock.session(&session_id).ok_or("session not found")?.data();
+
+    match game_id {
+        None => {
+            let mut ret: Vec<_> = RunInfo::get_session_runs(data.th07())
+                .chain(RunInfo::get_session_runs(data.th08()))
+                .chain(RunInfo::get_session_runs(data.th10()))
+                .collect();
+            ret.sort_by_key(|run| run.start_time());
+            Ok(ret)
+        }
+        Some(GameId::PCB) => Ok(RunInfo::get_session_runs(data.th07()).collect()),
+        Some(GameId::IN) => Ok(RunInfo::get_session_runs(data.th08()).collect()),
+        Some(GameId::MoF) => Ok(RunInfo::get_session_runs(data.th10()).collect()),
+        Some(_) => Err("game not supported for tracking"),
+    }
+}
+
+#[tauri::command]
+async fn start_event_server(port: Option<u16>) -> Result<String, String> {
+    EventServer::get()
+        .start(port.unwrap_or(server::DEFAULT_PORT))
+        .await
+        .map(|addr| addr.to_string())
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 fn get_locations(game_id: GameId) -> Result<&'static [LocationInfo], &'static str> {
     match game_id {
@@ -102,13 +158,30 @@ fn end_tracking() {
 
 fn main() {
     tauri::Builder::default()
+        .setup(|_| {
+            // Allow the event server to be started without going through the UI, e.g. for overlays.
+            if let Ok(port) = env::var("TOUHOU_WATCH_SERVER_PORT") {
+                let port = port.parse().unwrap_or(server::DEFAULT_PORT);
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = EventServer::get().start(port).await {
+                        eprintln!("Could not start event server: {}", e);
+                    }
+                });
+            }
+
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             start_watcher,
             load_spellcard_data,
             get_practice_data,
             start_tracking,
             end_tracking,
-            get_locations
+            get_locations,
+            list_sessions,
+            get_session_practice_data,
+            get_session_runs,
+            start_event_server
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -1,7 +1,6 @@
 use std::fs;
 use std::fs::File;
 use std::io::{BufReader, Error as IOError, ErrorKind, Read, Result as IOResult, Write};
-use std::marker::PhantomData;
 use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 
@@ -9,10 +8,14 @@ use directories::ProjectDirs;
 use flate2::bufread::GzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
+use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
-use touhou::Game;
+use touhou::{Touhou10, Touhou7, Touhou8};
 
-use crate::run::Run;
+use crate::set_track::{Attempt, RunHistory, SetKey};
+use crate::watcher::TrackedGame;
+
+const SESSION_EXTENSION: &str = "json.gz";
 
 fn get_project_dirs() -> &'static ProjectDirs {
     static CELL: OnceLock<ProjectDirs> = OnceLock::new();
@@ -22,44 +25,119 @@ fn get_project_dirs() -> &'static ProjectDirs {
     })
 }
 
-fn get_data_path<G: Game>() -> PathBuf {
-    get_project_dirs()
-        .data_dir()
-        .join(G::GAME_ID.abbreviation())
+fn get_sessions_path() -> PathBuf {
+    get_project_dirs().data_dir().join("sessions")
+}
+
+/// Set tracking attempts and finished runs for a single game within a session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "G: TrackedGame")]
+pub struct GameSession<G: TrackedGame> {
+    attempts: Vec<(SetKey<G>, Attempt)>,
+    runs: Vec<RunHistory<G>>,
+}
+
+impl<G: TrackedGame> GameSession<G> {
+    pub fn attempts(&self) -> &[(SetKey<G>, Attempt)] {
+        &self.attempts
+    }
+
+    pub fn runs(&self) -> &[RunHistory<G>] {
+        &self.runs
+    }
+
+    pub fn push_attempt(&mut self, key: SetKey<G>, attempt: Attempt) {
+        self.attempts.push((key, attempt));
+    }
+
+    pub fn push_run(&mut self, run: RunHistory<G>) {
+        let idx = self
+            .runs
+            .binary_search_by_key(&run.start_time(), |r| r.start_time())
+            .unwrap_or_else(std::convert::identity);
+        self.runs.insert(idx, run);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.attempts.is_empty() && self.runs.is_empty()
+    }
+}
+
+impl<G: TrackedGame> Default for GameSession<G> {
+    fn default() -> Self {
+        Self {
+            attempts: Vec::new(),
+            runs: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionData {
+    #[serde(default)]
+    th07: GameSession<Touhou7>,
+    #[serde(default)]
+    th08: GameSession<Touhou8>,
+    #[serde(default)]
+    th10: GameSession<Touhou10>,
+}
+
+impl SessionData {
+    pub fn th07(&self) -> &GameSession<Touhou7> {
+        &self.th07
+    }
+
+    pub fn th07_mut(&mut self) -> &mut GameSession<Touhou7> {
+        &mut self.th07
+    }
+
+    pub fn th08(&self) -> &GameSession<Touhou8> {
+        &self.th08
+    }
+
+    pub fn th08_mut(&mut self) -> &mut GameSession<Touhou8> {
+        &mut self.th08
+    }
+
+    pub fn th10(&self) -> &GameSession<Touhou10> {
+        &self.th10
+    }
+
+    pub fn th10_mut(&mut self) -> &mut GameSession<Touhou10> {
+        &mut self.th10
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.th07.is_empty() && self.th08.is_empty() && self.th10.is_empty()
+    }
 }
 
+/// A single run of the application, saved to disk as gzipped JSON.
 #[derive(Debug)]
-pub struct SessionFile<G: Game> {
+pub struct SessionFile {
+    id: String,
     path: PathBuf,
-    runs: Vec<Run>,
-    _phantom: PhantomData<G>,
+    data: SessionData,
 }
 
-impl<G: Game> SessionFile<G> {
-    pub fn new(file_name: impl AsRef<Path>) -> IOResult<Self> {
-        let mut path = get_data_path::<G>();
+impl SessionFile {
+    pub fn new(id: impl Into<String>) -> IOResult<Self> {
+        let id = id.into();
+        let mut path = get_sessions_path();
         if !path.is_dir() {
             fs::create_dir_all(&path)?;
         }
 
-        path.push(file_name);
-        path.set_extension("json.gz");
+        path.push(&id);
+        path.set_extension(SESSION_EXTENSION);
 
-        let runs = if path.is_file() {
-            let mut reader = File::open(&path).map(BufReader::new).map(GzDecoder::new)?;
-            let mut data = Vec::new();
-            reader.read_to_end(&mut data)?;
-            serde_json::from_slice(&data[..])
-                .map_err(|e| IOError::new(ErrorKind::InvalidData, e))?
+        let data = if path.is_file() {
+            Self::read_data(&path)?
         } else {
-            Vec::new()
+            SessionData::default()
         };
 
-        Ok(Self {
-            path,
-            runs,
-            _phantom: PhantomData,
-        })
+        Ok(Self { id, path, data })
     }
 
     pub fn new_default() -> IOResult<Self> {
@@ -77,22 +155,64 @@ impl<G: Game> SessionFile<G> {
         ))
     }
 
+    /// Load all saved sessions, in order from oldest to newest.
+    pub fn load_all() -> IOResult<Vec<Self>> {
+        let dir = get_sessions_path();
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut sessions = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let id = match path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(SESSION_EXTENSION))
+                .and_then(|name| name.strip_suffix('.'))
+            {
+                Some(id) => id.to_string(),
+                None => continue,
+            };
+
+            match Self::read_data(&path) {
+                Ok(data) => sessions.push(Self { id, path, data }),
+                Err(e) => eprintln!("Could not load session {}: {}", path.display(), e),
+            }
+        }
+
+        sessions.sort_unstable_by(|a, b| a.id.cmp(&b.id));
+        Ok(sessions)
+    }
+
+    fn read_data(path: &Path) -> IOResult<SessionData> {
+        let mut reader = File::open(path).map(BufReader::new).map(GzDecoder::new)?;
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        serde_json::from_slice(&data[..]).map_err(|e| IOError::new(ErrorKind::InvalidData, e))
+    }
+
     pub fn save(&self) -> IOResult<()> {
         let mut file = GzEncoder::new(File::create(&self.path)?, Compression::default());
         let data =
-            serde_json::to_vec(&self.runs).map_err(|e| IOError::new(ErrorKind::InvalidData, e))?;
-        file.write_all(&data[..])
+            serde_json::to_vec(&self.data).map_err(|e| IOError::new(ErrorKind::InvalidData, e))?;
+        file.write_all(&data[..])?;
+        file.finish().map(|_| ())
     }
 
-    pub fn add_run(&mut self, run: Run) {
-        let idx = self
-            .runs
-            .binary_search_by_key(&run.start_time(), |r| r.start_time())
-            .unwrap_or_else(std::convert::identity);
-        self.runs.insert(idx, run);
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn data(&self) -> &SessionData {
+        &self.data
+    }
+
+    pub fn data_mut(&mut self) -> &mut SessionData {
+        &mut self.data
     }
 
     pub fn is_empty(&self) -> bool {
-        self.runs.is_empty()
+        self.data.is_empty()
     }
 }
@@ -0,0 +1,277 @@
+//! Local HTTP and WebSocket server for stream overlays.
+//!
+//! Tracking events are broadcast as JSON to every client connected to `/events`, and the current
+//! run state and practice data can be queried from the `/api` endpoints.
+
+use std::collections::HashMap;
+use std::io::Result as IOResult;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::{Mutex, OnceLock};
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::Query;
+use axum::http::header::ACCESS_CONTROL_ALLOW_ORIGIN;
+use axum::http::{HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{middleware, Json, Router};
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpListener;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::{self, Receiver, Sender};
+use touhou::types::GameId;
+
+use crate::event_serialize::{
+    AttachEvent, CurrentRunInfo, RunInfo, SerializedGameId, SerializedLocation, SessionInfo,
+    SetInfo,
+};
+use crate::set_track::{Attempt, Metrics};
+
+pub const DEFAULT_PORT: u16 = 15700;
+
+const EVENT_QUEUE_SIZE: usize = 256;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerEvent {
+    Attached(AttachEvent),
+    Detached(AttachEvent),
+    LocationChanged {
+        game: SerializedGameId,
+        location: SerializedLocation,
+    },
+    Miss {
+        game: SerializedGameId,
+        location: Option<SerializedLocation>,
+    },
+    Bomb {
+        game: SerializedGameId,
+        location: Option<SerializedLocation>,
+    },
+    SpellResult {
+        game: SerializedGameId,
+        location: SerializedLocation,
+        captured: bool,
+        attempt: Attempt,
+    },
+    RunFinished(RunInfo),
+    Error {
+        message: String,
+    },
+}
+
+#[derive(Debug)]
+pub struct EventServer {
+    sender: Sender<ServerEvent>,
+    current_runs: Mutex<HashMap<GameId, CurrentRunInfo>>,
+    address: Mutex<Option<SocketAddr>>,
+}
+
+impl EventServer {
+    fn new() -> Self {
+        Self {
+            sender: broadcast::channel(EVENT_QUEUE_SIZE).0,
+            current_runs: Mutex::new(HashMap::new()),
+            address: Mutex::new(None),
+        }
+    }
+
+    pub fn get() -> &'static Self {
+        static INSTANCE: OnceLock<EventServer> = OnceLock::new();
+        INSTANCE.get_or_init(Self::new)
+    }
+
+    /// Send an event to all connected clients.
+    ///
+    /// Events sent while no clients are connected are dropped.
+    pub fn broadcast(&self, event: ServerEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    fn subscribe(&self) -> Receiver<ServerEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Set (or clear) the state of the game currently being played.
+    pub fn update_run(&self, game: GameId, run: Option<CurrentRunInfo>) {
+        let mut lock = self
+            .current_runs
+            .lock()
+            .expect("could not lock current runs");
+
+        match run {
+            Some(run) => lock.insert(game, run),
+            None => lock.remove(&game),
+        };
+    }
+
+    fn current_runs(&self) -> Vec<CurrentRunInfo> {
+        let lock = self
+            .current_runs
+            .lock()
+            .expect("could not lock current runs");
+
+        let mut runs: Vec<_> = lock.iter().collect();
+        runs.sort_unstable_by_key(|(game, _)| **game);
+        runs.into_iter().map(|(_, run)| run.clone()).collect()
+    }
+
+    /// Start listening on the given port on localhost, returning the bound address.
+    ///
+    /// If the server is already running, this returns the address it is listening on instead.
+    /// Must be called from within a Tokio runtime.
+    pub async fn start(&'static self, port: u16) -> IOResult<SocketAddr> {
+        let running = *self.address.lock().expect("could not lock server address");
+        if let Some(addr) = running {
+            return Ok(addr);
+        }
+
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port)).await?;
+        let addr = listener.local_addr()?;
+
+        {
+            let mut lock = self.address.lock().expect("could not lock server address");
+            if let Some(addr) = *lock {
+                return Ok(addr);
+            }
+
+            *lock = Some(addr);
+        }
+
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, router()).await {
+                eprintln!("Event server error: {}", e);
+            }
+
+            *self.address.lock().expect("could not lock server address") = None;
+        });
+
+        eprintln!("Event server listening on {}", addr);
+        Ok(addr)
+    }
+}
+
+fn router() -> Router {
+    Router::new()
+        .route("/events", get(events))
+        .route("/api/runs/current", get(current_runs))
+        .route("/api/practice-data", get(practice_data))
+        .route("/api/sessions", get(sessions))
+        .layer(middleware::map_response(allow_any_origin))
+}
+
+async fn allow_any_origin(mut response: Response) -> Response {
+    response
+        .headers_mut()
+        .insert(ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+    response
+}
+
+async fn events(ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(forward_events)
+}
+
+async fn forward_events(mut socket: WebSocket) {
+    let mut receiver = EventServer::get().subscribe();
+
+    loop {
+        tokio::select! {
+            event = receiver.recv() => match event {
+                Ok(event) => {
+                    let text = match serde_json::to_string(&event) {
+                        Ok(text) => text,
+                        Err(e) => {
+                            eprintln!("Could not serialize event: {}", e);
+                            continue;
+                        }
+                    };
+
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            }
+        }
+    }
+}
+
+async fn current_runs() -> Json<Vec<CurrentRunInfo>> {
+    Json(EventServer::get().current_runs())
+}
+
+#[derive(Debug, Deserialize)]
+struct PracticeDataQuery {
+    game_id: Option<GameId>,
+}
+
+async fn practice_data(Query(query): Query<PracticeDataQuery>) -> impl IntoResponse {
+    let metrics = Metrics::get();
+    let lock = metrics.lock();
+
+    SetInfo::get_all_sets(&lock, query.game_id)
+        .map(Json)
+        .map_err(|e| (StatusCode::NOT_FOUND, e))
+}
+
+async fn sessions() -> Json<Vec<SessionInfo>> {
+    let metrics = Metrics::get();
+    let lock = metrics.lock();
+    Json(SessionInfo::get_sessions(&lock))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures_util::StreamExt;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+    use tokio::time::timeout;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn serves_localhost_clients() {
+        let server = EventServer::get();
+        let addr = server.start(0).await.unwrap();
+        assert_ne!(addr.port(), 0);
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let request =
+            "GET /api/runs/current HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n";
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        let response = response.to_ascii_lowercase();
+        assert!(response.starts_with("http/1.1 200 ok"), "{}", response);
+        assert!(response.contains("access-control-allow-origin: *"));
+        assert!(response.ends_with("\r\n\r\n[]"));
+
+        let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/events", addr))
+            .await
+            .unwrap();
+
+        // The connection only subscribes to events after the handshake, so keep sending until one arrives.
+        let message = loop {
+            server.broadcast(ServerEvent::Error {
+                message: String::from("test"),
+            });
+
+            if let Ok(message) = timeout(Duration::from_millis(100), socket.next()).await {
+                break message.unwrap().unwrap();
+            }
+        };
+
+        let event: serde_json::Value = serde_json::from_str(message.to_text().unwrap()).unwrap();
+        assert_eq!(
+            event,
+            serde_json::json!({ "type": "error", "message": "test" })
+        );
+    }
+}
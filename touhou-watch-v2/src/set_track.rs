@@ -2,7 +2,7 @@ mod data;
 mod metrics;
 mod tracking;
 
-pub use data::{Attempt, MultiSetKey, SetKey};
+pub use data::{Attempt, MultiSetKey, RunHistory, SetKey};
 pub use metrics::{
     start_tracking_th07, start_tracking_th08, start_tracking_th10, LocationInfo, Metrics,
     MetricsHandle,
@@ -89,4 +89,64 @@ impl Attempt {
     pub fn duration(&self) -> Duration {
         self.end_time.game_duration_between(&self.start_time)
     }
+
+    /// Whether this attempt lasted long enough to count, rather than being a brief location change.
+    pub fn is_significant(&self) -> bool {
+        self.duration() >= Duration::seconds_f64(2.0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "G: TrackedGame")]
+pub struct RunHistory<G: TrackedGame> {
+    shot: ShotType<G>,
+    difficulty: Difficulty<G>,
+    start_time: GameTime,
+    end_time: GameTime,
+    cleared: bool,
+    attempts: Vec<(SetKey<G>, Attempt)>,
+}
+
+impl<G: TrackedGame> RunHistory<G> {
+    pub(super) fn new(
+        shot: ShotType<G>,
+        difficulty: Difficulty<G>,
+        start_time: GameTime,
+        end_time: GameTime,
+        cleared: bool,
+        attempts: Vec<(SetKey<G>, Attempt)>,
+    ) -> Self {
+        Self {
+            shot,
+            difficulty,
+            start_time,
+            end_time,
+            cleared,
+            attempts,
+        }
+    }
+
+    pub fn shot(&self) -> ShotType<G> {
+        self.shot
+    }
+
+    pub fn difficulty(&self) -> Difficulty<G> {
+        self.difficulty
+    }
+
+    pub fn start_time(&self) -> GameTime {
+        self.start_time
+    }
+
+    pub fn end_time(&self) -> GameTime {
+        self.end_time
+    }
+
+    pub fn cleared(&self) -> bool {
+        self.cleared
+    }
+
+    pub fn attempts(&self) -> &[(SetKey<G>, Attempt)] {
+        &self.attempts
+    }
 }
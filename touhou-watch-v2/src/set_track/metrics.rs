@@ -11,18 +11,42 @@ use touhou::{
     AllIterable, GameValue, HasLocations, Location, SpellCard, Touhou10, Touhou7, Touhou8,
 };
 
-use super::SetTracker;
+use super::{Attempt, RunHistory, SetKey, SetTracker};
+use crate::persist::SessionFile;
+use crate::watcher::TrackedGame;
 
 #[derive(Debug, Default)]
 pub struct Metrics {
     th07: SetTracker<Touhou7>,
     th08: SetTracker<Touhou8>,
     th10: SetTracker<Touhou10>,
+    sessions: Vec<SessionFile>,
 }
 
 impl Metrics {
     fn new() -> Self {
-        Self::default()
+        let mut ret = Self::default();
+
+        match SessionFile::load_all() {
+            Ok(sessions) => {
+                for session in &sessions {
+                    ret.th07.load_attempts(session.data().th07().attempts());
+                    ret.th08.load_attempts(session.data().th08().attempts());
+                    ret.th10.load_attempts(session.data().th10().attempts());
+                }
+
+                eprintln!("Loaded {} saved sessions", sessions.len());
+                ret.sessions = sessions;
+            }
+            Err(e) => eprintln!("Could not load saved sessions: {}", e),
+        }
+
+        match SessionFile::new_default() {
+            Ok(session) => ret.sessions.push(session),
+            Err(e) => eprintln!("Could not create session file: {}", e),
+        }
+
+        ret
     }
 
     pub fn get() -> MetricsHandle {
@@ -54,6 +78,45 @@ impl Metrics {
     pub fn th10_mut(&mut self) -> &mut SetTracker<Touhou10> {
         &mut self.th10
     }
+
+    /// Get all saved sessions, including the current one, in order from oldest to newest.
+    pub fn sessions(&self) -> &[SessionFile] {
+        &self.sessions
+    }
+
+    pub fn session(&self, id: &str) -> Option<&SessionFile> {
+        self.sessions.iter().find(|session| session.id() == id)
+    }
+
+    fn current_session_mut(&mut self) -> Option<&mut SessionFile> {
+        self.sessions.last_mut()
+    }
+
+    fn save_current_session(&self) {
+        if let Some(session) = self.sessions.last() {
+            if let Err(e) = session.save() {
+                eprintln!("Could not save session {}: {}", session.id(), e);
+            }
+        }
+    }
+
+    pub(super) fn push_attempt<G: TrackedGame>(&mut self, key: SetKey<G>, attempt: Attempt) {
+        if !G::get_tracker_mut(self).push_attempt(key, attempt) {
+            return;
+        }
+
+        if let Some(session) = self.current_session_mut() {
+            G::get_session_mut(session.data_mut()).push_attempt(key, attempt);
+            self.save_current_session();
+        }
+    }
+
+    pub(super) fn push_run<G: TrackedGame>(&mut self, run: RunHistory<G>) {
+        if let Some(session) = self.current_session_mut() {
+            G::get_session_mut(session.data_mut()).push_run(run);
+            self.save_current_session();
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -1,11 +1,12 @@
 use std::collections::HashMap;
 use std::time::Instant;
 
-use time::Duration;
 use touhou::memory::{Location, PauseState, PlayerData, RunData, SpellState, StageData};
 use touhou::{Difficulty, ShotType};
 
-use super::{Attempt, Metrics, SetKey};
+use super::{Attempt, Metrics, RunHistory, SetKey};
+use crate::event_serialize::{CurrentRunInfo, RunInfo, SerializedLocation};
+use crate::server::{EventServer, ServerEvent};
 use crate::time::{GameTime, GameTimeCounter};
 use crate::watcher::TrackedGame;
 
@@ -92,27 +93,67 @@ struct ActiveAttempt<G: TrackedGame> {
 #[derive(Debug, Clone)]
 pub struct ActiveGame<G: TrackedGame> {
     time_counter: GameTimeCounter,
+    start_time: GameTime,
     shot: ShotType<G>,
     difficulty: Difficulty<G>,
     spell_tracker: Option<SpellState<G>>,
     location_filter: Option<LocationResolveFilter<G>>,
     cur_attempt: Option<ActiveAttempt<G>>,
     attempts: Vec<(SetKey<G>, Attempt)>,
+    misses: u32,
+    bombs: u32,
     cleared: bool,
 }
 
 impl<G: TrackedGame> ActiveGame<G> {
     pub fn new<T: RunData<G>>(run: &T) -> Self {
-        ActiveGame {
-            time_counter: GameTimeCounter::default(),
+        let time_counter = GameTimeCounter::default();
+
+        let ret = ActiveGame {
+            start_time: time_counter.now(),
+            time_counter,
             shot: run.player().shot(),
             difficulty: run.difficulty(),
             spell_tracker: run.stage().active_spell(),
             location_filter: None,
             cur_attempt: None,
             attempts: Vec::new(),
+            misses: 0,
+            bombs: 0,
             cleared: false,
-        }
+        };
+
+        ret.publish_state();
+        ret
+    }
+
+    pub fn shot(&self) -> ShotType<G> {
+        self.shot
+    }
+
+    pub fn difficulty(&self) -> Difficulty<G> {
+        self.difficulty
+    }
+
+    pub fn start_time(&self) -> GameTime {
+        self.start_time
+    }
+
+    /// Get the significant attempts made so far during this game.
+    pub fn attempts(&self) -> &[(SetKey<G>, Attempt)] {
+        &self.attempts
+    }
+
+    pub fn misses(&self) -> u32 {
+        self.misses
+    }
+
+    pub fn bombs(&self) -> u32 {
+        self.bombs
+    }
+
+    fn publish_state(&self) {
+        EventServer::get().update_run(G::GAME_ID, Some(CurrentRunInfo::new(self)));
     }
 
     pub fn cur_location(&self) -> Option<Location<G>> {
@@ -130,11 +171,26 @@ impl<G: TrackedGame> ActiveGame<G> {
         {
             let key = SetKey::new(self.shot, self.difficulty, location);
             let end_time = self.time_counter.now();
+            let attempt = Attempt::new(start_time, end_time, success);
+
+            if attempt.is_significant() {
+                self.attempts.push((key, attempt));
+            }
+
+            if location.spell().is_some() {
+                EventServer::get().broadcast(ServerEvent::SpellResult {
+                    game: G::GAME_ID.into(),
+                    location: SerializedLocation::new(location),
+                    captured: success,
+                    attempt,
+                });
+            }
+
+            self.publish_state();
 
             let metrics = Metrics::get();
             let mut lock = metrics.lock();
-            G::get_tracker_mut(&mut lock)
-                .push_attempt(key, Attempt::new(start_time, end_time, success));
+            lock.push_attempt(key, attempt);
         }
     }
 
@@ -184,6 +240,12 @@ impl<G: TrackedGame> ActiveGame<G> {
                     success: true,
                 });
 
+                EventServer::get().broadcast(ServerEvent::LocationChanged {
+                    game: G::GAME_ID.into(),
+                    location: SerializedLocation::new(location),
+                });
+                self.publish_state();
+
                 true
             } else {
                 false
@@ -194,11 +256,6 @@ impl<G: TrackedGame> ActiveGame<G> {
         }
     }
 
-    pub fn exit_location(&mut self) {
-        self.push_attempt();
-        self.location_filter = None;
-    }
-
     fn set_success(&mut self, value: bool) {
         if let Some(attempt) = &mut self.cur_attempt {
             attempt.success = value;
@@ -209,6 +266,28 @@ impl<G: TrackedGame> ActiveGame<G> {
         self.set_success(false);
     }
 
+    pub fn mark_miss(&mut self) {
+        self.misses += 1;
+        self.mark_failed();
+
+        EventServer::get().broadcast(ServerEvent::Miss {
+            game: G::GAME_ID.into(),
+            location: self.cur_location().map(SerializedLocation::new),
+        });
+        self.publish_state();
+    }
+
+    pub fn mark_bomb(&mut self) {
+        self.bombs += 1;
+        self.mark_failed();
+
+        EventServer::get().broadcast(ServerEvent::Bomb {
+            game: G::GAME_ID.into(),
+            location: self.cur_location().map(SerializedLocation::new),
+        });
+        self.publish_state();
+    }
+
     pub fn mark_cleared(&mut self, cleared: bool) {
         self.cleared = cleared;
     }
@@ -221,6 +300,23 @@ impl<G: TrackedGame> Drop for ActiveGame<G> {
         }
 
         self.push_attempt();
+
+        let run = RunHistory::new(
+            self.shot,
+            self.difficulty,
+            self.start_time,
+            self.time_counter.now(),
+            self.cleared,
+            std::mem::take(&mut self.attempts),
+        );
+
+        let server = EventServer::get();
+        server.update_run(G::GAME_ID, None);
+        server.broadcast(ServerEvent::RunFinished(RunInfo::new(&run)));
+
+        let metrics = Metrics::get();
+        let mut lock = metrics.lock();
+        lock.push_run(run);
     }
 }
 
@@ -262,14 +358,25 @@ impl<G: TrackedGame> SetTracker<G> {
         })
     }
 
-    fn push_attempt(&mut self, key: SetKey<G>, attempt: Attempt) {
+    /// Record an attempt, returning whether it was accepted.
+    pub(super) fn push_attempt(&mut self, key: SetKey<G>, attempt: Attempt) -> bool {
         let location = key.location();
-        if (attempt.duration() >= Duration::seconds_f64(2.0))
+        if attempt.is_significant()
             && !self
                 .track_range
                 .is_some_and(|(start, end)| location < start || location > end)
         {
             self.attempts.entry(key).or_default().push(attempt);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Load previously-recorded attempts, such as those from a saved session.
+    pub(super) fn load_attempts(&mut self, attempts: &[(SetKey<G>, Attempt)]) {
+        for (key, attempt) in attempts {
+            self.attempts.entry(*key).or_default().push(*attempt);
         }
     }
 }
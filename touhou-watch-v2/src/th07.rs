@@ -4,6 +4,7 @@ use touhou::memory::ResolveLocation;
 use touhou::th07::memory::{GameMemory, GameState, ReadResult, RunState};
 use touhou::Touhou7;
 
+use crate::persist::{GameSession, SessionData};
 use crate::set_track::{ActiveGame, Metrics, SetTracker};
 use crate::watcher::{GameReader, TrackedGame};
 
@@ -51,8 +52,11 @@ impl State {
             interesting = self.tracking.update_location(location);
         }
 
-        if player.total_misses() > self.misses || player.total_bombs() > self.bombs {
-            self.tracking.mark_failed();
+        if player.total_misses() > self.misses {
+            self.tracking.mark_miss();
+            interesting = true;
+        } else if player.total_bombs() > self.bombs {
+            self.tracking.mark_bomb();
             interesting = true;
         }
 
@@ -114,10 +118,15 @@ impl TrackedGame for Touhou7 {
     fn get_tracker_mut(metrics: &mut Metrics) -> &mut SetTracker<Self> {
         metrics.th07_mut()
     }
+
+    fn get_session_mut(data: &mut SessionData) -> &mut GameSession<Self> {
+        data.th07_mut()
+    }
 }
 
 impl GameReader<Touhou7> for MemoryWrapper {
     fn is_in_game(&mut self) -> ReadResult<Option<bool>> {
+        self.memory.advance();
         self.memory
             .access()
             .map(GameState::game_is_active)
@@ -2,6 +2,7 @@ use touhou::memory::ResolveLocation;
 use touhou::th08::memory::{GameMemory, GameState, GameType, ReadResult, RunState};
 use touhou::Touhou8;
 
+use crate::persist::{GameSession, SessionData};
 use crate::set_track::{ActiveGame, Metrics, SetTracker};
 use crate::watcher::{GameReader, TrackedGame};
 
@@ -24,6 +25,10 @@ impl TrackedGame for Touhou8 {
     fn get_tracker_mut(metrics: &mut Metrics) -> &mut SetTracker<Self> {
         metrics.th08_mut()
     }
+
+    fn get_session_mut(data: &mut SessionData) -> &mut GameSession<Self> {
+        data.th08_mut()
+    }
 }
 
 #[derive(Debug)]
@@ -66,11 +71,11 @@ impl State {
             interesting = self.tracking.update_location(location);
         }
 
-        if player.total_misses() > self.misses
-            || player.bombs() < self.bombs
-            || player.total_bombs() > self.total_bombs
-        {
-            self.tracking.mark_failed();
+        if player.total_misses() > self.misses {
+            self.tracking.mark_miss();
+            interesting = true;
+        } else if player.bombs() < self.bombs || player.total_bombs() > self.total_bombs {
+            self.tracking.mark_bomb();
             interesting = true;
         }
 
@@ -96,6 +101,7 @@ pub struct ReadWrapper {
 
 impl GameReader<Touhou8> for ReadWrapper {
     fn is_in_game(&mut self) -> ReadResult<Option<bool>> {
+        self.memory.advance();
         self.memory
             .access()
             .map(GameState::run_is_active)
@@ -103,6 +109,7 @@ impl GameReader<Touhou8> for ReadWrapper {
     }
 
     fn reset(&mut self) {
+        self.memory.advance();
         match self.memory.access().map(GameState::new).transpose() {
             Ok(Some(GameState::GameOver { cleared, game })) => match game {
                 GameType::Main(run) => {
@@ -3,6 +3,7 @@ use touhou::th10::memory::{GameMemory, GameState, RunState};
 use touhou::th10::ReadResult;
 use touhou::{ShotPower, Touhou10};
 
+use crate::persist::{GameSession, SessionData};
 use crate::set_track::{ActiveGame, Metrics, SetTracker};
 use crate::watcher::{GameReader, TrackedGame};
 
@@ -19,9 +20,7 @@ impl State {
         let mut tracking = ActiveGame::new(memory_state);
 
         if let Some(location) = memory_state.resolve_location() {
-            if !location.is_stage_section() {
-                tracking.update_location(location);
-            }
+            tracking.update_location(location);
         }
 
         Self {
@@ -41,15 +40,14 @@ impl State {
         }
 
         if let Some(location) = memory_state.resolve_location() {
-            if !location.is_stage_section() {
-                interesting = self.tracking.update_location(location);
-            } else {
-                self.tracking.exit_location();
-            };
+            interesting = self.tracking.update_location(location);
         }
 
-        if player.lives() < self.lives || player.power() < self.power {
-            self.tracking.mark_failed();
+        if player.lives() < self.lives {
+            self.tracking.mark_miss();
+            interesting = true;
+        } else if player.power() < self.power {
+            self.tracking.mark_bomb();
             interesting = true;
         }
 
@@ -90,10 +88,15 @@ impl TrackedGame for Touhou10 {
     fn get_tracker_mut(metrics: &mut Metrics) -> &mut SetTracker<Self> {
         metrics.th10_mut()
     }
+
+    fn get_session_mut(data: &mut SessionData) -> &mut GameSession<Self> {
+        data.th10_mut()
+    }
 }
 
 impl GameReader<Touhou10> for ReadWrapper {
     fn is_in_game(&mut self) -> ReadResult<Option<bool>> {
+        self.reader.advance();
         self.reader.access().map(GameState::is_in_game).transpose()
     }
 
@@ -8,7 +8,9 @@ use tauri::Window;
 use touhou::memory::{HasLocations, MemoryReadError};
 
 use crate::event_serialize::AttachEvent;
+use crate::persist::{GameSession, SessionData};
 use crate::run_track::{GameSpecificEvent, GameSpecificState};
+use crate::server::{EventServer, ServerEvent};
 use crate::set_track::{Metrics, SetTracker};
 
 pub trait TrackedGame: Debug + HasLocations {
@@ -19,6 +21,7 @@ pub trait TrackedGame: Debug + HasLocations {
     fn autodetect_process() -> Result<Option<Self::Reader>, MemoryReadError<Self>>;
     fn get_tracker(metrics: &Metrics) -> &SetTracker<Self>;
     fn get_tracker_mut(metrics: &mut Metrics) -> &mut SetTracker<Self>;
+    fn get_session_mut(data: &mut SessionData) -> &mut GameSession<Self>;
 }
 
 pub trait GameReader<G: TrackedGame>: Debug + Sized {
@@ -28,6 +31,11 @@ pub trait GameReader<G: TrackedGame>: Debug + Sized {
     fn pid(&self) -> u32;
 }
 
+fn report_error(window: &Window, message: String) {
+    window.emit("error", &message).unwrap();
+    EventServer::get().broadcast(ServerEvent::Error { message });
+}
+
 #[derive(Debug)]
 struct Watcher<G: TrackedGame>(G::Reader);
 
@@ -36,9 +44,9 @@ impl<G: TrackedGame> Watcher<G> {
         loop {
             match G::autodetect_process() {
                 Ok(Some(reader)) => {
-                    window
-                        .emit("attached", AttachEvent::from_reader::<G>(&reader))
-                        .unwrap();
+                    let event = AttachEvent::from_reader::<G>(&reader);
+                    window.emit("attached", event).unwrap();
+                    EventServer::get().broadcast(ServerEvent::Attached(event));
 
                     eprintln!(
                         "Attached to {}, PID {}",
@@ -49,7 +57,7 @@ impl<G: TrackedGame> Watcher<G> {
                     return Self(reader);
                 }
                 Ok(None) => {}
-                Err(e) => window.emit("error", e.to_string()).unwrap(),
+                Err(e) => report_error(window, e.to_string()),
             }
 
             sleep(Duration::from_millis(100));
@@ -80,9 +88,9 @@ impl<G: TrackedGame> Watcher<G> {
 
         loop {
             match self.0.is_in_game() {
-                Err(e) => window.emit("error", e.to_string()).unwrap(),
+                Err(e) => report_error(window, e.to_string()),
                 Ok(Some(true)) => match self.0.update() {
-                    Err(e) => window.emit("error", e.to_string()).unwrap(),
+                    Err(e) => report_error(window, e.to_string()),
                     Ok(true) => window.emit("updated", G::GAME_ID).unwrap(),
                     Ok(false) => {}
                 },
@@ -100,7 +108,7 @@ impl<G: TrackedGame> Watcher<G> {
         loop {
             window.emit("updated", G::GAME_ID).unwrap();
             match self.wait_for_game() {
-                Err(e) => window.emit("error", e.to_string()).unwrap(),
+                Err(e) => report_error(window, e.to_string()),
                 Ok(false) => break,
                 Ok(true) => {
                     if !self.watch_game(window) {
@@ -114,9 +122,9 @@ impl<G: TrackedGame> Watcher<G> {
 
         window.emit("updated", G::GAME_ID).unwrap();
 
-        window
-            .emit("detached", AttachEvent::new(G::GAME_ID, pid))
-            .unwrap();
+        let event = AttachEvent::new(G::GAME_ID, pid);
+        window.emit("detached", event).unwrap();
+        EventServer::get().broadcast(ServerEvent::Detached(event));
     }
 }
 
@@ -0,0 +1,176 @@
+//! Measures how long it takes to read a full [`RunState`] from each game in a single poll.
+//!
+//! The games themselves only run on Windows, so this forks a stand-in process and maps a fake copy
+//! of each game's memory at the addresses its memory reader expects, populated with a run in progress.
+//!
+//! Run with `cargo bench -p touhou --features memory,th07,th08,th10`.
+
+#[cfg(target_os = "linux")]
+mod fake_game {
+    use std::fmt::Debug;
+    use std::time::{Duration, Instant};
+
+    use sysinfo::{Pid, PidExt, System, SystemExt};
+    use touhou::memory::ProcessAttached;
+
+    /// The range of addresses mapped in the fake game process.
+    ///
+    /// This covers the static data of every benchmarked game, plus room for the structures they point to.
+    const MAPPED_START: usize = 0x0040_0000;
+    const MAPPED_LEN: usize = 0x0200_0000;
+
+    /// Where pointed-to structures (such as the player state) are placed within the mapped range.
+    const HEAP: u32 = 0x0200_0000;
+
+    const POLLS: u32 = 20_000;
+
+    fn write<T: Copy>(addr: u32, value: T) {
+        // SAFETY: this is only called in the fake game process, after mapping the range containing `addr`.
+        unsafe { (addr as usize as *mut T).write_unaligned(value) }
+    }
+
+    /// A forked process with a game's memory layout, which lives until it is dropped.
+    struct FakeGame(libc::pid_t);
+
+    impl FakeGame {
+        fn spawn(populate: fn()) -> Self {
+            let mut fds = [0; 2];
+            assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+
+            // SAFETY: the benchmark is single-threaded, and the child never returns from this function.
+            let pid = unsafe { libc::fork() };
+            assert!(pid >= 0, "fork failed: {}", std::io::Error::last_os_error());
+
+            if pid == 0 {
+                unsafe {
+                    let mapped = libc::mmap(
+                        MAPPED_START as *mut libc::c_void,
+                        MAPPED_LEN,
+                        libc::PROT_READ | libc::PROT_WRITE,
+                        libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_FIXED_NOREPLACE,
+                        -1,
+                        0,
+                    );
+
+                    if mapped as usize != MAPPED_START {
+                        libc::_exit(1);
+                    }
+
+                    populate();
+                    libc::write(fds[1], [1u8].as_ptr().cast(), 1);
+                    loop {
+                        libc::pause();
+                    }
+                }
+            }
+
+            // the child only writes to the pipe once its memory is ready
+            let mut ready = 0u8;
+            let read = unsafe {
+                libc::close(fds[1]);
+                let read = libc::read(fds[0], (&mut ready as *mut u8).cast(), 1);
+                libc::close(fds[0]);
+                read
+            };
+
+            let game = Self(pid);
+            assert_eq!(read, 1, "could not map fake game memory");
+            game
+        }
+
+        fn attach<T: ProcessAttached>(&self) -> T {
+            let pid = Pid::from_u32(self.0 as u32);
+            let mut system = System::new();
+            system.refresh_process(pid);
+            T::from_process(system.process(pid).unwrap()).unwrap()
+        }
+    }
+
+    impl Drop for FakeGame {
+        fn drop(&mut self) {
+            unsafe {
+                libc::kill(self.0, libc::SIGKILL);
+                libc::waitpid(self.0, std::ptr::null_mut(), 0);
+            }
+        }
+    }
+
+    fn measure<T: ProcessAttached, R, E: Debug>(
+        name: &str,
+        populate: fn(),
+        poll: fn(&T) -> Result<R, E>,
+    ) {
+        let game = FakeGame::spawn(populate);
+        let mut access: T = game.attach();
+
+        let mut elapsed = Duration::ZERO;
+        for _ in 0..POLLS {
+            let start = Instant::now();
+            access.clear_cache();
+            std::hint::black_box(poll(&access).unwrap());
+            elapsed += start.elapsed();
+        }
+
+        println!(
+            "{} RunState::new: {:.2} µs/poll ({} polls)",
+            name,
+            elapsed.as_secs_f64() * 1e6 / POLLS as f64,
+            POLLS
+        );
+    }
+
+    fn th07_memory() {
+        write(0x0062_f85c, 1u32); // stage
+        write(0x0057_5aa8, 2u32); // game state
+        write(0x0062_f648, 0x04u8); // game mode
+        write(0x0062_6280, 1u32); // difficulty
+        write(0x009a_9af8, 0x0010_0000u32); // ECL pointer
+        write(0x0010_0000 + 0x0095_45fc, 1000u32); // ECL time
+        write(0x0062_f888, 50_000u32); // cherry max
+
+        write(0x0062_6278, HEAP); // player pointer
+        write(HEAP + 0x5c, 2.0f32); // lives
+        write(HEAP + 0x68, 3.0f32); // bombs
+        write(HEAP + 0x04, 1_000_000u32); // score
+    }
+
+    fn th08_memory() {
+        write(0x017c_e8b4, 2u32); // program state
+        write(0x0164_d0b4, 0x04u32); // game mode
+        write(0x0160_f538, 1u8); // difficulty
+        write(0x0164_cfb4, 1000u32); // time
+        write(0x0160_f464, 1.0f32); // boss damage multiplier
+
+        write(0x0160_f510, HEAP); // player pointer
+        write(HEAP + 0x74, 2.0f32); // lives
+        write(HEAP + 0x80, 3.0f32); // bombs
+        write(HEAP, 100_000u32); // score
+        write(HEAP + 0x08, 100_000u32);
+        write(HEAP + 0x3c, 1000u32); // time
+        write(HEAP + 0x44, 1000u32);
+    }
+
+    fn th10_memory() {
+        write(0x0047_4c70, 2u32); // lives
+        write(0x0047_4c74, 1u32); // difficulty
+        write(0x0047_4c7c, 1u32); // stage
+        write(0x0047_4c84, 2u32); // game state
+        write(0x0047_4c44, 100_000u32); // score
+
+        write(0x0047_76f4, HEAP); // spell pointer
+        write(0x0047_770c, HEAP); // boss pointer
+    }
+
+    pub fn main() {
+        use touhou::{th07, th08, th10};
+
+        measure("th07", th07_memory, th07::memory::RunState::new);
+        measure("th08", th08_memory, th08::memory::RunState::new);
+        measure("th10", th10_memory, th10::memory::RunState::new);
+    }
+}
+
+fn main() {
+    #[cfg(target_os = "linux")]
+    fake_game::main();
+}
@@ -0,0 +1,291 @@
+//! Exports spell card and stage practice statistics from score files.
+//!
+//! Usage: `thscore [OPTIONS] <SCORE FILE>`
+//!
+//! Options:
+//! - `--format <table|csv|json>`: output format (default: `table`)
+//! - `--game <N>`: the game the score file is for (e.g. `7` or `th07`); detected automatically if not given,
+//!   in which case the errors from every game's parser are reported if none of them can load the file
+//! - `--practice`: export stage practice records instead of spell card records
+//! - `--all`: include spell cards that have never been attempted
+//! - `--stage <STAGE>`, `--difficulty <DIFFICULTY>`, `--shot <SHOT TYPE>`, `--spell-type <SPELL TYPE>`:
+//!   only export matching records; each of these can be given more than once
+
+use std::env;
+use std::fs;
+use std::io::{self, Cursor, Write};
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail, Context};
+use serde::Serialize;
+use touhou::score::export::{card_stats, practice_stats, CardStats, PracticeStats, RecordFilter};
+use touhou::score::HasScoreFile;
+use touhou::types::{GameId, SpellType};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Table,
+    Csv,
+    Json,
+}
+
+#[derive(Debug)]
+struct Options {
+    path: PathBuf,
+    format: Format,
+    game: Option<GameId>,
+    practice: bool,
+    all: bool,
+    filter: RecordFilter,
+}
+
+fn parse_spell_type(value: &str) -> anyhow::Result<SpellType> {
+    match value
+        .to_ascii_lowercase()
+        .replace(['-', '_', ' '], "")
+        .as_str()
+    {
+        "midboss" => Ok(SpellType::Midboss),
+        "boss" => Ok(SpellType::Boss),
+        "lastspell" => Ok(SpellType::LastSpell),
+        "lastword" => Ok(SpellType::LastWord),
+        _ => Err(anyhow!("unknown spell type {}", value)),
+    }
+}
+
+fn spell_type_name(spell_type: SpellType) -> &'static str {
+    match spell_type {
+        SpellType::Midboss => "midboss",
+        SpellType::Boss => "boss",
+        SpellType::LastSpell => "last_spell",
+        SpellType::LastWord => "last_word",
+    }
+}
+
+fn parse_game(value: &str) -> anyhow::Result<GameId> {
+    let number = value.trim_start_matches("th").parse::<u8>()?;
+    GameId::new(number).map_err(|e| e.into())
+}
+
+impl Options {
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> anyhow::Result<Self> {
+        let mut path = None;
+        let mut format = Format::Table;
+        let mut game = None;
+        let mut practice = false;
+        let mut all = false;
+        let mut filter = RecordFilter::new();
+
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| anyhow!("missing value for {}", arg))
+            };
+
+            match arg.as_str() {
+                "--format" => {
+                    format = match value()?.as_str() {
+                        "table" => Format::Table,
+                        "csv" => Format::Csv,
+                        "json" => Format::Json,
+                        other => bail!("unknown output format {}", other),
+                    }
+                }
+                "--game" => game = Some(parse_game(&value()?)?),
+                "--practice" => practice = true,
+                "--all" => all = true,
+                "--stage" => filter = filter.with_stage(value()?),
+                "--difficulty" => filter = filter.with_difficulty(value()?),
+                "--shot" => filter = filter.with_shot_type(value()?),
+                "--spell-type" => filter = filter.with_spell_type(parse_spell_type(&value()?)?),
+                _ if arg.starts_with("--") => bail!("unknown option {}", arg),
+                _ => {
+                    if path.replace(PathBuf::from(&arg)).is_some() {
+                        bail!("more than one score file given");
+                    }
+                }
+            }
+        }
+
+        Ok(Self {
+            path: path.ok_or_else(|| anyhow!("no score file given"))?,
+            format,
+            game,
+            practice,
+            all,
+            filter,
+        })
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn write_json<T: Serialize, W: Write>(dst: &mut W, rows: &[T]) -> anyhow::Result<()> {
+    serde_json::to_writer_pretty(&mut *dst, rows)?;
+    writeln!(dst)?;
+    Ok(())
+}
+
+fn write_cards<W: Write>(dst: &mut W, format: Format, rows: &[CardStats]) -> anyhow::Result<()> {
+    match format {
+        Format::Json => write_json(dst, rows)?,
+        Format::Csv => {
+            writeln!(
+                dst,
+                "game,card_id,card_name,stage,difficulty,spell_type,shot_type,attempts,captures,max_bonus"
+            )?;
+            for row in rows {
+                writeln!(
+                    dst,
+                    "{},{},{},{},{},{},{},{},{},{}",
+                    row.game.number(),
+                    row.card_id,
+                    csv_field(row.card_name),
+                    csv_field(row.stage),
+                    csv_field(row.difficulty),
+                    spell_type_name(row.spell_type),
+                    csv_field(row.shot_type),
+                    row.attempts,
+                    row.captures,
+                    row.max_bonus
+                )?;
+            }
+        }
+        Format::Table => {
+            for row in rows {
+                let rate = if row.attempts > 0 {
+                    (row.captures as f64) / (row.attempts as f64) * 100.0
+                } else {
+                    0.0
+                };
+
+                writeln!(
+                    dst,
+                    "#{:03} {:<70} {:<10} {:>5} / {:<5} ({:>5.1}%)  max bonus {}",
+                    row.card_id,
+                    row.card_name,
+                    row.shot_type,
+                    row.captures,
+                    row.attempts,
+                    rate,
+                    row.max_bonus
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn write_practices<W: Write>(
+    dst: &mut W,
+    format: Format,
+    rows: &[PracticeStats],
+) -> anyhow::Result<()> {
+    match format {
+        Format::Json => write_json(dst, rows)?,
+        Format::Csv => {
+            writeln!(dst, "game,stage,difficulty,shot_type,attempts,high_score")?;
+            for row in rows {
+                writeln!(
+                    dst,
+                    "{},{},{},{},{},{}",
+                    row.game.number(),
+                    csv_field(row.stage),
+                    csv_field(row.difficulty),
+                    csv_field(row.shot_type),
+                    row.attempts,
+                    row.high_score
+                )?;
+            }
+        }
+        Format::Table => {
+            for row in rows {
+                writeln!(
+                    dst,
+                    "{:<15} {:<10} {:<10} {:>5} attempts, high score {}",
+                    row.stage, row.difficulty, row.shot_type, row.attempts, row.high_score
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn export<G: HasScoreFile>(score_file: &G::ScoreFile, options: &Options) -> anyhow::Result<()> {
+    let mut stdout = io::stdout().lock();
+
+    if options.practice {
+        let rows = practice_stats(score_file, &options.filter);
+        write_practices(&mut stdout, options.format, &rows)
+    } else {
+        let mut rows = card_stats(score_file, &options.filter);
+        if !options.all {
+            rows.retain(|row| row.attempts > 0);
+        }
+        write_cards(&mut stdout, options.format, &rows)
+    }
+}
+
+macro_rules! dispatch_games {
+    ($data:ident, $options:ident, $($feature:literal => $game:ident($id:ident)),*) => {{
+        let mut errors = Vec::new();
+
+        $(
+            #[cfg(feature = $feature)]
+            if $options.game.is_none() || $options.game == Some(GameId::$id) {
+                match <touhou::$game as HasScoreFile>::load_score_file(Cursor::new(&$data[..])) {
+                    Ok(score_file) => return export::<touhou::$game>(&score_file, &$options),
+                    Err(e) => errors.push((GameId::$id, e)),
+                }
+            }
+        )*
+
+        load_error(errors)
+    }};
+}
+
+/// Explain why a score file couldn't be loaded, given the error from each parser that was tried.
+fn load_error(mut errors: Vec<(GameId, io::Error)>) -> anyhow::Result<()> {
+    match errors.len() {
+        0 => Err(anyhow!(
+            "support for this game was not enabled at compile time"
+        )),
+        1 => Err(errors.pop().unwrap().1).context("could not load score file"),
+        _ => {
+            let details: String = errors
+                .iter()
+                .map(|(game, e)| format!("\n  {}: {}", game.abbreviation(), e))
+                .collect();
+
+            Err(anyhow!(
+                "could not load score file as any supported game (use --game to pick one):{}",
+                details
+            ))
+        }
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let options = Options::parse(env::args().skip(1))?;
+    let data = fs::read(&options.path)
+        .with_context(|| format!("could not read {}", options.path.display()))?;
+
+    // Newer games' score files have distinct headers, so try loading those first. If every parser
+    // fails, all of their errors are reported.
+    dispatch_games!(
+        data,
+        options,
+        "th15" => Touhou15(LoLK),
+        "th10" => Touhou10(MoF),
+        "th08" => Touhou8(IN),
+        "th07" => Touhou7(PCB)
+    )
+}
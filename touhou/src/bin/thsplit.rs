@@ -0,0 +1,223 @@
+//! Autosplitter for running Touhou games, driving LiveSplit through the LiveSplit Server component.
+//!
+//! Usage: `thsplit [OPTIONS]`
+//!
+//! Options:
+//! - `--address <ADDR>`: the address LiveSplit Server is listening on (default: `127.0.0.1:16834`)
+//! - `--game <N>`: only watch for the given game (e.g. `7` or `th07`); can be given more than once
+//! - `--boss-splits`: also split at the start of each boss fight
+//! - `--no-stage-splits`: don't split at the end of each stage
+//! - `--no-pause`: don't pause the timer while the game is paused
+//! - `--no-reset`: don't reset the timer when a game is exited without clearing it
+//! - `--poll-interval <MS>`: how often to read game memory, in milliseconds (default: 100)
+
+use std::env;
+use std::thread;
+use std::thread::sleep;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context};
+use touhou::memory::{GameMemory, MemoryReadError};
+use touhou::tracking::livesplit::{
+    LiveSplitClient, LiveSplitTracker, SplitConfig, DEFAULT_ADDRESS,
+};
+use touhou::tracking::{IntoGameTracker, TrackableGame};
+use touhou::types::GameId;
+
+#[derive(Debug)]
+struct Options {
+    address: String,
+    games: Vec<GameId>,
+    stage_splits: bool,
+    boss_splits: bool,
+    pause_timer: bool,
+    reset_on_exit: bool,
+    poll_interval: Duration,
+}
+
+fn parse_game(value: &str) -> anyhow::Result<GameId> {
+    let number = value.trim_start_matches("th").parse::<u8>()?;
+    GameId::new(number).map_err(|e| e.into())
+}
+
+impl Options {
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> anyhow::Result<Self> {
+        let mut ret = Self {
+            address: DEFAULT_ADDRESS.to_string(),
+            games: Vec::new(),
+            stage_splits: true,
+            boss_splits: false,
+            pause_timer: true,
+            reset_on_exit: true,
+            poll_interval: Duration::from_millis(100),
+        };
+
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| anyhow!("missing value for {}", arg))
+            };
+
+            match arg.as_str() {
+                "--address" => ret.address = value()?,
+                "--game" => ret.games.push(parse_game(&value()?)?),
+                "--boss-splits" => ret.boss_splits = true,
+                "--no-stage-splits" => ret.stage_splits = false,
+                "--no-pause" => ret.pause_timer = false,
+                "--no-reset" => ret.reset_on_exit = false,
+                "--poll-interval" => {
+                    ret.poll_interval = Duration::from_millis(
+                        value()?.parse().context("invalid poll interval")?,
+                    )
+                }
+                _ => bail!("unknown option {}", arg),
+            }
+        }
+
+        Ok(ret)
+    }
+
+    fn watches(&self, game: GameId) -> bool {
+        self.games.is_empty() || self.games.contains(&game)
+    }
+
+    fn split_config<G: TrackableGame>(&self) -> SplitConfig<G> {
+        SplitConfig::new()
+            .stage_splits(self.stage_splits)
+            .boss_splits(self.boss_splits)
+            .pause_timer(self.pause_timer)
+            .reset_on_exit(self.reset_on_exit)
+    }
+}
+
+#[derive(Debug)]
+enum SplitError<G: TrackableGame> {
+    Memory(MemoryReadError<G>),
+    LiveSplit(std::io::Error),
+}
+
+impl<G: TrackableGame> From<MemoryReadError<G>> for SplitError<G> {
+    fn from(value: MemoryReadError<G>) -> Self {
+        Self::Memory(value)
+    }
+}
+
+impl<G: TrackableGame> From<std::io::Error> for SplitError<G> {
+    fn from(value: std::io::Error) -> Self {
+        Self::LiveSplit(value)
+    }
+}
+
+fn track_process<G, M>(
+    memory: M,
+    client: &mut LiveSplitClient<G>,
+    poll_interval: Duration,
+) -> Result<(), SplitError<G>>
+where
+    G: TrackableGame,
+    M: GameMemory<G> + IntoGameTracker<G, LiveSplitTracker<G>>,
+{
+    let mut tracker = memory.track_games();
+
+    while tracker.is_running() {
+        if let Some(output) = tracker.update()? {
+            client.finish(output)?;
+        } else if let Some(current) = tracker.tracker_mut() {
+            client.update(current)?;
+        }
+
+        sleep(poll_interval);
+    }
+
+    if let (_, Some(output)) = tracker.close() {
+        client.finish(output)?;
+    }
+
+    Ok(())
+}
+
+fn watch_game<G, M, F>(mut new_memory: F, options: &Options)
+where
+    G: TrackableGame,
+    M: GameMemory<G> + IntoGameTracker<G, LiveSplitTracker<G>>,
+    F: FnMut() -> Result<Option<M>, MemoryReadError<G>>,
+{
+    let abbreviation = G::GAME_ID.abbreviation();
+
+    loop {
+        let memory = match new_memory() {
+            Ok(Some(memory)) => memory,
+            Ok(None) => {
+                sleep(Duration::from_millis(500));
+                continue;
+            }
+            Err(e) => {
+                eprintln!("{}: {}", abbreviation, e);
+                sleep(Duration::from_secs(5));
+                continue;
+            }
+        };
+
+        let mut client = match LiveSplitClient::connect(&options.address, options.split_config()) {
+            Ok(client) => client,
+            Err(e) => {
+                eprintln!(
+                    "{}: could not connect to LiveSplit at {}: {}",
+                    abbreviation, options.address, e
+                );
+                sleep(Duration::from_secs(5));
+                continue;
+            }
+        };
+
+        eprintln!("Attached to {}, PID {}", abbreviation, memory.pid());
+
+        match track_process(memory, &mut client, options.poll_interval) {
+            Ok(()) => eprintln!("{} exited", abbreviation),
+            Err(SplitError::Memory(e)) => eprintln!("{}: {}", abbreviation, e),
+            Err(SplitError::LiveSplit(e)) => {
+                eprintln!("{}: lost connection to LiveSplit: {}", abbreviation, e)
+            }
+        }
+
+        sleep(Duration::from_millis(500));
+    }
+}
+
+macro_rules! spawn_watchers {
+    ($scope:ident, $options:ident, $($feature:literal => $module:ident($id:ident)),*) => {{
+        let mut watching = 0;
+
+        $(
+            #[cfg(feature = $feature)]
+            if $options.watches(GameId::$id) {
+                let options = &$options;
+                $scope.spawn(move || watch_game(touhou::$module::GameMemory::new, options));
+                watching += 1;
+            }
+        )*
+
+        watching
+    }};
+}
+
+fn main() -> anyhow::Result<()> {
+    let options = Options::parse(env::args().skip(1))?;
+
+    thread::scope(|s| {
+        let watching = spawn_watchers!(
+            s,
+            options,
+            "th07" => th07(PCB),
+            "th08" => th08(IN),
+            "th10" => th10(MoF),
+            "th15" => th15(LoLK)
+        );
+
+        if watching == 0 {
+            Err(anyhow!("support for the selected games was not enabled at compile time"))
+        } else {
+            Ok(())
+        }
+    })
+}
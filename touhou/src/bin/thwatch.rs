@@ -0,0 +1,436 @@
+//! Watches for running Touhou games and logs tracked events and finished runs as newline-delimited JSON.
+//!
+//! Usage: `thwatch [OPTIONS]`
+//!
+//! Options:
+//! - `--output <FILE>`: append output to the given file instead of writing to stdout
+//! - `--game <N>`: only watch for the given game (e.g. `7` or `th07`); can be given more than once
+//! - `--runs-only`: only output finished runs, rather than every event and location change
+//! - `--poll-interval <MS>`: how often to read game memory, in milliseconds (default: 100)
+//!
+//! Each output line is a JSON object with a `type` field, which is one of `attached`, `detached`,
+//! `event`, `location`, `run_finished`, or `error`.
+
+use std::env;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use std::thread::sleep;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{anyhow, bail, Context};
+use serde::Serialize;
+use touhou::memory::{GameMemory, MemoryReadError};
+use touhou::tracking::recorder::{LocationSegment, RecordKind};
+use touhou::tracking::{
+    Event, EventTime, IntoGameTracker, RunRecorder, TrackableGame, TrackingType,
+};
+use touhou::types::GameId;
+use touhou::{GameValue, Location};
+
+#[derive(Debug)]
+struct Options {
+    output: Option<PathBuf>,
+    games: Vec<GameId>,
+    runs_only: bool,
+    poll_interval: Duration,
+}
+
+fn parse_game(value: &str) -> anyhow::Result<GameId> {
+    let number = value.trim_start_matches("th").parse::<u8>()?;
+    GameId::new(number).map_err(|e| e.into())
+}
+
+impl Options {
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> anyhow::Result<Self> {
+        let mut output = None;
+        let mut games = Vec::new();
+        let mut runs_only = false;
+        let mut poll_interval = Duration::from_millis(100);
+
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| anyhow!("missing value for {}", arg))
+            };
+
+            match arg.as_str() {
+                "--output" => output = Some(PathBuf::from(value()?)),
+                "--game" => games.push(parse_game(&value()?)?),
+                "--runs-only" => runs_only = true,
+                "--poll-interval" => {
+                    poll_interval = Duration::from_millis(
+                        value()?.parse().context("invalid poll interval")?,
+                    )
+                }
+                _ => bail!("unknown option {}", arg),
+            }
+        }
+
+        Ok(Self {
+            output,
+            games,
+            runs_only,
+            poll_interval,
+        })
+    }
+
+    fn watches(&self, game: GameId) -> bool {
+        self.games.is_empty() || self.games.contains(&game)
+    }
+}
+
+fn timestamp_ms(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+struct NamedValue<T> {
+    id: T,
+    name: &'static str,
+}
+
+impl<T> NamedValue<T> {
+    fn new<V: GameValue<RawValue = T>>(value: V) -> Self {
+        Self {
+            id: value.raw_id(),
+            name: value.name(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+struct LocationInfo {
+    index: u64,
+    name: &'static str,
+    stage: NamedValue<u16>,
+    spell: Option<NamedValue<u32>>,
+}
+
+impl LocationInfo {
+    fn new<G: TrackableGame>(location: Location<G>) -> Self {
+        Self {
+            index: location.index(),
+            name: location.name(),
+            stage: NamedValue::new(location.stage().unwrap()),
+            spell: location.spell().map(|spell| NamedValue::new(spell.unwrap())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+struct SegmentInfo {
+    location: LocationInfo,
+    start_play_time: f64,
+    end_play_time: f64,
+    misses: u32,
+    bombs: u32,
+    continues: u32,
+}
+
+impl SegmentInfo {
+    fn new<G: TrackableGame>(segment: &LocationSegment<G>) -> Self {
+        Self {
+            location: LocationInfo::new(segment.location),
+            start_play_time: segment.start_time.play_time().as_secs_f64(),
+            end_play_time: segment.end_time.play_time().as_secs_f64(),
+            misses: segment.misses,
+            bombs: segment.bombs,
+            continues: segment.continues,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Message {
+    Attached {
+        game: GameId,
+        pid: u32,
+        timestamp: u64,
+    },
+    Detached {
+        game: GameId,
+        pid: u32,
+        timestamp: u64,
+    },
+    Event {
+        game: GameId,
+        timestamp: u64,
+        play_time: f64,
+        location: Option<LocationInfo>,
+        event: &'static str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        data: Option<serde_json::Value>,
+    },
+    Location {
+        game: GameId,
+        timestamp: u64,
+        play_time: f64,
+        location: Option<LocationInfo>,
+    },
+    RunFinished {
+        game: GameId,
+        tracking_type: &'static str,
+        shot_type: NamedValue<u16>,
+        difficulty: NamedValue<u16>,
+        stage: Option<NamedValue<u16>>,
+        cleared: bool,
+        start_timestamp: u64,
+        end_timestamp: u64,
+        play_time: f64,
+        segments: Vec<SegmentInfo>,
+    },
+    Error {
+        game: GameId,
+        timestamp: u64,
+        message: String,
+    },
+}
+
+impl Message {
+    fn event<G>(time: EventTime, location: Option<Location<G>>, event: &Event<G>) -> Self
+    where
+        G: TrackableGame,
+        G::Event: Serialize,
+    {
+        let (event, data) = match event {
+            Event::Pause => ("pause", None),
+            Event::Unpause => ("unpause", None),
+            Event::Miss => ("miss", None),
+            Event::Bomb => ("bomb", None),
+            Event::Continue => ("continue", None),
+            Event::GameSpecific(data) => ("game_specific", serde_json::to_value(data).ok()),
+            _ => ("unknown", None),
+        };
+
+        Self::Event {
+            game: G::GAME_ID,
+            timestamp: timestamp_ms(time.timestamp()),
+            play_time: time.play_time().as_secs_f64(),
+            location: location.map(LocationInfo::new),
+            event,
+            data,
+        }
+    }
+
+    fn run_finished<G: TrackableGame>(run: &RunRecorder<G>) -> Self {
+        let end_time = run.end_time().unwrap_or_else(|| run.start_time());
+
+        Self::RunFinished {
+            game: G::GAME_ID,
+            tracking_type: match run.tracking_type() {
+                TrackingType::FullRun => "full_run",
+                TrackingType::StagePractice => "stage_practice",
+                TrackingType::SpellPractice => "spell_practice",
+            },
+            shot_type: NamedValue::new(run.shot().unwrap()),
+            difficulty: NamedValue::new(run.difficulty().unwrap()),
+            stage: run.stage().map(|stage| NamedValue::new(stage.unwrap())),
+            cleared: run.cleared(),
+            start_timestamp: timestamp_ms(run.start_time().timestamp()),
+            end_timestamp: timestamp_ms(end_time.timestamp()),
+            play_time: end_time.play_time().as_secs_f64(),
+            segments: run.segments().iter().map(SegmentInfo::new).collect(),
+        }
+    }
+}
+
+struct Output {
+    dst: Mutex<Box<dyn Write + Send>>,
+    runs_only: bool,
+}
+
+impl Output {
+    fn write(&self, message: &Message) {
+        if self.runs_only && matches!(message, Message::Event { .. } | Message::Location { .. }) {
+            return;
+        }
+
+        let mut dst = self.dst.lock().expect("could not lock output");
+        let result = serde_json::to_writer(&mut *dst, message)
+            .map_err(io::Error::from)
+            .and_then(|_| writeln!(dst))
+            .and_then(|_| dst.flush());
+
+        if let Err(e) = result {
+            eprintln!("could not write output: {}", e);
+        }
+    }
+
+    /// Write out any new records from a recorder, keeping track of the player's current location.
+    fn write_records<G>(&self, recorder: &mut RunRecorder<G>, location: &mut Option<Location<G>>)
+    where
+        G: TrackableGame,
+        G::Event: Serialize,
+    {
+        for record in recorder.take_new_records() {
+            match &record.kind {
+                RecordKind::Event(event) => {
+                    self.write(&Message::event(record.time, *location, event))
+                }
+                RecordKind::Location(new_location) => {
+                    *location = *new_location;
+                    self.write(&Message::Location {
+                        game: G::GAME_ID,
+                        timestamp: timestamp_ms(record.time.timestamp()),
+                        play_time: record.time.play_time().as_secs_f64(),
+                        location: new_location.map(LocationInfo::new),
+                    });
+                }
+            }
+        }
+    }
+
+    fn write_run<G>(&self, mut run: RunRecorder<G>, location: &mut Option<Location<G>>)
+    where
+        G: TrackableGame,
+        G::Event: Serialize,
+    {
+        self.write_records(&mut run, location);
+        self.write(&Message::run_finished(&run));
+        *location = None;
+    }
+}
+
+fn track_process<G, M>(
+    memory: M,
+    output: &Output,
+    poll_interval: Duration,
+) -> Result<(), MemoryReadError<G>>
+where
+    G: TrackableGame,
+    G::Event: Serialize,
+    M: GameMemory<G> + IntoGameTracker<G, RunRecorder<G>>,
+{
+    let mut tracker = memory.track_games();
+    let mut location = None;
+
+    while tracker.is_running() {
+        if let Some(run) = tracker.update()? {
+            output.write_run(run, &mut location);
+        } else if let Some(recorder) = tracker.tracker_mut() {
+            output.write_records(recorder, &mut location);
+        }
+
+        sleep(poll_interval);
+    }
+
+    if let (_, Some(run)) = tracker.close() {
+        output.write_run(run, &mut location);
+    }
+
+    Ok(())
+}
+
+fn watch_game<G, M, F>(mut new_memory: F, output: &Output, poll_interval: Duration)
+where
+    G: TrackableGame,
+    G::Event: Serialize,
+    M: GameMemory<G> + IntoGameTracker<G, RunRecorder<G>>,
+    F: FnMut() -> Result<Option<M>, MemoryReadError<G>>,
+{
+    let report_error = |e: MemoryReadError<G>| {
+        output.write(&Message::Error {
+            game: G::GAME_ID,
+            timestamp: timestamp_ms(SystemTime::now()),
+            message: e.to_string(),
+        })
+    };
+
+    loop {
+        let memory = match new_memory() {
+            Ok(Some(memory)) => memory,
+            Ok(None) => {
+                sleep(Duration::from_millis(500));
+                continue;
+            }
+            Err(e) => {
+                report_error(e);
+                sleep(Duration::from_secs(5));
+                continue;
+            }
+        };
+
+        let pid = memory.pid();
+        output.write(&Message::Attached {
+            game: G::GAME_ID,
+            pid,
+            timestamp: timestamp_ms(SystemTime::now()),
+        });
+
+        if let Err(e) = track_process(memory, output, poll_interval) {
+            report_error(e);
+        }
+
+        output.write(&Message::Detached {
+            game: G::GAME_ID,
+            pid,
+            timestamp: timestamp_ms(SystemTime::now()),
+        });
+
+        sleep(Duration::from_millis(500));
+    }
+}
+
+macro_rules! spawn_watchers {
+    ($scope:ident, $output:ident, $options:ident, $($feature:literal => $module:ident($id:ident)),*) => {{
+        let mut watching = 0;
+
+        $(
+            #[cfg(feature = $feature)]
+            if $options.watches(GameId::$id) {
+                let output = &$output;
+                let poll_interval = $options.poll_interval;
+                $scope.spawn(move || {
+                    watch_game(touhou::$module::GameMemory::new, output, poll_interval)
+                });
+                watching += 1;
+            }
+        )*
+
+        watching
+    }};
+}
+
+fn main() -> anyhow::Result<()> {
+    let options = Options::parse(env::args().skip(1))?;
+
+    let dst: Box<dyn Write + Send> = match &options.output {
+        Some(path) => Box::new(
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("could not open {}", path.display()))?,
+        ),
+        None => Box::new(io::stdout()),
+    };
+
+    let output = Output {
+        dst: Mutex::new(dst),
+        runs_only: options.runs_only,
+    };
+
+    thread::scope(|s| {
+        let watching = spawn_watchers!(
+            s,
+            output,
+            options,
+            "th07" => th07(PCB),
+            "th08" => th08(IN),
+            "th10" => th10(MoF),
+            "th15" => th15(LoLK)
+        );
+
+        if watching == 0 {
+            Err(anyhow!("support for the selected games was not enabled at compile time"))
+        } else {
+            Ok(())
+        }
+    })
+}
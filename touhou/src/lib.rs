@@ -3,12 +3,21 @@
 #[cfg(feature = "score-file")]
 pub mod score;
 
+#[cfg(feature = "replay-file")]
+pub mod replay;
+
+#[cfg(feature = "find-process")]
+pub mod process;
+
 #[cfg(feature = "memory")]
 pub mod memory;
 
 #[cfg(feature = "tracking")]
 pub mod tracking;
 
+#[cfg(feature = "storage")]
+pub mod storage;
+
 #[cfg(feature = "th07")]
 pub mod th07;
 #[cfg(feature = "th08")]
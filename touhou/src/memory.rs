@@ -3,12 +3,20 @@
 //! Aside from extracting basic game state such as player lives and bomb counts,
 //! this module also provides support for working with player locations (for example, determining which section of a stage they're playing at the moment).
 
+#[cfg(feature = "replay")]
+use std::collections::VecDeque;
 use std::io;
+use std::path::PathBuf;
 
 use sysinfo::{Pid, PidExt, Process, ProcessExt, ProcessRefreshKind, System, SystemExt};
 
-use crate::types::Game;
+#[cfg(feature = "replay")]
+use self::replay::{MemoryFrame, MemoryRecorder};
+use crate::process::GamePaths;
+use crate::types::{Game, GameId};
 
+#[cfg(feature = "replay")]
+pub mod replay;
 #[doc(hidden)]
 pub mod traits;
 #[doc(hidden)]
@@ -56,7 +64,6 @@ macro_rules! define_state_struct {
             $($field_name: $field_type),*
         }
 
-        #[automatically_derived]
         impl $struct_name {
             $(
                 pub fn $field_name(&self) -> $field_type {
@@ -68,38 +75,95 @@ macro_rules! define_state_struct {
 }
 
 pub trait ProcessAttached: Sized {
-    fn from_pid(pid: u32) -> io::Result<Self>;
+    /// Attach to a running process.
+    ///
+    /// Implementations with per-version memory layouts detect the version of the process's
+    /// executable here, failing with an [`UnknownGameVersion`] error if it isn't recognized.
+    fn from_process(proc: &Process) -> io::Result<Self>;
+
+    /// Discard any memory cached from previous reads.
+    ///
+    /// This is called once per poll by [`advance`](Attached::advance), so that values read
+    /// during a single poll are consistent with each other but never stale across polls.
+    fn clear_cache(&mut self) {}
     fn is_attachable_process(proc: &Process) -> bool;
+
+    /// Read the values of every memory field into a frame for recording.
+    #[cfg(feature = "replay")]
+    fn record_frame(&self) -> MemoryFrame;
+
+    /// Create an instance that reads memory field values from a recorded frame.
+    #[cfg(feature = "replay")]
+    fn from_frame(frame: MemoryFrame) -> Self;
+}
+
+/// Where the memory fields of a game are actually read from.
+#[doc(hidden)]
+#[derive(Debug)]
+pub enum MemorySource<T> {
+    Process(T),
+    #[cfg(feature = "replay")]
+    Replay(MemoryFrame),
+}
+
+#[derive(Debug)]
+enum AttachedSource {
+    Process {
+        system: Box<System>,
+        pid: Pid,
+    },
+    #[cfg(feature = "replay")]
+    Replay {
+        frames: VecDeque<MemoryFrame>,
+        loaded: bool,
+    },
 }
 
 #[derive(Debug)]
 pub struct Attached<T> {
-    system: System,
-    pid: Pid,
+    source: AttachedSource,
     inner: T,
+    #[cfg(feature = "replay")]
+    recorder: Option<MemoryRecorder>,
 }
 
 impl<T: ProcessAttached> Attached<T> {
     fn find_process(system: &System) -> Option<&Process> {
         system
             .processes()
-            .iter()
-            .map(|(_, process)| process)
+            .values()
             .find(|proc| T::is_attachable_process(proc))
             .filter(|proc| proc.run_time() > 15)
     }
 
+    fn attach(system: System, pid: Pid) -> io::Result<Self> {
+        let inner = system
+            .process(pid)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("no process with PID {}", pid),
+                )
+            })
+            .and_then(T::from_process)?;
+
+        Ok(Self {
+            source: AttachedSource::Process {
+                system: Box::new(system),
+                pid,
+            },
+            inner,
+            #[cfg(feature = "replay")]
+            recorder: None,
+        })
+    }
+
     pub fn new() -> io::Result<Option<Self>> {
         let mut system = System::new();
         system.refresh_processes_specifics(ProcessRefreshKind::new());
 
-        if let Some(proc) = Self::find_process(&system) {
-            let inner = T::from_pid(proc.pid().as_u32())?;
-            Ok(Some(Self {
-                pid: proc.pid(),
-                system,
-                inner,
-            }))
+        if let Some(pid) = Self::find_process(&system).map(|proc| proc.pid()) {
+            Self::attach(system, pid).map(Some)
         } else {
             Ok(None)
         }
@@ -108,29 +172,296 @@ impl<T: ProcessAttached> Attached<T> {
     pub fn from_pid(pid: u32) -> io::Result<Self> {
         let mut system = System::new();
         system.refresh_processes_specifics(ProcessRefreshKind::new());
-        T::from_pid(pid).map(|inner| Self {
-            pid: Pid::from_u32(pid),
-            system,
-            inner,
-        })
+        Self::attach(system, Pid::from_u32(pid))
+    }
+
+    /// Create an instance that replays previously recorded memory frames.
+    ///
+    /// Each call to [`advance`](Attached::advance) will step to the next frame, and the replay
+    /// will be treated as a process that has exited once all frames are exhausted.
+    #[cfg(feature = "replay")]
+    pub fn from_frames<I: IntoIterator<Item = MemoryFrame>>(frames: I) -> Self {
+        Self {
+            source: AttachedSource::Replay {
+                frames: frames.into_iter().collect(),
+                loaded: false,
+            },
+            inner: T::from_frame(MemoryFrame::new()),
+            recorder: None,
+        }
+    }
+
+    /// Create a new instance attached to the same process, or a copy of this instance's replay state.
+    pub fn try_clone(&self) -> io::Result<Self> {
+        match &self.source {
+            AttachedSource::Process { pid, .. } => Self::from_pid(pid.as_u32()),
+            #[cfg(feature = "replay")]
+            AttachedSource::Replay { frames, loaded } => Ok(Self {
+                source: AttachedSource::Replay {
+                    frames: frames.clone(),
+                    loaded: *loaded,
+                },
+                inner: T::from_frame(self.inner.record_frame()),
+                recorder: None,
+            }),
+        }
     }
 
+    /// Get the PID of the attached process.
+    ///
+    /// Replayed recordings always have a PID of 0.
     pub fn pid(&self) -> u32 {
-        self.pid.as_u32()
+        match &self.source {
+            AttachedSource::Process { pid, .. } => pid.as_u32(),
+            #[cfg(feature = "replay")]
+            AttachedSource::Replay { .. } => 0,
+        }
+    }
+
+    fn process(&self) -> Option<&Process> {
+        match &self.source {
+            AttachedSource::Process { system, pid } => system.process(*pid),
+            #[cfg(feature = "replay")]
+            AttachedSource::Replay { .. } => None,
+        }
+    }
+
+    /// Get the path to the attached process's executable.
+    ///
+    /// Replayed recordings have no associated executable.
+    pub fn executable_path(&self) -> Option<PathBuf> {
+        self.process().and_then(crate::process::executable_path)
     }
 
+    /// Get the locations of the files used by the attached process, assuming it is running the given game.
+    pub fn game_paths(&self, game: GameId) -> Option<GamePaths> {
+        self.process()
+            .and_then(|proc| GamePaths::from_process(game, proc))
+    }
+
+    /// Get whether the attached process is still running.
+    ///
+    /// Replayed recordings are treated as running for as long as there are frames left to
+    /// [`advance`](Attached::advance) to.
     pub fn is_running(&mut self) -> bool {
-        self.system
-            .refresh_process_specifics(self.pid, ProcessRefreshKind::new())
+        match &mut self.source {
+            AttachedSource::Process { system, pid } => {
+                system.refresh_process_specifics(*pid, ProcessRefreshKind::new())
+            }
+            #[cfg(feature = "replay")]
+            AttachedSource::Replay { frames, .. } => !frames.is_empty(),
+        }
+    }
+
+    /// Move on to a new poll of the attached process.
+    ///
+    /// This should be called once per poll, before any values are read through [`access`](Attached::access).
+    /// If a recording is in progress, the values of every field are recorded here; when replaying a recording,
+    /// this steps to the next recorded frame instead.
+    ///
+    /// Returns `false` if the process has exited, or if a replay has run out of frames.
+    pub fn advance(&mut self) -> bool {
+        #[cfg(feature = "replay")]
+        if let AttachedSource::Replay { frames, loaded } = &mut self.source {
+            *loaded = match frames.pop_front() {
+                Some(frame) => {
+                    self.inner = T::from_frame(frame);
+                    true
+                }
+                None => false,
+            };
+
+            return *loaded;
+        }
+
+        if self.is_running() {
+            self.inner.clear_cache();
+
+            #[cfg(feature = "replay")]
+            if let Some(recorder) = &mut self.recorder {
+                recorder.record(&self.inner.record_frame());
+            }
+
+            true
+        } else {
+            false
+        }
     }
 
+    /// Access the memory of the attached process.
+    ///
+    /// This can be called any number of times per poll; reads share the memory cached since the last call to
+    /// [`advance`](Attached::advance). When replaying a recording, this always reads from the frame most recently
+    /// stepped to by `advance`.
     pub fn access(&mut self) -> Option<&T> {
+        #[cfg(feature = "replay")]
+        if let AttachedSource::Replay { loaded, .. } = &self.source {
+            return loaded.then_some(&self.inner);
+        }
+
         if self.is_running() {
             Some(&self.inner)
         } else {
             None
         }
     }
+
+    /// Access the memory of the attached process, discarding any memory cached since the last poll first.
+    ///
+    /// This is meant for one-off reads made without calling [`advance`](Attached::advance) beforehand; unlike
+    /// `advance`, it does not record a frame. When replaying a recording, this is the same as [`access`](Attached::access).
+    pub fn access_uncached(&mut self) -> Option<&T> {
+        #[cfg(feature = "replay")]
+        if let AttachedSource::Replay { loaded, .. } = &self.source {
+            return loaded.then_some(&self.inner);
+        }
+
+        if self.is_running() {
+            self.inner.clear_cache();
+            Some(&self.inner)
+        } else {
+            None
+        }
+    }
+
+    /// Start recording memory values to a file each time [`advance`](Attached::advance) is called.
+    ///
+    /// Any recording already in progress will be finished first.
+    #[cfg(feature = "replay")]
+    pub fn start_recording<P: AsRef<std::path::Path>>(&mut self, path: P) -> io::Result<()> {
+        self.finish_recording()?;
+        self.recorder = Some(MemoryRecorder::create(path)?);
+        Ok(())
+    }
+
+    /// Stop recording memory values, flushing any buffered frames to disk.
+    ///
+    /// This returns any errors that occurred while writing frames during the recording.
+    #[cfg(feature = "replay")]
+    pub fn finish_recording(&mut self) -> io::Result<()> {
+        self.recorder.take().map_or(Ok(()), MemoryRecorder::finish)
+    }
 }
 
 pub(crate) use {define_state_struct, ensure_float_within_range};
+
+#[cfg(all(test, feature = "th07"))]
+mod tests {
+    use touhou_macros::define_memory;
+
+    use std::num::NonZeroUsize;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use sysinfo::{PidExt, ProcessRefreshKind, System, SystemExt};
+    use touhou_process::Pid;
+
+    use super::{Attached, AttachedSource, MemorySource, ProcessAttached};
+    use crate::th07::Touhou7;
+
+    define_memory! {
+        TestMemory {
+            process_name = "th07",
+            game = Touhou7,
+            access = TestAccess,
+
+            versions = TestVersion {
+                Old = 0x1000_0000,
+                New = 0x2000_0000,
+            },
+
+            signatures = {
+                player = "A1 ?? ?? ?? ?? 8B 40 5C" + 1,
+                score_base = "8B 0D ?? ?? ?? ?? 85 C9" + 2,
+            },
+
+            stage: u32 @ [0x0062f85c],
+            score: u32 @ {
+                Old: [0x00626278, 0x04],
+                New: [score_base, 0x04],
+            },
+            player_lives: f32 @ [player, 0x5c],
+            player_flags: u32 @ [player],
+        }
+    }
+
+    #[test]
+    fn versioned_layouts() {
+        assert_eq!(
+            TestVersion::from_pe_timestamp(0x2000_0000),
+            Some(TestVersion::New)
+        );
+        assert_eq!(TestVersion::Old.pe_timestamp(), 0x1000_0000);
+        assert_eq!(TestVersion::from_pe_timestamp(0x3000_0000), None);
+    }
+
+    #[test]
+    fn versioned_offsets() {
+        let handle = Pid::from(std::process::id())
+            .try_into_process_handle()
+            .unwrap();
+        let player = NonZeroUsize::new(0x0040_1000).unwrap();
+
+        let score_base = NonZeroUsize::new(0x0040_2000).unwrap();
+
+        let old = TestAccessFields::new(handle, TestVersion::Old, Some(player), None);
+        assert_eq!(old.version, TestVersion::Old);
+        assert_eq!(old.score.offsets(), [0x00626278, 0x04]);
+
+        let new = TestAccessFields::new(handle, TestVersion::New, Some(player), Some(score_base));
+        assert_eq!(new.version, TestVersion::New);
+        assert_eq!(new.score.offsets(), [0x0040_2000, 0x04]);
+
+        assert_eq!(old.stage.offsets(), new.stage.offsets());
+        assert_eq!(new.player_lives.offsets(), [0x0040_1000, 0x5c]);
+    }
+
+    #[test]
+    fn resolve_used_signatures() {
+        // contains the `player` signature, pointing at 0x00401000, but not `score_base`
+        static IMAGE: [u8; 8] = [0xA1, 0x00, 0x10, 0x40, 0x00, 0x8B, 0x40, 0x5C];
+
+        let handle = Pid::from(std::process::id())
+            .try_into_process_handle()
+            .unwrap();
+        let start = IMAGE.as_ptr() as usize;
+        let image = start..(start + IMAGE.len());
+
+        let old = TestAccessFields::resolve(handle, TestVersion::Old, image.clone()).unwrap();
+        assert_eq!(old.score.offsets(), [0x00626278, 0x04]);
+        assert_eq!(old.player_lives.offsets(), [0x0040_1000, 0x5c]);
+
+        let err = TestAccessFields::resolve(handle, TestVersion::New, image)
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn wrapper_reads_current_values() {
+        static FLAGS: AtomicU32 = AtomicU32::new(1);
+
+        let pid = sysinfo::Pid::from_u32(std::process::id());
+        let mut system = System::new();
+        system.refresh_process_specifics(pid, ProcessRefreshKind::new());
+
+        let handle = Pid::from(std::process::id())
+            .try_into_process_handle()
+            .unwrap();
+        let player = NonZeroUsize::new(FLAGS.as_ptr() as usize).unwrap();
+        let fields = TestAccessFields::new(handle, TestVersion::New, Some(player), Some(player));
+
+        let mut memory = TestMemory(Attached {
+            source: AttachedSource::Process {
+                system: Box::new(system),
+                pid,
+            },
+            inner: TestAccess(MemorySource::Process(fields)),
+            #[cfg(feature = "replay")]
+            recorder: None,
+        });
+
+        assert_eq!(memory.player_flags().unwrap(), Some(1));
+        FLAGS.store(2, Ordering::SeqCst);
+        assert_eq!(memory.player_flags().unwrap(), Some(2));
+    }
+}
@@ -0,0 +1,153 @@
+//! Support for recording game memory to disk and replaying it later.
+//!
+//! Recordings are stored as [JSON Lines](https://jsonlines.org/) files, where each line is a [`MemoryFrame`]
+//! containing the values of every field declared for a game's memory reader, as read during a single poll
+//! of the game process.
+//!
+//! Replayed recordings can be fed through the same memory reader types (and thus the same state readers and
+//! [trackers](crate::tracking)) used for live processes, which makes it possible to test them without having
+//! a running game on hand.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, ErrorKind, Write};
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use super::MemoryReadError;
+use crate::types::Game;
+
+/// The values of all memory fields for a game, as read during a single poll.
+///
+/// Fields that couldn't be read are stored alongside the error message that was produced at the time.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct MemoryFrame(BTreeMap<String, Result<serde_json::Value, String>>);
+
+impl MemoryFrame {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the result of reading a field.
+    pub fn insert<T: Serialize, E: Display>(&mut self, field: &str, value: Result<T, E>) {
+        let value = value
+            .map_err(|e| e.to_string())
+            .and_then(|v| serde_json::to_value(v).map_err(|e| e.to_string()));
+        self.0.insert(field.to_string(), value);
+    }
+
+    /// Get the recorded value of a field.
+    ///
+    /// Fields that failed to read while recording will return an error containing the original error message.
+    pub fn get<G: Game, T: DeserializeOwned>(&self, field: &str) -> Result<T, MemoryReadError<G>> {
+        match self.0.get(field) {
+            Some(Ok(value)) => T::deserialize(value).map_err(|e| {
+                MemoryReadError::other(format!("could not decode recorded field {}: {}", field, e))
+            }),
+            Some(Err(msg)) => Err(MemoryReadError::other(msg)),
+            None => Err(MemoryReadError::other(format!(
+                "no recorded value for field {}",
+                field
+            ))),
+        }
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = &str> + '_ {
+        self.0.keys().map(String::as_str)
+    }
+}
+
+/// Read all frames from a memory recording.
+pub fn read_frames<R: BufRead>(reader: R) -> io::Result<VecDeque<MemoryFrame>> {
+    reader
+        .lines()
+        .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+        .map(|line| {
+            line.and_then(|line| {
+                serde_json::from_str(&line).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+            })
+        })
+        .collect()
+}
+
+/// Read all frames from a memory recording file.
+pub fn read_frames_from_file<P: AsRef<Path>>(path: P) -> io::Result<VecDeque<MemoryFrame>> {
+    File::open(path).map(BufReader::new).and_then(read_frames)
+}
+
+/// Writes memory frames to a recording file.
+#[derive(Debug)]
+pub struct MemoryRecorder {
+    writer: BufWriter<File>,
+    error: Option<io::Error>,
+}
+
+impl MemoryRecorder {
+    /// Create a new recording file at the given path, overwriting any existing file.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        File::create(path).map(|file| Self {
+            writer: BufWriter::new(file),
+            error: None,
+        })
+    }
+
+    /// Append a frame to the recording.
+    ///
+    /// If an error occurs, the recorder stops writing frames, and the error is returned from [`finish`](MemoryRecorder::finish).
+    pub fn record(&mut self, frame: &MemoryFrame) {
+        if self.error.is_none() {
+            if let Err(e) = self.write_frame(frame) {
+                self.error = Some(e);
+            }
+        }
+    }
+
+    fn write_frame(&mut self, frame: &MemoryFrame) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, frame)?;
+        self.writer.write_all(b"\n")
+    }
+
+    /// Finish the recording, flushing any buffered frames to disk.
+    pub fn finish(mut self) -> io::Result<()> {
+        if let Some(e) = self.error.take() {
+            Err(e)
+        } else {
+            self.writer.flush()
+        }
+    }
+}
+
+#[cfg(all(test, feature = "th07"))]
+mod tests {
+    use super::*;
+    use crate::th07::Touhou7;
+
+    #[test]
+    fn test_frame_round_trip() {
+        let mut frame = MemoryFrame::new();
+        frame.insert("stage", Ok::<u32, &str>(3));
+        frame.insert("player_lives", Ok::<f32, &str>(2.0));
+        frame.insert("bgm_filename", Ok::<[u8; 4], &str>(*b"th07"));
+        frame.insert("ecl_time", Err::<u32, _>("encountered null pointer"));
+
+        let line = serde_json::to_string(&frame).unwrap();
+        let frames = read_frames(format!("{}\n\n{}\n", line, line).as_bytes()).unwrap();
+        assert_eq!(frames.len(), 2);
+
+        let frame = &frames[0];
+        assert_eq!(frame.get::<Touhou7, u32>("stage").unwrap(), 3);
+        assert_eq!(frame.get::<Touhou7, f32>("player_lives").unwrap(), 2.0);
+        assert_eq!(
+            frame.get::<Touhou7, [u8; 4]>("bgm_filename").unwrap(),
+            *b"th07"
+        );
+
+        let err = frame.get::<Touhou7, u32>("ecl_time").unwrap_err();
+        assert!(err.to_string().contains("encountered null pointer"));
+        assert!(frame.get::<Touhou7, u32>("boss_id").is_err());
+    }
+}
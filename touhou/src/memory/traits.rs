@@ -1,9 +1,12 @@
 //! Traits representing the different kinds of game state that can be extracted from running Touhou processes.
 
+use std::path::PathBuf;
+
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
 use super::types::SpellState;
+use crate::process::GamePaths;
 use crate::types::Game;
 use crate::{Difficulty, Location, ShotPower, ShotType, SpellCard, Stage};
 
@@ -11,10 +14,25 @@ pub trait GameMemory<G: Game>: Sized {
     type MemoryAccess;
 
     fn pid(&self) -> u32;
+
+    /// Move on to a new poll of the game's memory.
+    ///
+    /// This should be called once per poll before reading values through [`access`](GameMemory::access).
+    /// Replayed recordings step to their next frame here, and return `false` once they run out of frames.
+    fn advance(&mut self) -> bool;
+
     fn access(&mut self) -> Option<&Self::MemoryAccess>;
     fn is_running(&mut self) -> bool {
         self.access().is_some()
     }
+
+    /// Get the path to the attached process's executable, if it can be determined.
+    ///
+    /// See [`crate::process::executable_path`] for details.
+    fn executable_path(&self) -> Option<PathBuf>;
+
+    /// Get the locations of the score file, replays, and configuration used by the attached process.
+    fn game_paths(&self) -> Option<GamePaths>;
 }
 
 /// Top-level trait for accessing the state of an active Touhou game.
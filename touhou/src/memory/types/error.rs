@@ -93,9 +93,51 @@ impl<G: Game> Display for InvalidLocationData<G> {
     }
 }
 
+/// The executable a game process is running doesn't match any of the memory layouts known for that game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnknownGameVersion {
+    game: GameId,
+    timestamp: u32,
+}
+
+impl UnknownGameVersion {
+    pub const fn new(game: GameId, timestamp: u32) -> Self {
+        Self { game, timestamp }
+    }
+
+    pub const fn game(&self) -> GameId {
+        self.game
+    }
+
+    /// The PE header timestamp of the unrecognized executable.
+    pub const fn timestamp(&self) -> u32 {
+        self.timestamp
+    }
+}
+
+impl Error for UnknownGameVersion {}
+
+impl Display for UnknownGameVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Unsupported {} executable version (PE timestamp {:#010x})",
+            self.game.abbreviation(),
+            self.timestamp
+        )
+    }
+}
+
+impl From<UnknownGameVersion> for IOError {
+    fn from(value: UnknownGameVersion) -> Self {
+        IOError::new(ErrorKind::Unsupported, value)
+    }
+}
+
 #[derive(Debug)]
 pub enum MemoryReadError<G: Game> {
     IO(IOError),
+    UnknownVersion(UnknownGameVersion),
     InvalidStage(InvalidStageId<G>),
     InvalidShotType(InvalidShotType<G>),
     InvalidPowerValue(InvalidShotPower<G>),
@@ -150,6 +192,7 @@ impl<G: Game> From<MemoryReadError<G>> for IOError {
     fn from(value: MemoryReadError<G>) -> Self {
         match value {
             MemoryReadError::IO(err) => err,
+            MemoryReadError::UnknownVersion(err) => err.into(),
             MemoryReadError::InvalidStage(err) => IOError::new(ErrorKind::InvalidData, err),
             MemoryReadError::InvalidShotType(err) => IOError::new(ErrorKind::InvalidData, err),
             MemoryReadError::InvalidPowerValue(err) => IOError::new(ErrorKind::InvalidData, err),
@@ -157,14 +200,27 @@ impl<G: Game> From<MemoryReadError<G>> for IOError {
             MemoryReadError::InvalidSpellCard(err) => IOError::new(ErrorKind::InvalidData, err),
             MemoryReadError::InvalidFloat(err) => IOError::new(ErrorKind::InvalidData, err),
             MemoryReadError::InvalidOther(err) => IOError::new(ErrorKind::InvalidData, err),
-            MemoryReadError::Other(s) => IOError::new(ErrorKind::Other, s),
+            MemoryReadError::Other(s) => IOError::other(s),
         }
     }
 }
 
 impl<G: Game> From<IOError> for MemoryReadError<G> {
     fn from(value: IOError) -> Self {
-        Self::IO(value)
+        // attaching to a process only reports IO errors, so version mismatches are smuggled through them
+        match value
+            .get_ref()
+            .and_then(|err| err.downcast_ref::<UnknownGameVersion>())
+        {
+            Some(err) => Self::UnknownVersion(*err),
+            None => Self::IO(value),
+        }
+    }
+}
+
+impl<G: Game> From<UnknownGameVersion> for MemoryReadError<G> {
+    fn from(value: UnknownGameVersion) -> Self {
+        Self::UnknownVersion(value)
     }
 }
 
@@ -209,6 +265,7 @@ impl<G: Game> Display for MemoryReadError<G> {
         f.write_str("memory read error: ")?;
         match self {
             Self::IO(err) => err.fmt(f),
+            Self::UnknownVersion(err) => err.fmt(f),
             Self::InvalidStage(err) => err.fmt(f),
             Self::InvalidShotType(err) => err.fmt(f),
             Self::InvalidPowerValue(err) => err.fmt(f),
@@ -225,6 +282,7 @@ impl<G: Game> Error for MemoryReadError<G> {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
             Self::IO(err) => Some(err),
+            Self::UnknownVersion(err) => Some(err),
             Self::InvalidStage(err) => Some(err),
             Self::InvalidShotType(err) => Some(err),
             Self::InvalidPowerValue(err) => Some(err),
@@ -8,16 +8,17 @@ use super::error::InvalidLocationData;
 use super::{GameLocation, HasLocations};
 use crate::types::errors::InvalidStageId;
 use crate::types::{GameId, GameValue, SpellCard, Stage};
-use crate::AllIterable;
 
 /// Represents a gameplay location within a running Touhou game.
 ///
 /// What exactly counts as a 'location' heavily depends on the game
 /// and what memory-reading capabilities have been implemented for it:
 /// - [PCB](`crate::th07::memory`) and [IN](`crate::th08::memory`) have the most fine-grained location definitions (bugs aside),
-/// covering nonspell attacks, spell cards, and sections within stages; the stage sections generally match up with thprac's stage/section warp functionality.
-/// - The memory reader for [MoF](`crate::th10::memory`) currently lacks the ability to distinguish sections within stages; nonetheless,
-/// the reader can fully resolve both nonspell and spell card attacks for both midbosses and stage bosses.
+///   covering nonspell attacks, spell cards, and sections within stages; the stage sections generally match up with thprac's stage/section warp functionality.
+/// - The memory reader for [MoF](`crate::th10::memory`) fully resolves nonspell and spell card attacks for both midbosses and stage bosses,
+///   but only splits the rest of each stage into the sections before and after the midboss.
+/// - The memory reader for [LoLK](`crate::th15::memory`) resolves stage sections by the game's own chapter numbers,
+///   and resolves spell cards (but not nonspells) for midbosses and stage bosses.
 #[derive(Debug)]
 #[repr(transparent)]
 pub struct Location<G: HasLocations>(G::Location);
@@ -76,7 +77,7 @@ impl<G: HasLocations> Ord for Location<G> {
 
 impl<G: HasLocations> PartialOrd for Location<G> {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.0.cmp(&other.0))
+        Some(self.cmp(other))
     }
 }
 
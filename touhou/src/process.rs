@@ -0,0 +1,391 @@
+//! Helpers for finding running Touhou game processes.
+//!
+//! The games are all Windows executables, but they're also commonly played on Linux via Wine or Proton.
+//! In that case, the OS reports the Wine loader (for example `wine64-preloader`) as the executable for the
+//! game process, so naively checking executable names won't work; the functions in this module account for that.
+
+use std::fs::File;
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use sysinfo::{Process, ProcessExt, System, SystemExt};
+
+use crate::types::GameId;
+
+const WINE_LOADERS: [&str; 4] = ["wine", "wine64", "wine-preloader", "wine64-preloader"];
+
+/// Gets the last component of a path, accepting both Windows and Unix-style separators.
+fn file_name(path: &str) -> &str {
+    path.rsplit(['\\', '/']).next().unwrap_or(path)
+}
+
+fn strip_exe_extension(name: &str) -> Option<&str> {
+    let split = name.len().checked_sub(4)?;
+    if name.get(split..)?.eq_ignore_ascii_case(".exe") {
+        name.get(..split)
+    } else {
+        None
+    }
+}
+
+fn is_wine_loader(name: &str) -> bool {
+    WINE_LOADERS.contains(&name)
+}
+
+/// Gets the value of an environment variable for a process.
+fn process_env<'a>(proc: &'a Process, key: &str) -> Option<&'a str> {
+    proc.environ().iter().find_map(|var| {
+        var.split_once('=')
+            .filter(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value)
+    })
+}
+
+/// Gets the Wine prefix used by a process, defaulting to `~/.wine` if `WINEPREFIX` isn't set.
+fn wine_prefix(proc: &Process) -> Option<PathBuf> {
+    process_env(proc, "WINEPREFIX")
+        .map(PathBuf::from)
+        .or_else(|| process_env(proc, "HOME").map(|home| Path::new(home).join(".wine")))
+}
+
+/// Converts a path as seen by a program running under Wine into a native path.
+///
+/// Paths with drive letters are resolved through the prefix's `dosdevices` directory,
+/// and relative paths are resolved against the given working directory.
+fn resolve_wine_path(prefix: &Path, cwd: &Path, path: &str) -> PathBuf {
+    let path = path.replace('\\', "/");
+
+    match path.as_bytes() {
+        [drive, b':', rest @ ..] if drive.is_ascii_alphabetic() => {
+            let rest = std::str::from_utf8(rest).unwrap().trim_start_matches('/');
+            prefix
+                .join("dosdevices")
+                .join(format!("{}:", drive.to_ascii_lowercase() as char))
+                .join(rest)
+        }
+        _ => cwd.join(path),
+    }
+}
+
+/// Gets the file name of the Windows executable run by a process (for example, `th07.exe`).
+///
+/// For native processes, this is just the file name of the process's executable.
+/// For processes running under Wine, the game executable is instead found from the command line,
+/// falling back to the process name if the command line doesn't reference an `.exe` file.
+pub fn executable_name(proc: &Process) -> Option<String> {
+    let exe = proc.exe().file_name().and_then(|s| s.to_str())?;
+
+    if !is_wine_loader(exe) {
+        return Some(exe.to_string());
+    }
+
+    proc.cmd()
+        .iter()
+        .map(|arg| file_name(arg))
+        .find(|name| strip_exe_extension(name).is_some())
+        .or_else(|| Some(proc.name()).filter(|name| strip_exe_extension(name).is_some()))
+        .map(str::to_string)
+}
+
+/// Gets the name of the Windows executable run by a process without its extension (for example, `th07`).
+///
+/// See [`executable_name`] for details on how the executable is determined.
+pub fn executable_stem(proc: &Process) -> Option<String> {
+    executable_name(proc).map(|name| match strip_exe_extension(&name) {
+        Some(stem) => stem.to_string(),
+        None => name,
+    })
+}
+
+/// Checks whether an executable name (without extension) is one of the given names, ignoring case.
+///
+/// Names must match exactly, so that `th10` doesn't match spinoff games like `th105`.
+fn stem_matches(stem: &str, names: &[&str]) -> bool {
+    names.iter().any(|name| stem.eq_ignore_ascii_case(name))
+}
+
+/// Checks whether a process is running an executable named after any of the given names (without extension).
+pub fn is_game_process(proc: &Process, names: &[&str]) -> bool {
+    executable_stem(proc).is_some_and(|stem| stem_matches(&stem, names))
+}
+
+/// Gets the full path to the Windows executable run by a process.
+///
+/// For processes running under Wine, the path is taken from the command line and converted to a native path,
+/// using the process's `WINEPREFIX` to resolve paths with drive letters.
+pub fn executable_path(proc: &Process) -> Option<PathBuf> {
+    let exe = proc.exe();
+    let name = exe.file_name().and_then(|s| s.to_str())?;
+
+    if !is_wine_loader(name) {
+        return Some(exe.to_path_buf());
+    }
+
+    let arg = proc
+        .cmd()
+        .iter()
+        .find(|arg| strip_exe_extension(file_name(arg)).is_some())?;
+
+    if arg.starts_with('/') {
+        Some(PathBuf::from(arg))
+    } else {
+        wine_prefix(proc).map(|prefix| resolve_wine_path(&prefix, proc.cwd(), arg))
+    }
+}
+
+/// Gets the roaming AppData directory used by a process, if it has one.
+///
+/// Wine usually doesn't export `APPDATA` to the process's native environment, so for processes running
+/// under Wine this falls back to the default location for the current user within the prefix.
+fn appdata_dir(proc: &Process) -> Option<PathBuf> {
+    let appdata = process_env(proc, "APPDATA");
+
+    match proc.exe().file_name().and_then(|s| s.to_str()) {
+        Some(name) if is_wine_loader(name) => {
+            let prefix = wine_prefix(proc)?;
+            match appdata {
+                Some(appdata) => Some(resolve_wine_path(&prefix, proc.cwd(), appdata)),
+                None => process_env(proc, "USER").map(|user| wine_appdata_dir(&prefix, user)),
+            }
+        }
+        _ => appdata.map(PathBuf::from),
+    }
+}
+
+/// Gets the default roaming AppData directory for a user within a Wine prefix.
+///
+/// Prefixes created by older versions of Wine use the Windows XP layout (`Application Data`) instead.
+fn wine_appdata_dir(prefix: &Path, user: &str) -> PathBuf {
+    let user_dir = prefix.join("drive_c").join("users").join(user);
+    let roaming = user_dir.join("AppData").join("Roaming");
+    let legacy = user_dir.join("Application Data");
+
+    if !roaming.exists() && legacy.exists() {
+        legacy
+    } else {
+        roaming
+    }
+}
+
+/// Reads the link timestamp out of the PE header of a Windows executable.
+///
+/// Each release of a game's executable has a distinct timestamp, so this is a cheap way to tell
+/// which version of a game is installed without hashing the entire file.
+pub fn pe_timestamp<P: AsRef<Path>>(path: P) -> io::Result<u32> {
+    File::open(path).and_then(read_pe_timestamp)
+}
+
+fn read_pe_timestamp<R: Read + Seek>(mut reader: R) -> io::Result<u32> {
+    let mut buf = [0; 4];
+
+    reader.seek(SeekFrom::Start(0x3c))?;
+    reader.read_exact(&mut buf)?;
+    let header_offset = u32::from_le_bytes(buf);
+
+    reader.seek(SeekFrom::Start(header_offset as u64))?;
+    reader.read_exact(&mut buf)?;
+    if &buf != b"PE\0\0" {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            "executable does not have a PE header",
+        ));
+    }
+
+    // skip over the machine type and section count in the COFF header
+    reader.seek(SeekFrom::Current(4))?;
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+/// Finds a running process for the given game.
+pub fn find_game_process(system: &System, game: GameId) -> Option<&Process> {
+    let name = format!("th{:02}", game.number());
+    system
+        .processes()
+        .values()
+        .find(|&process| is_game_process(process, &[&name]))
+}
+
+/// The locations of the files used by an installed game.
+///
+/// Older games keep their score files, replays, and configuration next to the game executable.
+/// Newer games (starting with Touhou 10 when it is installed somewhere that isn't writable) keep them under
+/// `%APPDATA%\ShanghaiAlice\thNN` instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GamePaths {
+    game: GameId,
+    executable: PathBuf,
+    data_dir: PathBuf,
+}
+
+impl GamePaths {
+    /// Determine where a game keeps its files, given the path to its executable and the roaming AppData directory
+    /// (if known).
+    ///
+    /// For Touhou 10, this checks whether a score file exists next to the executable before falling back to AppData.
+    pub fn new<P: Into<PathBuf>>(game: GameId, executable: P, appdata: Option<&Path>) -> Self {
+        let executable = executable.into();
+        let install_dir = executable
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let appdata_dir = appdata.map(|dir| {
+            dir.join("ShanghaiAlice")
+                .join(format!("th{:02}", game.number()))
+        });
+
+        let data_dir = match (game, appdata_dir) {
+            (GameId::PCB | GameId::IN, _) | (_, None) => install_dir,
+            (GameId::MoF, Some(appdata_dir)) => {
+                if install_dir.join(score_file_name(game)).exists() {
+                    install_dir
+                } else {
+                    appdata_dir
+                }
+            }
+            (_, Some(appdata_dir)) => appdata_dir,
+        };
+
+        Self {
+            game,
+            executable,
+            data_dir,
+        }
+    }
+
+    /// Determine where the game run by a process keeps its files.
+    pub fn from_process(game: GameId, proc: &Process) -> Option<Self> {
+        executable_path(proc).map(|exe| Self::new(game, exe, appdata_dir(proc).as_deref()))
+    }
+
+    pub fn game(&self) -> GameId {
+        self.game
+    }
+
+    pub fn executable(&self) -> &Path {
+        &self.executable
+    }
+
+    /// The directory containing the game executable.
+    pub fn install_dir(&self) -> &Path {
+        self.executable.parent().unwrap_or(Path::new(""))
+    }
+
+    /// The directory containing the game's score file, replays, and configuration.
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    pub fn score_file(&self) -> PathBuf {
+        self.data_dir.join(score_file_name(self.game))
+    }
+
+    pub fn replay_dir(&self) -> PathBuf {
+        self.data_dir.join("replay")
+    }
+
+    pub fn config_file(&self) -> PathBuf {
+        self.data_dir
+            .join(format!("th{:02}.cfg", self.game.number()))
+    }
+}
+
+fn score_file_name(game: GameId) -> String {
+    match game {
+        GameId::PCB | GameId::IN => String::from("score.dat"),
+        _ => format!("scoreth{:02}.dat", game.number()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn wine_paths() {
+        let prefix = Path::new("/home/user/.wine");
+        let cwd = Path::new("/home/user/games/th07");
+
+        assert_eq!(
+            resolve_wine_path(prefix, cwd, "C:\\Games\\th07\\th07.exe"),
+            Path::new("/home/user/.wine/dosdevices/c:/Games/th07/th07.exe")
+        );
+        assert_eq!(
+            resolve_wine_path(prefix, cwd, "th07.exe"),
+            Path::new("/home/user/games/th07/th07.exe")
+        );
+    }
+
+    #[test]
+    fn wine_appdata() {
+        let prefix = std::env::temp_dir().join(format!("thstats-wine-{}", std::process::id()));
+        let user_dir = prefix.join("drive_c/users/user");
+
+        assert_eq!(
+            wine_appdata_dir(&prefix, "user"),
+            user_dir.join("AppData/Roaming")
+        );
+
+        std::fs::create_dir_all(user_dir.join("Application Data")).unwrap();
+        assert_eq!(
+            wine_appdata_dir(&prefix, "user"),
+            user_dir.join("Application Data")
+        );
+
+        std::fs::create_dir_all(user_dir.join("AppData/Roaming")).unwrap();
+        assert_eq!(
+            wine_appdata_dir(&prefix, "user"),
+            user_dir.join("AppData/Roaming")
+        );
+
+        std::fs::remove_dir_all(&prefix).unwrap();
+    }
+
+    #[test]
+    fn executable_stems() {
+        assert!(stem_matches("th10", &["th10"]));
+        assert!(stem_matches("TH10", &["th10"]));
+        assert!(stem_matches("th15", &["th10", "th15"]));
+        assert!(!stem_matches("th105", &["th10"]));
+        assert!(!stem_matches("th155", &["th15"]));
+        assert!(!stem_matches("th1", &["th10"]));
+    }
+
+    #[test]
+    fn game_paths() {
+        let appdata = Path::new("/appdata");
+
+        let paths = GamePaths::new(GameId::PCB, "/games/th07/th07.exe", Some(appdata));
+        assert_eq!(paths.score_file(), Path::new("/games/th07/score.dat"));
+        assert_eq!(paths.replay_dir(), Path::new("/games/th07/replay"));
+        assert_eq!(paths.config_file(), Path::new("/games/th07/th07.cfg"));
+
+        let paths = GamePaths::new(GameId::MoF, "/nonexistent/th10/th10.exe", Some(appdata));
+        assert_eq!(paths.install_dir(), Path::new("/nonexistent/th10"));
+        assert_eq!(
+            paths.score_file(),
+            Path::new("/appdata/ShanghaiAlice/th10/scoreth10.dat")
+        );
+
+        let paths = GamePaths::new(GameId::LoLK, "/games/th15/th15.exe", None);
+        assert_eq!(paths.score_file(), Path::new("/games/th15/scoreth15.dat"));
+    }
+
+    #[test]
+    fn pe_timestamps() {
+        let mut exe = vec![0u8; 0x90];
+        exe[..2].copy_from_slice(b"MZ");
+        exe[0x3c..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        exe[0x80..0x84].copy_from_slice(b"PE\0\0");
+        exe[0x88..0x8c].copy_from_slice(&0x3f7b_1c2du32.to_le_bytes());
+        assert_eq!(
+            read_pe_timestamp(io::Cursor::new(&exe)).unwrap(),
+            0x3f7b_1c2d
+        );
+
+        exe[0x80] = 0;
+        assert!(read_pe_timestamp(io::Cursor::new(&exe)).is_err());
+    }
+}
@@ -0,0 +1,148 @@
+//! Types and traits for reading replay files.
+
+use std::fmt::Debug;
+use std::io::{self, ErrorKind, Read};
+
+use crate::score::StreamDecompressor;
+use crate::types::{Difficulty, Game, ShotType, Stage};
+
+/// A change in the player's inputs recorded within a replay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct InputFrame {
+    frame: u32,
+    keys: u16,
+}
+
+impl InputFrame {
+    pub const fn new(frame: u32, keys: u16) -> Self {
+        Self { frame, keys }
+    }
+
+    /// The frame number (relative to the start of the stage) that this input was recorded on.
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    /// The raw bitmask of keys held on this frame.
+    pub fn keys(&self) -> u16 {
+        self.keys
+    }
+}
+
+/// The player's state at the start of a stage within a replay, along with the inputs recorded during that stage.
+#[derive(Debug)]
+pub struct StageReplay<G: Game> {
+    stage: Stage<G>,
+    score: u64,
+    power: u16,
+    lives: u8,
+    bombs: Option<u8>,
+    inputs: Vec<InputFrame>,
+}
+
+impl<G: Game> StageReplay<G> {
+    pub(crate) fn new(
+        stage: Stage<G>,
+        score: u64,
+        power: u16,
+        lives: u8,
+        bombs: Option<u8>,
+        inputs: Vec<InputFrame>,
+    ) -> Self {
+        Self {
+            stage,
+            score,
+            power,
+            lives,
+            bombs,
+            inputs,
+        }
+    }
+
+    pub fn stage(&self) -> Stage<G> {
+        self.stage
+    }
+
+    pub fn score(&self) -> u64 {
+        self.score
+    }
+
+    /// The raw shot power value, as stored by the game.
+    pub fn power(&self) -> u16 {
+        self.power
+    }
+
+    pub fn lives(&self) -> u8 {
+        self.lives
+    }
+
+    /// The player's bomb stock, for games that track bombs separately from other resources.
+    pub fn bombs(&self) -> Option<u8> {
+        self.bombs
+    }
+
+    pub fn inputs(&self) -> &[InputFrame] {
+        &self.inputs[..]
+    }
+}
+
+impl<G: Game> Clone for StageReplay<G> {
+    fn clone(&self) -> Self {
+        Self {
+            stage: self.stage,
+            score: self.score,
+            power: self.power,
+            lives: self.lives,
+            bombs: self.bombs,
+            inputs: self.inputs.clone(),
+        }
+    }
+}
+
+/// A type representing a parsed replay file.
+pub trait ReplayFile<G: Game>: Sized + Debug {
+    /// The raw player name stored in the replay, with any trailing null bytes removed.
+    fn name(&self) -> &[u8];
+    fn shot_type(&self) -> ShotType<G>;
+    fn difficulty(&self) -> Difficulty<G>;
+    fn score(&self) -> u64;
+    fn stages(&self) -> &[StageReplay<G>];
+}
+
+pub(crate) fn invalid_data<E>(error: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(ErrorKind::InvalidData, error)
+}
+
+/// Trims a null-terminated string buffer at its first null byte.
+pub(crate) fn trim_name(buf: &[u8]) -> &[u8] {
+    buf.split(|&x| x == 0).next().unwrap_or(buf)
+}
+
+/// Decrypts the simple additive cipher used by older games' replay files.
+pub(crate) fn decrypt_additive(buf: &mut [u8], mut key: u8) {
+    for x in buf.iter_mut() {
+        *x = x.wrapping_sub(key);
+        key = key.wrapping_add(7);
+    }
+}
+
+/// Decompresses a replay's body, checking that it has the expected size.
+pub(crate) fn decompress_body<R: Read>(src: R, size: usize) -> io::Result<Vec<u8>> {
+    let mut body = Vec::with_capacity(size);
+    StreamDecompressor::new(src)
+        .take(size as u64)
+        .read_to_end(&mut body)?;
+
+    if body.len() != size {
+        return Err(invalid_data(format!(
+            "expected {} bytes of decompressed replay data, got {}",
+            size,
+            body.len()
+        )));
+    }
+
+    Ok(body)
+}
@@ -1,16 +1,25 @@
-//! Types and traits for reading score file data.
+//! Types and traits for reading and writing score file data.
 
+mod compress;
 mod crypt;
 mod decompress;
+pub mod diff;
+mod error;
+pub mod export;
+mod segment;
+pub mod watch;
 
 use std::fmt::{Debug, Display};
-use std::io::{self, ErrorKind, Read};
+use std::io::{self, ErrorKind, Read, Write};
 use std::str;
 use std::str::FromStr;
 
 use anyhow::anyhow;
-pub use crypt::ThCrypt;
+pub use compress::StreamCompressor;
+pub use crypt::{ThCrypt, ThEncrypt};
 pub use decompress::StreamDecompressor;
+pub use error::ScoreParseError;
+pub(crate) use segment::{parse_segments, ShortSegments};
 
 use crate::types::{Difficulty, Game, ShotType, SpellCard, Stage};
 
@@ -30,6 +39,10 @@ impl ShortDate {
             .parse()
             .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
     }
+
+    pub fn write_to<W: Write>(&self, dst: &mut W) -> Result<(), io::Error> {
+        write!(dst, "{:02}/{:02}\0", self.month, self.day)
+    }
 }
 
 impl Display for ShortDate {
@@ -58,7 +71,13 @@ pub trait SpellCardRecord<G: Game>: Sized + Debug {
     fn shot_types(&self) -> &[ShotType<G>];
     fn attempts(&self, shot: &ShotType<G>) -> u32;
     fn captures(&self, shot: &ShotType<G>) -> u32;
-    fn max_bonus(&self, shot: &ShotType<G>) -> u32;
+
+    /// The highest bonus earned for this card with the given shot type.
+    ///
+    /// Score files from MoF onward don't record spell card bonuses, so those games keep this default of 0.
+    fn max_bonus(&self, _shot: &ShotType<G>) -> u32 {
+        0
+    }
 
     fn total_attempts(&self) -> u32 {
         self.shot_types()
@@ -115,7 +134,14 @@ pub trait SpellPracticeRecord<G: Game>: SpellCardRecord<G> {
 /// A type representing a stage practice record stored within a score file.
 pub trait PracticeRecord<G: Game>: Sized + Debug {
     fn high_score(&self) -> u32;
-    fn attempts(&self) -> u32;
+
+    /// The number of times this stage has been attempted in practice.
+    ///
+    /// Score files from MoF onward only store a high score and some flags per practice stage, so those games keep this default of 0.
+    fn attempts(&self) -> u32 {
+        0
+    }
+
     fn shot_type(&self) -> ShotType<G>;
     fn difficulty(&self) -> Difficulty<G>;
     fn stage(&self) -> Stage<G>;
@@ -129,3 +155,10 @@ pub trait ScoreFile<G: Game>: Sized + Debug {
     fn spell_cards(&self) -> &[Self::SpellCardRecord];
     fn practice_records(&self) -> &[Self::PracticeRecord];
 }
+
+/// A game with a score file format that can be read by this crate.
+pub trait HasScoreFile: Game {
+    type ScoreFile: ScoreFile<Self>;
+
+    fn load_score_file<R: Read>(src: R) -> Result<Self::ScoreFile, io::Error>;
+}
@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+const DICT_SIZE: usize = 0x2000;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 18;
+const MAX_CANDIDATES: usize = 32;
+
+#[derive(Debug)]
+struct BitWriter {
+    out: Vec<u8>,
+    cur_byte: u8,
+    cur_bit: u8,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        Self {
+            out: Vec::new(),
+            cur_byte: 0,
+            cur_bit: 0x80,
+        }
+    }
+
+    fn push_bit(&mut self, bit: bool) {
+        if bit {
+            self.cur_byte |= self.cur_bit;
+        }
+
+        if self.cur_bit == 1 {
+            self.out.push(self.cur_byte);
+            self.cur_byte = 0;
+            self.cur_bit = 0x80;
+        } else {
+            self.cur_bit >>= 1;
+        }
+    }
+
+    fn push_bits(&mut self, value: u16, n_bits: u32) {
+        for i in (0..n_bits).rev() {
+            self.push_bit(((value >> i) & 1) != 0);
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.cur_bit != 0x80 {
+            self.out.push(self.cur_byte);
+        }
+
+        self.out
+    }
+}
+
+/// Compresses data into the LZ77 format read by [`StreamDecompressor`](`super::StreamDecompressor`).
+///
+/// Data written to this type is buffered in memory; it is compressed and written to the underlying [`Write`] type
+/// when [`finish`](StreamCompressor::finish) is called.
+#[derive(Debug)]
+pub struct StreamCompressor<W> {
+    dst: W,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> StreamCompressor<W> {
+    /// Create a new compressor wrapping an underlying [`Write`] type.
+    pub fn new(dst: W) -> Self {
+        Self {
+            dst,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Compress all written data to the underlying writer, and return it along with the number of compressed bytes written.
+    pub fn finish(mut self) -> io::Result<(W, usize)> {
+        let compressed = compress(&self.buf);
+        self.dst.write_all(&compressed)?;
+        self.dst.flush()?;
+        Ok((self.dst, compressed.len()))
+    }
+}
+
+impl<W: Write> Write for StreamCompressor<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Gets the dictionary index used by the decompressor to refer to the byte at the given position in the output.
+fn dict_index(pos: usize) -> usize {
+    (pos + 1) & (DICT_SIZE - 1)
+}
+
+fn compress(data: &[u8]) -> Vec<u8> {
+    let mut out = BitWriter::new();
+    let mut chains: HashMap<&[u8], Vec<usize>> = HashMap::new();
+    let mut pos = 0;
+
+    while pos < data.len() {
+        let mut best: Option<(usize, usize)> = None;
+
+        if let Some(prefix) = data.get(pos..(pos + MIN_MATCH)) {
+            if let Some(candidates) = chains.get(prefix) {
+                for &candidate in candidates.iter().rev().take(MAX_CANDIDATES) {
+                    // Index 0 is used as an end-of-stream marker, so it can't be referenced.
+                    if (pos - candidate) >= DICT_SIZE || dict_index(candidate) == 0 {
+                        continue;
+                    }
+
+                    let len = data[pos..]
+                        .iter()
+                        .take(MAX_MATCH)
+                        .zip(&data[candidate..])
+                        .take_while(|(a, b)| a == b)
+                        .count();
+
+                    if len > best.map(|(_, best_len)| best_len).unwrap_or(0) {
+                        best = Some((candidate, len));
+                    }
+                }
+            }
+        }
+
+        let advance = match best {
+            Some((candidate, len)) if len >= MIN_MATCH => {
+                out.push_bit(false);
+                out.push_bits(dict_index(candidate) as u16, 13);
+                out.push_bits((len - MIN_MATCH) as u16, 4);
+                len
+            }
+            _ => {
+                out.push_bit(true);
+                out.push_bits(data[pos] as u16, 8);
+                1
+            }
+        };
+
+        for i in pos..(pos + advance) {
+            if let Some(prefix) = data.get(i..(i + MIN_MATCH)) {
+                chains.entry(prefix).or_default().push(i);
+            }
+        }
+
+        pos += advance;
+    }
+
+    // end-of-stream marker
+    out.push_bit(false);
+    out.push_bits(0, 13);
+
+    out.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+
+    use proptest::prelude::*;
+
+    use super::super::StreamDecompressor;
+    use super::StreamCompressor;
+
+    fn decompress(compressed: &[u8]) -> Vec<u8> {
+        let mut ret = Vec::new();
+        StreamDecompressor::new(compressed)
+            .read_to_end(&mut ret)
+            .unwrap();
+        ret
+    }
+
+    proptest! {
+        #[test]
+        fn round_trip(data in proptest::collection::vec(any::<u8>(), 0..0x4000)) {
+            let mut compressor = StreamCompressor::new(Vec::new());
+            compressor.write_all(&data).unwrap();
+            let (compressed, n) = compressor.finish().unwrap();
+
+            prop_assert_eq!(compressed.len(), n);
+            prop_assert_eq!(decompress(&compressed), data);
+        }
+
+        #[test]
+        fn round_trip_repetitive(
+            pattern in proptest::collection::vec(any::<u8>(), 1..32),
+            repeats in 1usize..0x400,
+        ) {
+            let data: Vec<u8> = pattern
+                .iter()
+                .copied()
+                .cycle()
+                .take(pattern.len() * repeats)
+                .collect();
+            let mut compressor = StreamCompressor::new(Vec::new());
+            compressor.write_all(&data).unwrap();
+            let (compressed, _) = compressor.finish().unwrap();
+
+            prop_assert_eq!(decompress(&compressed), data);
+        }
+    }
+}
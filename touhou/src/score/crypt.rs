@@ -1,4 +1,4 @@
-use std::io::Read;
+use std::io::{Read, Write};
 
 #[derive(Debug)]
 struct CryptState {
@@ -14,7 +14,9 @@ impl CryptState {
     fn decrypt_block(&mut self, input: &[u8], output: &mut [u8]) {
         assert_eq!(input.len(), output.len());
         assert_eq!(input.len() % 2, 0);
-        assert!(input.len() > 4);
+        if input.is_empty() {
+            return;
+        }
 
         /* Bytes from the first half are used to compute odd-indexed bytes in the output block.
          * Bytes from the second half correspond to even-indexed output bytes.
@@ -34,6 +36,31 @@ impl CryptState {
             }
         }
     }
+
+    /// The inverse of [`decrypt_block`](CryptState::decrypt_block).
+    fn encrypt_block(&mut self, input: &[u8], output: &mut [u8]) {
+        assert_eq!(input.len(), output.len());
+        assert_eq!(input.len() % 2, 0);
+        if input.is_empty() {
+            return;
+        }
+
+        let (first, second) = output.split_at_mut(output.len() / 2);
+        for (i, half) in [first, second].into_iter().enumerate() {
+            let mut in_idx = input.len() - 1 - i;
+            let half_len = half.len();
+
+            for (j, out_byte) in half.iter_mut().enumerate() {
+                *out_byte = input[in_idx] ^ self.key;
+
+                if j != (half_len - 1) {
+                    in_idx = in_idx.checked_sub(2).unwrap();
+                }
+
+                self.key = self.key.wrapping_add(self.step);
+            }
+        }
+    }
 }
 
 /// Decrypts the contents of modern Touhou games' score files as they're being read.
@@ -81,7 +108,7 @@ impl<R: Read> ThCrypt<R> {
                         .extend_from_slice(&self.in_buf[..self.in_cursor]);
                 } else if self.in_cursor % 2 == 1 {
                     /* Decrypt everything but the last byte, which is simply copied */
-                    self.out_buf.resize(self.in_cursor, 0);
+                    self.out_buf.resize(self.in_cursor - 1, 0);
                     let (last, block) = self.in_buf[..self.in_cursor].split_last().unwrap();
                     self.state
                         .decrypt_block(block, &mut self.out_buf[..self.in_cursor - 1]);
@@ -146,16 +173,14 @@ impl<R: Read> Read for ThCrypt<R> {
 
         self.n_read += n;
 
-        if let Some(limit) = self.limit {
-            if self.n_read >= limit {
+        while !buf.is_empty() {
+            if self.at_limit() {
                 let src_amt = self.src.read(buf)?;
                 n += src_amt;
                 self.n_read += src_amt;
-                return Ok(n);
+                break;
             }
-        }
 
-        while !buf.is_empty() {
             let done = self.decrypt_next_block()?;
             let next_amt = self.drain_to_buf(buf);
 
@@ -171,3 +196,169 @@ impl<R: Read> Read for ThCrypt<R> {
         Ok(n)
     }
 }
+
+/// Encrypts data written to it in the format read by [`ThCrypt`].
+///
+/// Since partial blocks at the end of the data are handled specially, [`finish`](ThEncrypt::finish)
+/// must be called once all data has been written.
+#[derive(Debug)]
+pub struct ThEncrypt<W> {
+    state: CryptState,
+    block_sz: usize,
+    in_buf: Vec<u8>,
+    out_buf: Box<[u8]>,
+    limit: Option<usize>,
+    n_written: usize,
+    dst: W,
+}
+
+impl<W: Write> ThEncrypt<W> {
+    /// Create a new instance wrapping an underlying [`Write`] type.
+    ///
+    /// The parameters should be the same as those passed to [`ThCrypt::new`] when reading the data back.
+    /// If a `limit` is given, it must be a multiple of the block size.
+    pub fn new(dst: W, key: u8, step: u8, block_sz: usize, limit: Option<usize>) -> Self {
+        assert!(block_sz >= 4);
+        assert_eq!(block_sz % 2, 0);
+        if let Some(limit) = limit {
+            assert_eq!(limit % block_sz, 0);
+        }
+
+        Self {
+            state: CryptState::new(key, step),
+            block_sz,
+            in_buf: Vec::with_capacity(block_sz),
+            out_buf: vec![0u8; block_sz].into(),
+            limit,
+            n_written: 0,
+            dst,
+        }
+    }
+
+    fn at_limit(&self) -> bool {
+        self.limit
+            .map(|limit| self.n_written >= limit)
+            .unwrap_or(false)
+    }
+
+    /// Encrypt and write out any remaining buffered data, and return the underlying writer.
+    pub fn finish(mut self) -> std::io::Result<W> {
+        let n = self.in_buf.len();
+
+        if n > 0 {
+            if n < self.block_sz / 4 {
+                self.dst.write_all(&self.in_buf)?;
+            } else {
+                let n_crypt = n - (n % 2);
+                self.state
+                    .encrypt_block(&self.in_buf[..n_crypt], &mut self.out_buf[..n_crypt]);
+                self.dst.write_all(&self.out_buf[..n_crypt])?;
+                self.dst.write_all(&self.in_buf[n_crypt..])?;
+            }
+
+            self.n_written += n;
+            self.in_buf.clear();
+        }
+
+        self.dst.flush()?;
+        Ok(self.dst)
+    }
+}
+
+impl<W: Write> Write for ThEncrypt<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.at_limit() {
+            let n = self.dst.write(buf)?;
+            self.n_written += n;
+            return Ok(n);
+        }
+
+        let amt = (self.block_sz - self.in_buf.len()).min(buf.len());
+        self.in_buf.extend_from_slice(&buf[..amt]);
+
+        if self.in_buf.len() == self.block_sz {
+            self.state.encrypt_block(&self.in_buf, &mut self.out_buf);
+            self.dst.write_all(&self.out_buf)?;
+            self.n_written += self.block_sz;
+            self.in_buf.clear();
+        }
+
+        Ok(amt)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.dst.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+
+    use proptest::prelude::*;
+
+    use super::{ThCrypt, ThEncrypt};
+
+    fn round_trip_bytes(
+        data: &[u8],
+        key: u8,
+        step: u8,
+        block_sz: usize,
+        limit: Option<usize>,
+    ) -> (Vec<u8>, Vec<u8>) {
+        let mut encrypt = ThEncrypt::new(Vec::new(), key, step, block_sz, limit);
+        encrypt.write_all(data).unwrap();
+        let encrypted = encrypt.finish().unwrap();
+
+        let mut decrypted = Vec::new();
+        ThCrypt::new(&encrypted[..], key, step, block_sz, limit)
+            .read_to_end(&mut decrypted)
+            .unwrap();
+        (encrypted, decrypted)
+    }
+
+    fn params() -> impl Strategy<Value = (usize, Option<usize>)> {
+        // 0x10 and 0x100 are the block sizes actually used by the games.
+        prop_oneof![
+            Just(0x10),
+            Just(0x100),
+            (2usize..0x200).prop_map(|half_sz| half_sz * 2)
+        ]
+        .prop_flat_map(|block_sz| {
+            (
+                Just(block_sz),
+                proptest::option::of((0usize..8).prop_map(move |n| n * block_sz)),
+            )
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn round_trip(
+            data in proptest::collection::vec(any::<u8>(), 0..0x2000),
+            key: u8,
+            step: u8,
+            (block_sz, limit) in params(),
+        ) {
+            let (encrypted, decrypted) = round_trip_bytes(&data, key, step, block_sz, limit);
+            prop_assert_eq!(encrypted.len(), data.len());
+            prop_assert_eq!(decrypted, data);
+        }
+    }
+
+    #[test]
+    fn short_tail_blocks() {
+        let data: Vec<u8> = (0..0x40).collect();
+
+        for block_sz in [4, 0x10] {
+            for len in 0..data.len() {
+                let (_, decrypted) = round_trip_bytes(&data[..len], 0x5C, 0xE1, block_sz, None);
+                assert_eq!(
+                    decrypted,
+                    &data[..len],
+                    "block size {block_sz}, length {len}"
+                );
+            }
+        }
+    }
+}
@@ -36,6 +36,11 @@ impl<R: ReadBytesExt> StreamDecompressor<R> {
         }
     }
 
+    /// Get a reference to the underlying reader wrapped within this instance.
+    pub fn get_ref(&self) -> &R {
+        &self.src
+    }
+
     fn ensure_next_byte(&mut self) -> io::Result<Option<u8>> {
         if self.cur_byte.is_none() {
             self.cur_byte = match self.src.read_u8() {
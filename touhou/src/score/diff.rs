@@ -0,0 +1,315 @@
+//! Comparing two snapshots of a score file.
+//!
+//! The functions here only rely on the [`ScoreFile`], [`SpellCardRecord`], and [`PracticeRecord`] traits,
+//! so they work with the score file types from any supported game.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use super::{PracticeRecord, ScoreFile, SpellCardRecord};
+use crate::types::{Difficulty, Game, ShotType, SpellCard, Stage};
+
+/// A change between two snapshots of a score file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ScoreEvent<G: Game> {
+    /// A spell card's attempt count increased for a shot type.
+    CardAttempted {
+        card: SpellCard<G>,
+        shot_type: ShotType<G>,
+        prev_attempts: u32,
+        attempts: u32,
+    },
+    /// A spell card's capture count increased for a shot type.
+    CardCaptured {
+        card: SpellCard<G>,
+        shot_type: ShotType<G>,
+        prev_captures: u32,
+        captures: u32,
+    },
+    /// A spell card's maximum capture bonus increased for a shot type.
+    NewMaxBonus {
+        card: SpellCard<G>,
+        shot_type: ShotType<G>,
+        prev_bonus: u32,
+        bonus: u32,
+    },
+    /// A stage practice high score was beaten.
+    PracticeHighScore {
+        shot_type: ShotType<G>,
+        difficulty: Difficulty<G>,
+        stage: Stage<G>,
+        prev_score: u32,
+        score: u32,
+    },
+    /// A stage practice record's play count changed.
+    PlayCountChanged {
+        shot_type: ShotType<G>,
+        difficulty: Difficulty<G>,
+        stage: Stage<G>,
+        prev_attempts: u32,
+        attempts: u32,
+    },
+}
+
+impl<G: Game> Display for ScoreEvent<G> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CardAttempted {
+                card,
+                shot_type,
+                attempts,
+                ..
+            } => write!(
+                f,
+                "{} attempted with {} ({} attempts)",
+                card, shot_type, attempts
+            ),
+            Self::CardCaptured {
+                card,
+                shot_type,
+                captures,
+                ..
+            } => write!(
+                f,
+                "{} captured with {} ({} captures)",
+                card, shot_type, captures
+            ),
+            Self::NewMaxBonus {
+                card,
+                shot_type,
+                bonus,
+                ..
+            } => write!(f, "{} max bonus with {}: {}", card, shot_type, bonus),
+            Self::PracticeHighScore {
+                shot_type,
+                difficulty,
+                stage,
+                score,
+                ..
+            } => write!(
+                f,
+                "{} {} practice high score with {}: {}",
+                difficulty, stage, shot_type, score
+            ),
+            Self::PlayCountChanged {
+                shot_type,
+                difficulty,
+                stage,
+                attempts,
+                ..
+            } => write!(
+                f,
+                "{} {} practiced with {} ({} attempts)",
+                difficulty, stage, shot_type, attempts
+            ),
+        }
+    }
+}
+
+/// Compare two snapshots of a score file, returning the changes from `prev` to `cur` in sorted order.
+///
+/// Records that are missing from `prev` are treated as if all of their counters were zero.
+pub fn compare<G, F>(prev: &F, cur: &F) -> Vec<ScoreEvent<G>>
+where
+    G: Game,
+    F: ScoreFile<G>,
+{
+    let mut ret = Vec::new();
+
+    let prev_cards: HashMap<SpellCard<G>, &F::SpellCardRecord> = prev
+        .spell_cards()
+        .iter()
+        .map(|record| (record.card(), record))
+        .collect();
+
+    for record in cur.spell_cards() {
+        let card = record.card();
+        let prev_record = prev_cards.get(&card);
+
+        for shot_type in record.shot_types() {
+            let shot_type = *shot_type;
+            let (prev_attempts, prev_captures, prev_bonus) = prev_record
+                .map(|prev| {
+                    (
+                        prev.attempts(&shot_type),
+                        prev.captures(&shot_type),
+                        prev.max_bonus(&shot_type),
+                    )
+                })
+                .unwrap_or((0, 0, 0));
+
+            let attempts = record.attempts(&shot_type);
+            if attempts > prev_attempts {
+                ret.push(ScoreEvent::CardAttempted {
+                    card,
+                    shot_type,
+                    prev_attempts,
+                    attempts,
+                });
+            }
+
+            let captures = record.captures(&shot_type);
+            if captures > prev_captures {
+                ret.push(ScoreEvent::CardCaptured {
+                    card,
+                    shot_type,
+                    prev_captures,
+                    captures,
+                });
+            }
+
+            let bonus = record.max_bonus(&shot_type);
+            if bonus > prev_bonus {
+                ret.push(ScoreEvent::NewMaxBonus {
+                    card,
+                    shot_type,
+                    prev_bonus,
+                    bonus,
+                });
+            }
+        }
+    }
+
+    let prev_practices: HashMap<_, &F::PracticeRecord> = prev
+        .practice_records()
+        .iter()
+        .map(|record| {
+            (
+                (record.shot_type(), record.difficulty(), record.stage()),
+                record,
+            )
+        })
+        .collect();
+
+    for record in cur.practice_records() {
+        let shot_type = record.shot_type();
+        let difficulty = record.difficulty();
+        let stage = record.stage();
+
+        let (prev_attempts, prev_score) = prev_practices
+            .get(&(shot_type, difficulty, stage))
+            .map(|prev| (prev.attempts(), prev.high_score()))
+            .unwrap_or((0, 0));
+
+        let attempts = record.attempts();
+        if attempts != prev_attempts {
+            ret.push(ScoreEvent::PlayCountChanged {
+                shot_type,
+                difficulty,
+                stage,
+                prev_attempts,
+                attempts,
+            });
+        }
+
+        let score = record.high_score();
+        if score > prev_score {
+            ret.push(ScoreEvent::PracticeHighScore {
+                shot_type,
+                difficulty,
+                stage,
+                prev_score,
+                score,
+            });
+        }
+    }
+
+    ret.sort_unstable();
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "th07")]
+    #[test]
+    fn compare_th07() {
+        use crate::th07::score::test_data::{test_segments, updated_segments, write_segments};
+        use crate::th07::{Difficulty, ScoreFile, ShotType, SpellId, Stage};
+
+        let prev = ScoreFile::new(&write_segments(&test_segments())[..]).unwrap();
+        let cur = ScoreFile::new(&write_segments(&updated_segments())[..]).unwrap();
+        let card = SpellCard::new(SpellId::new(42).unwrap());
+        let shot_type = crate::types::ShotType::new(ShotType::ReimuA);
+
+        assert_eq!(
+            compare(&prev, &cur),
+            vec![
+                ScoreEvent::CardAttempted {
+                    card,
+                    shot_type,
+                    prev_attempts: 1,
+                    attempts: 2
+                },
+                ScoreEvent::CardCaptured {
+                    card,
+                    shot_type,
+                    prev_captures: 0,
+                    captures: 1
+                },
+                ScoreEvent::NewMaxBonus {
+                    card,
+                    shot_type,
+                    prev_bonus: 100,
+                    bonus: 150
+                },
+                ScoreEvent::PlayCountChanged {
+                    shot_type: crate::types::ShotType::new(ShotType::ReimuB),
+                    difficulty: crate::types::Difficulty::new(Difficulty::Hard),
+                    stage: crate::types::Stage::new(Stage::Four),
+                    prev_attempts: 17,
+                    attempts: 18
+                },
+            ]
+        );
+        assert!(compare(&cur, &cur).is_empty());
+    }
+
+    #[cfg(feature = "th08")]
+    #[test]
+    fn compare_th08() {
+        use crate::th08::score::test_data::{test_segments, updated_segments, write_segments};
+        use crate::th08::{Difficulty, ScoreFile, ShotType, SpellId, Stage};
+
+        let prev = ScoreFile::new(&write_segments(&test_segments())[..]).unwrap();
+        let cur = ScoreFile::new(&write_segments(&updated_segments())[..]).unwrap();
+        let card = SpellCard::new(SpellId::new(1).unwrap());
+        let shot_type = crate::types::ShotType::new(ShotType::BarrierTeam);
+        let difficulty = crate::types::Difficulty::new(Difficulty::Normal);
+        let stage = crate::types::Stage::new(Stage::One);
+
+        assert_eq!(
+            compare(&prev, &cur),
+            vec![
+                ScoreEvent::CardAttempted {
+                    card,
+                    shot_type,
+                    prev_attempts: 1,
+                    attempts: 2
+                },
+                ScoreEvent::CardCaptured {
+                    card,
+                    shot_type,
+                    prev_captures: 0,
+                    captures: 1
+                },
+                ScoreEvent::PracticeHighScore {
+                    shot_type,
+                    difficulty,
+                    stage,
+                    prev_score: 100000,
+                    score: 150000
+                },
+                ScoreEvent::PlayCountChanged {
+                    shot_type,
+                    difficulty,
+                    stage,
+                    prev_attempts: 1,
+                    attempts: 2
+                },
+            ]
+        );
+        assert!(compare(&cur, &cur).is_empty());
+    }
+}
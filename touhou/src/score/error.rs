@@ -0,0 +1,225 @@
+use std::error::Error;
+use std::fmt::Display;
+use std::io::{Error as IOError, ErrorKind};
+
+use crate::types::errors::{InvalidCardId, InvalidShotType};
+use crate::types::Game;
+
+fn signature_string(signature: &[u8; 4]) -> String {
+    signature
+        .iter()
+        .flat_map(|b| std::ascii::escape_default(*b))
+        .map(char::from)
+        .collect()
+}
+
+/// An error encountered while parsing a score file.
+///
+/// Offsets for segment-level errors are relative to the start of the decompressed file body.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ScoreParseError<G: Game> {
+    /// An I/O error occurred while reading the file, or the file header could not be read.
+    IO(IOError),
+    /// The checksum computed over the file data did not match the one stored in the file.
+    ChecksumMismatch { expected: u16, actual: u16 },
+    /// A segment signature was not made up of printable ASCII characters, indicating that the segment is damaged
+    /// or that segment boundaries have been lost.
+    BadSignature { offset: usize, signature: [u8; 4] },
+    /// A segment's declared size extends past the end of the file body.
+    TruncatedSegment {
+        offset: usize,
+        signature: [u8; 4],
+        size: usize,
+        available: usize,
+    },
+    /// A segment with an unrecognized signature was found.
+    ///
+    /// This is never returned as a fatal error; unknown segments are preserved as-is, but are reported
+    /// when parsing in lenient mode.
+    UnknownSegment {
+        offset: usize,
+        signature: [u8; 4],
+        size: usize,
+    },
+    /// A segment contained an invalid shot type.
+    InvalidShotType {
+        offset: usize,
+        signature: [u8; 4],
+        error: InvalidShotType<G>,
+    },
+    /// A segment contained an invalid spell card ID.
+    InvalidCardId {
+        offset: usize,
+        signature: [u8; 4],
+        error: InvalidCardId<G>,
+    },
+    /// A segment contained some other invalid data.
+    InvalidSegmentData {
+        offset: usize,
+        signature: [u8; 4],
+        error: IOError,
+    },
+}
+
+impl<G: Game> ScoreParseError<G> {
+    /// Convert an error from reading a segment's contents into a more specific error type, where possible.
+    pub(crate) fn from_segment_error(offset: usize, signature: [u8; 4], error: IOError) -> Self {
+        if let Some(inner) = error.get_ref() {
+            if let Some(error) = inner.downcast_ref::<InvalidShotType<G>>() {
+                return Self::InvalidShotType {
+                    offset,
+                    signature,
+                    error: *error,
+                };
+            }
+
+            if let Some(error) = inner.downcast_ref::<InvalidCardId<G>>() {
+                return Self::InvalidCardId {
+                    offset,
+                    signature,
+                    error: *error,
+                };
+            }
+        }
+
+        Self::InvalidSegmentData {
+            offset,
+            signature,
+            error,
+        }
+    }
+
+    /// Get the offset of the segment associated with this error, if any.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            Self::IO(_) | Self::ChecksumMismatch { .. } => None,
+            Self::BadSignature { offset, .. }
+            | Self::TruncatedSegment { offset, .. }
+            | Self::UnknownSegment { offset, .. }
+            | Self::InvalidShotType { offset, .. }
+            | Self::InvalidCardId { offset, .. }
+            | Self::InvalidSegmentData { offset, .. } => Some(*offset),
+        }
+    }
+
+    /// Get the signature of the segment associated with this error, if any.
+    pub fn signature(&self) -> Option<&[u8; 4]> {
+        match self {
+            Self::IO(_) | Self::ChecksumMismatch { .. } => None,
+            Self::BadSignature { signature, .. }
+            | Self::TruncatedSegment { signature, .. }
+            | Self::UnknownSegment { signature, .. }
+            | Self::InvalidShotType { signature, .. }
+            | Self::InvalidCardId { signature, .. }
+            | Self::InvalidSegmentData { signature, .. } => Some(signature),
+        }
+    }
+}
+
+impl<G: Game> From<IOError> for ScoreParseError<G> {
+    fn from(value: IOError) -> Self {
+        Self::IO(value)
+    }
+}
+
+impl<G: Game> From<ScoreParseError<G>> for IOError {
+    fn from(value: ScoreParseError<G>) -> Self {
+        match value {
+            ScoreParseError::IO(err) => err,
+            other => IOError::new(ErrorKind::InvalidData, other),
+        }
+    }
+}
+
+impl<G: Game> Display for ScoreParseError<G> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} score file error: ", G::GAME_ID.abbreviation())?;
+        match self {
+            Self::IO(err) => err.fmt(f),
+            Self::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "checksum mismatch (expected {:#06x}, got {:#06x})",
+                expected, actual
+            ),
+            Self::BadSignature { offset, signature } => write!(
+                f,
+                "bad segment signature \"{}\" at offset {:#x}",
+                signature_string(signature),
+                offset
+            ),
+            Self::TruncatedSegment {
+                offset,
+                signature,
+                size,
+                available,
+            } => write!(
+                f,
+                "segment \"{}\" at offset {:#x} is truncated ({} bytes declared, {} bytes available)",
+                signature_string(signature),
+                offset,
+                size,
+                available
+            ),
+            Self::UnknownSegment {
+                offset,
+                signature,
+                size,
+            } => write!(
+                f,
+                "unknown segment \"{}\" at offset {:#x} ({} bytes)",
+                signature_string(signature),
+                offset,
+                size
+            ),
+            Self::InvalidShotType {
+                offset,
+                signature,
+                error,
+            } => write!(
+                f,
+                "in segment \"{}\" at offset {:#x}: {}",
+                signature_string(signature),
+                offset,
+                error
+            ),
+            Self::InvalidCardId {
+                offset,
+                signature,
+                error,
+            } => write!(
+                f,
+                "in segment \"{}\" at offset {:#x}: {}",
+                signature_string(signature),
+                offset,
+                error
+            ),
+            Self::InvalidSegmentData {
+                offset,
+                signature,
+                error,
+            } => write!(
+                f,
+                "in segment \"{}\" at offset {:#x}: {}",
+                signature_string(signature),
+                offset,
+                error
+            ),
+        }
+    }
+}
+
+impl<G: Game> Error for ScoreParseError<G> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::IO(err) => Some(err),
+            Self::InvalidShotType { error, .. } => Some(error),
+            Self::InvalidCardId { error, .. } => Some(error),
+            Self::InvalidSegmentData { error, .. } => Some(error),
+            Self::ChecksumMismatch { .. }
+            | Self::BadSignature { .. }
+            | Self::TruncatedSegment { .. }
+            | Self::UnknownSegment { .. } => None,
+        }
+    }
+}
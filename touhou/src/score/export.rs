@@ -0,0 +1,199 @@
+//! Flattening score file data into rows for exporting to other formats.
+//!
+//! The row types here store names instead of game-specific values, so that rows from different games
+//! can be written out (to CSV, JSON, etc.) in the same way.
+
+use serde::Serialize;
+
+use super::{PracticeRecord, ScoreFile, SpellCardRecord};
+use crate::types::{Game, GameId, GameValue, SpellType};
+
+/// Statistics for a single spell card with a single shot type.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CardStats {
+    pub game: GameId,
+    pub card_id: u32,
+    pub card_name: &'static str,
+    pub stage: &'static str,
+    pub difficulty: &'static str,
+    pub spell_type: SpellType,
+    pub shot_type: &'static str,
+    pub attempts: u32,
+    pub captures: u32,
+    pub max_bonus: u32,
+}
+
+/// Statistics for a single stage practice record.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PracticeStats {
+    pub game: GameId,
+    pub stage: &'static str,
+    pub difficulty: &'static str,
+    pub shot_type: &'static str,
+    pub attempts: u32,
+    pub high_score: u32,
+}
+
+/// Selects which records are exported from a score file.
+///
+/// Stages, difficulties, and shot types are matched by name, ignoring case and whitespace;
+/// stages can also be matched by number alone (so `"4"` matches `"Stage 4"`).
+/// An empty filter list matches everything.
+#[derive(Debug, Clone, Default)]
+pub struct RecordFilter {
+    stages: Vec<String>,
+    difficulties: Vec<String>,
+    shot_types: Vec<String>,
+    spell_types: Vec<SpellType>,
+}
+
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+impl RecordFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_stage<S: AsRef<str>>(mut self, stage: S) -> Self {
+        self.stages.push(normalize_name(stage.as_ref()));
+        self
+    }
+
+    pub fn with_difficulty<S: AsRef<str>>(mut self, difficulty: S) -> Self {
+        self.difficulties.push(normalize_name(difficulty.as_ref()));
+        self
+    }
+
+    pub fn with_shot_type<S: AsRef<str>>(mut self, shot_type: S) -> Self {
+        self.shot_types.push(normalize_name(shot_type.as_ref()));
+        self
+    }
+
+    /// Only include spell cards of the given type.
+    ///
+    /// This has no effect on stage practice records.
+    pub fn with_spell_type(mut self, spell_type: SpellType) -> Self {
+        self.spell_types.push(spell_type);
+        self
+    }
+
+    fn matches_any(filters: &[String], name: &str) -> bool {
+        let name = normalize_name(name);
+        filters.is_empty() || filters.contains(&name)
+    }
+
+    fn matches_stage(&self, name: &str) -> bool {
+        let name = normalize_name(name);
+        self.stages.is_empty()
+            || self
+                .stages
+                .iter()
+                .any(|filter| *filter == name || name.strip_prefix("stage") == Some(filter))
+    }
+
+    pub fn matches_card(&self, stats: &CardStats) -> bool {
+        self.matches_stage(stats.stage)
+            && Self::matches_any(&self.difficulties, stats.difficulty)
+            && Self::matches_any(&self.shot_types, stats.shot_type)
+            && (self.spell_types.is_empty() || self.spell_types.contains(&stats.spell_type))
+    }
+
+    pub fn matches_practice(&self, stats: &PracticeStats) -> bool {
+        self.matches_stage(stats.stage)
+            && Self::matches_any(&self.difficulties, stats.difficulty)
+            && Self::matches_any(&self.shot_types, stats.shot_type)
+    }
+}
+
+/// Get statistics for every spell card and shot type in a score file that matches a filter.
+pub fn card_stats<G, F>(score_file: &F, filter: &RecordFilter) -> Vec<CardStats>
+where
+    G: Game,
+    F: ScoreFile<G>,
+{
+    score_file
+        .spell_cards()
+        .iter()
+        .flat_map(|record| {
+            let card = record.card();
+            let info = card.info();
+
+            record.shot_types().iter().map(move |shot_type| CardStats {
+                game: G::GAME_ID,
+                card_id: card.id(),
+                card_name: info.name,
+                stage: info.stage.name(),
+                difficulty: info.difficulty.name(),
+                spell_type: info.spell_type,
+                shot_type: shot_type.name(),
+                attempts: record.attempts(shot_type),
+                captures: record.captures(shot_type),
+                max_bonus: record.max_bonus(shot_type),
+            })
+        })
+        .filter(|stats| filter.matches_card(stats))
+        .collect()
+}
+
+/// Get statistics for every stage practice record in a score file that matches a filter.
+pub fn practice_stats<G, F>(score_file: &F, filter: &RecordFilter) -> Vec<PracticeStats>
+where
+    G: Game,
+    F: ScoreFile<G>,
+{
+    score_file
+        .practice_records()
+        .iter()
+        .map(|record| PracticeStats {
+            game: G::GAME_ID,
+            stage: record.stage().name(),
+            difficulty: record.difficulty().name(),
+            shot_type: record.shot_type().name(),
+            attempts: record.attempts(),
+            high_score: record.high_score(),
+        })
+        .filter(|stats| filter.matches_practice(stats))
+        .collect()
+}
+
+#[cfg(all(test, feature = "th07"))]
+mod tests {
+    use super::*;
+    use crate::th07::score::test_data::{test_segments, write_segments};
+    use crate::th07::ScoreFile;
+    use crate::types::SpellType;
+
+    #[test]
+    fn export_th07_stats() {
+        let score_file = ScoreFile::new(&write_segments(&test_segments())[..]).unwrap();
+
+        let cards = card_stats(&score_file, &RecordFilter::new());
+        assert_eq!(cards.len(), 6);
+        assert_eq!(cards[0].card_id, 42);
+        assert_eq!((cards[0].attempts, cards[0].captures), (1, 0));
+
+        let filter = RecordFilter::new()
+            .with_shot_type("marisa b")
+            .with_difficulty(cards[0].difficulty.to_uppercase());
+        let cards = card_stats(&score_file, &filter);
+        assert_eq!(cards.len(), 1);
+        assert_eq!(cards[0].shot_type, "Marisa B");
+        assert_eq!(cards[0].max_bonus, 400);
+
+        let filter = RecordFilter::new().with_stage("4");
+        assert!(card_stats(&score_file, &filter).is_empty());
+        let practices = practice_stats(&score_file, &filter);
+        assert_eq!(practices.len(), 1);
+        assert_eq!(practices[0].stage, "Stage 4");
+        assert_eq!(practices[0].high_score, 9876540);
+
+        let filter = RecordFilter::new().with_spell_type(SpellType::LastWord);
+        assert!(card_stats(&score_file, &filter).is_empty());
+        assert_eq!(practice_stats(&score_file, &filter).len(), 1);
+    }
+}
@@ -0,0 +1,251 @@
+//! Splitting decompressed score file bodies into segments.
+//!
+//! Older score files (such as those for Touhou 7 and 8) are made up of a sequence of segments, each starting with a
+//! four-byte signature followed by two 16-bit size fields. The functions here find segment boundaries within a
+//! decompressed file body, leaving the parsing of segment contents to game-specific code.
+
+use std::io;
+
+use super::error::ScoreParseError;
+use crate::types::Game;
+
+/// A segment header and its data, as found within a decompressed score file body.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RawSegment<'a> {
+    pub signature: [u8; 4],
+    pub size1: usize,
+    pub size2: usize,
+    pub data: &'a [u8],
+}
+
+/// How to handle segments with a declared size too small to contain a segment header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ShortSegments {
+    /// Treat the segment as the end of the file.
+    EndOfFile,
+    /// Skip over the segment header and keep reading.
+    Skip,
+}
+
+fn valid_signature(signature: &[u8; 4]) -> bool {
+    signature.iter().all(u8::is_ascii_graphic)
+}
+
+/// Find the next offset at or after `start` that begins with one of the given signatures.
+fn resync(body: &[u8], start: usize, known: &[[u8; 4]]) -> Option<usize> {
+    (start..body.len()).find(|&pos| known.iter().any(|sig| body[pos..].starts_with(sig)))
+}
+
+/// Split a decompressed score file body into segments, parsing each one with `parse`.
+///
+/// In strict mode, the first problem found is returned as an error. In lenient mode, damaged segments are skipped
+/// and reported alongside the segments that were parsed successfully; if segment boundaries are lost (due to a bad
+/// signature or size field), parsing resumes at the next occurrence of one of the `known` signatures.
+#[allow(clippy::type_complexity)]
+pub(crate) fn parse_segments<G, T, F>(
+    body: &[u8],
+    known: &[[u8; 4]],
+    short_segments: ShortSegments,
+    lenient: bool,
+    mut parse: F,
+) -> Result<(Vec<T>, Vec<ScoreParseError<G>>), ScoreParseError<G>>
+where
+    G: Game,
+    F: FnMut(RawSegment<'_>) -> io::Result<T>,
+{
+    let mut segments = Vec::new();
+    let mut errors = Vec::new();
+    let mut pos = 0;
+
+    macro_rules! report {
+        ($err:expr) => {{
+            let err = $err;
+            if lenient {
+                errors.push(err);
+            } else {
+                return Err(err);
+            }
+        }};
+    }
+
+    while body.len() - pos >= 8 {
+        let offset = pos;
+        let signature: [u8; 4] = body[pos..pos + 4].try_into().unwrap();
+        let size1 = u16::from_le_bytes([body[pos + 4], body[pos + 5]]) as usize;
+        let size2 = u16::from_le_bytes([body[pos + 6], body[pos + 7]]) as usize;
+
+        if size1 <= 8 {
+            match short_segments {
+                ShortSegments::EndOfFile => break,
+                ShortSegments::Skip => {
+                    pos += 8;
+                    continue;
+                }
+            }
+        }
+
+        if !valid_signature(&signature) {
+            report!(ScoreParseError::BadSignature { offset, signature });
+            match resync(body, offset + 1, known) {
+                Some(next) => {
+                    pos = next;
+                    continue;
+                }
+                None => break,
+            }
+        }
+
+        let end = offset + size1;
+        if end > body.len() {
+            report!(ScoreParseError::TruncatedSegment {
+                offset,
+                signature,
+                size: size1,
+                available: body.len() - offset,
+            });
+            match resync(body, offset + 8, known) {
+                Some(next) => {
+                    pos = next;
+                    continue;
+                }
+                None => break,
+            }
+        }
+
+        pos = end;
+
+        if lenient && !known.contains(&signature) {
+            errors.push(ScoreParseError::UnknownSegment {
+                offset,
+                signature,
+                size: size1,
+            });
+        }
+
+        let raw = RawSegment {
+            signature,
+            size1,
+            size2,
+            data: &body[offset + 8..end],
+        };
+
+        match parse(raw) {
+            Ok(segment) => segments.push(segment),
+            Err(e) => report!(ScoreParseError::from_segment_error(offset, signature, e)),
+        }
+    }
+
+    Ok((segments, errors))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offset_of(offsets: &[([u8; 4], usize)], signature: &[u8; 4]) -> usize {
+        offsets.iter().find(|(sig, _)| sig == signature).unwrap().1
+    }
+
+    #[cfg(feature = "th07")]
+    #[test]
+    fn th07_lenient_parsing() {
+        use crate::score::ScoreFile as _;
+        use crate::th07::score::test_data::{encode_body, test_segments, write_body};
+        use crate::th07::ScoreFile;
+
+        let (body, offsets) = write_body(&test_segments());
+
+        let (score_file, errors) = ScoreFile::parse_lenient(&encode_body(&body, 0)[..]).unwrap();
+        assert_eq!(score_file.spell_cards().len(), 1);
+        assert!(matches!(
+            errors[..],
+            [ScoreParseError::UnknownSegment { signature, .. }] if &signature == b"ABCD"
+        ));
+
+        let err = ScoreFile::parse(&encode_body(&body, 1)[..]).unwrap_err();
+        assert!(matches!(err, ScoreParseError::ChecksumMismatch { .. }));
+        let (_, errors) = ScoreFile::parse_lenient(&encode_body(&body, 1)[..]).unwrap();
+        assert!(matches!(
+            errors[0],
+            ScoreParseError::ChecksumMismatch { .. }
+        ));
+
+        let mut damaged = body.clone();
+        damaged[offset_of(&offsets, b"PSCR") + 20] = 0xFF;
+        damaged[offset_of(&offsets, b"CATK")..][..4].copy_from_slice(&[0xFF; 4]);
+        let damaged = encode_body(&damaged, 0);
+
+        let err = ScoreFile::parse(&damaged[..]).unwrap_err();
+        assert!(matches!(err, ScoreParseError::BadSignature { .. }));
+        assert_eq!(err.offset(), Some(offset_of(&offsets, b"CATK")));
+
+        let (score_file, errors) = ScoreFile::parse_lenient(&damaged[..]).unwrap();
+        assert!(score_file.spell_cards().is_empty());
+        assert!(score_file.practice_records().is_empty());
+        assert!(matches!(errors[0], ScoreParseError::BadSignature { .. }));
+        assert!(matches!(errors[1], ScoreParseError::InvalidShotType { .. }));
+        assert_eq!(errors[1].offset(), Some(offset_of(&offsets, b"PSCR")));
+
+        let truncated = encode_body(&body[..body.len() - 2], 0);
+        let err = ScoreFile::parse(&truncated[..]).unwrap_err();
+        assert!(matches!(
+            err,
+            ScoreParseError::TruncatedSegment { size: 12, .. }
+        ));
+        let (score_file, errors) = ScoreFile::parse_lenient(&truncated[..]).unwrap();
+        assert_eq!(score_file.spell_cards().len(), 1);
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[cfg(feature = "th08")]
+    #[test]
+    fn th08_lenient_parsing() {
+        use crate::score::ScoreFile as _;
+        use crate::th08::score::test_data::{encode_body, test_segments, write_body};
+        use crate::th08::ScoreFile;
+
+        let (body, offsets) = write_body(&test_segments());
+
+        let (score_file, errors) = ScoreFile::parse_lenient(&encode_body(&body, 0)[..]).unwrap();
+        assert_eq!(score_file.spell_cards().len(), 20);
+        assert_eq!(score_file.practice_records().len(), 45);
+        assert!(matches!(
+            errors[..],
+            [ScoreParseError::UnknownSegment { signature, .. }] if &signature == b"ABCD"
+        ));
+
+        let err = ScoreFile::parse(&encode_body(&body, 1)[..]).unwrap_err();
+        assert!(matches!(err, ScoreParseError::ChecksumMismatch { .. }));
+        let (score_file, errors) = ScoreFile::parse_lenient(&encode_body(&body, 1)[..]).unwrap();
+        assert_eq!(score_file.spell_cards().len(), 20);
+        assert!(matches!(
+            errors[0],
+            ScoreParseError::ChecksumMismatch { .. }
+        ));
+
+        // Damage the shot type in the practice data, and the signature of the first spell card.
+        let mut damaged = body.clone();
+        damaged[offset_of(&offsets, b"PSCR") + 372] = 0xFF;
+        damaged[offset_of(&offsets, b"CATK")..][..4].copy_from_slice(&[0xFF; 4]);
+        let damaged = encode_body(&damaged, 0);
+
+        let err = ScoreFile::parse(&damaged[..]).unwrap_err();
+        assert!(matches!(err, ScoreParseError::InvalidShotType { .. }));
+        assert_eq!(err.offset(), Some(offset_of(&offsets, b"PSCR")));
+
+        let (score_file, errors) = ScoreFile::parse_lenient(&damaged[..]).unwrap();
+        assert_eq!(score_file.spell_cards().len(), 19);
+        assert!(score_file.practice_records().is_empty());
+        assert!(matches!(errors[0], ScoreParseError::InvalidShotType { .. }));
+        assert!(matches!(errors[1], ScoreParseError::UnknownSegment { .. }));
+        assert!(matches!(errors[2], ScoreParseError::BadSignature { .. }));
+        assert_eq!(errors[2].offset(), Some(offset_of(&offsets, b"CATK")));
+
+        let truncated = encode_body(&body[..body.len() - 2], 0);
+        let err = ScoreFile::parse(&truncated[..]).unwrap_err();
+        assert!(matches!(err, ScoreParseError::TruncatedSegment { .. }));
+        let (score_file, errors) = ScoreFile::parse_lenient(&truncated[..]).unwrap();
+        assert_eq!(score_file.spell_cards().len(), 19);
+        assert_eq!(errors.len(), 2);
+    }
+}
@@ -0,0 +1,435 @@
+//! Watching score files for changes.
+//!
+//! The games only write their score files when certain events happen (such as finishing a run or exiting the game),
+//! so the watchers in this module work by polling the file's metadata for changes instead of relying on platform-specific
+//! file notification APIs. Once a change is seen, a watcher waits for the file to stop changing (to avoid reading
+//! a partially-written file) before loading a new snapshot and comparing it against the previous one.
+
+use std::fs;
+use std::io::{self, Cursor, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+#[cfg(feature = "find-process")]
+use sysinfo::System;
+
+use super::diff::{compare, ScoreEvent};
+use super::HasScoreFile;
+#[cfg(feature = "find-process")]
+use crate::process::{find_game_process, GamePaths};
+use crate::types::GameId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileState {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl From<fs::Metadata> for FileState {
+    fn from(metadata: fs::Metadata) -> Self {
+        Self {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+        }
+    }
+}
+
+/// A new snapshot of a score file, along with the changes from the previous snapshot.
+#[derive(Debug)]
+pub struct ScoreUpdate<G: HasScoreFile> {
+    prev: Option<Arc<G::ScoreFile>>,
+    snapshot: Arc<G::ScoreFile>,
+    events: Vec<ScoreEvent<G>>,
+}
+
+impl<G: HasScoreFile> ScoreUpdate<G> {
+    /// The previously loaded snapshot, if this isn't the first snapshot loaded by the watcher.
+    pub fn prev(&self) -> Option<&Arc<G::ScoreFile>> {
+        self.prev.as_ref()
+    }
+
+    pub fn snapshot(&self) -> &Arc<G::ScoreFile> {
+        &self.snapshot
+    }
+
+    /// The changes between the previous snapshot and this one, in sorted order.
+    ///
+    /// This is always empty for the first snapshot loaded by a watcher.
+    pub fn events(&self) -> &[ScoreEvent<G>] {
+        &self.events[..]
+    }
+
+    pub fn is_initial(&self) -> bool {
+        self.prev.is_none()
+    }
+}
+
+/// Watches a score file for a specific game, loading new snapshots as it changes.
+///
+/// The watcher can be polled manually using [`poll`](ScoreWatcher::poll), or used as a blocking [`Iterator`]
+/// that waits for each update in turn.
+#[derive(Debug)]
+pub struct ScoreWatcher<G: HasScoreFile> {
+    path: PathBuf,
+    poll_interval: Duration,
+    debounce: Duration,
+    max_failures: u32,
+    last_loaded: Option<FileState>,
+    pending: Option<(FileState, Instant)>,
+    failures: u32,
+    snapshot: Option<Arc<G::ScoreFile>>,
+}
+
+impl<G: HasScoreFile> ScoreWatcher<G> {
+    /// Create a new watcher for the score file at the given path.
+    ///
+    /// The file does not need to exist yet; the first snapshot will be loaded once it does.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into(),
+            poll_interval: Duration::from_millis(1000),
+            debounce: Duration::from_millis(2000),
+            max_failures: 5,
+            last_loaded: None,
+            pending: None,
+            failures: 0,
+            snapshot: None,
+        }
+    }
+
+    /// Set how long to wait in-between polls when this watcher is used as an [`Iterator`].
+    ///
+    /// Defaults to one second.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Set how long the score file must go unchanged before it is loaded.
+    ///
+    /// Defaults to two seconds.
+    pub fn with_debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// Set how many times in a row loading a settled score file may fail before the error is reported.
+    ///
+    /// Defaults to five. Values less than one are treated as one.
+    pub fn with_max_failures(mut self, max_failures: u32) -> Self {
+        self.max_failures = max_failures.max(1);
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The most recently loaded snapshot of the score file, if any.
+    pub fn snapshot(&self) -> Option<&Arc<G::ScoreFile>> {
+        self.snapshot.as_ref()
+    }
+
+    /// Check the score file for changes, and load a new snapshot if it has changed.
+    ///
+    /// Returns `Ok(None)` if the file hasn't changed, doesn't exist yet, or is still being written.
+    /// If the file can't be read or parsed once it has settled (for example, if the game is holding it open),
+    /// it will be retried on a later poll. Once loading has failed [`with_max_failures`](ScoreWatcher::with_max_failures)
+    /// times in a row, the error is returned, and the file won't be loaded again until it changes.
+    pub fn poll(&mut self) -> io::Result<Option<ScoreUpdate<G>>> {
+        let state = match fs::metadata(&self.path) {
+            Ok(metadata) => FileState::from(metadata),
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        if self.last_loaded == Some(state) {
+            self.pending = None;
+            return Ok(None);
+        }
+
+        let now = Instant::now();
+        match self.pending {
+            Some((pending, since)) if pending == state => {
+                if now.duration_since(since) < self.debounce {
+                    return Ok(None);
+                }
+            }
+            _ => {
+                self.pending = Some((state, now));
+                self.failures = 0;
+                if !self.debounce.is_zero() {
+                    return Ok(None);
+                }
+            }
+        }
+
+        let snapshot =
+            match fs::read(&self.path).and_then(|data| G::load_score_file(Cursor::new(data))) {
+                Ok(snapshot) => Arc::new(snapshot),
+                Err(e) => {
+                    self.failures += 1;
+                    if self.failures >= self.max_failures {
+                        self.last_loaded = Some(state);
+                        self.pending = None;
+                        self.failures = 0;
+                        return Err(e);
+                    }
+
+                    self.pending = Some((state, now));
+                    return Ok(None);
+                }
+            };
+
+        self.last_loaded = Some(state);
+        self.pending = None;
+        self.failures = 0;
+
+        let prev = self.snapshot.replace(snapshot.clone());
+        let events = prev
+            .as_deref()
+            .map(|prev| compare(prev, &snapshot))
+            .unwrap_or_default();
+
+        Ok(Some(ScoreUpdate {
+            prev,
+            snapshot,
+            events,
+        }))
+    }
+}
+
+#[cfg(feature = "find-process")]
+impl<G: HasScoreFile> ScoreWatcher<G> {
+    /// Create a new watcher for the score file used by a running game process, if one can be found.
+    pub fn find(system: &System) -> Option<Self> {
+        find_score_path(G::GAME_ID, system).map(Self::new)
+    }
+}
+
+impl<G: HasScoreFile> Iterator for ScoreWatcher<G> {
+    type Item = io::Result<ScoreUpdate<G>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.poll() {
+                Ok(Some(update)) => return Some(Ok(update)),
+                Ok(None) => std::thread::sleep(self.poll_interval),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// Find the path to the score file used by a running game process, if one can be found.
+#[cfg(feature = "find-process")]
+pub fn find_score_path(game: GameId, system: &System) -> Option<PathBuf> {
+    find_game_process(system, game)
+        .and_then(|proc| GamePaths::from_process(game, proc))
+        .map(|paths| paths.score_file())
+}
+
+macro_rules! define_any_watcher {
+    ($($feature:literal => $game:ident($id:ident)),*) => {
+        /// A [`ScoreWatcher`] for a game chosen at runtime.
+        #[derive(Debug)]
+        #[non_exhaustive]
+        pub enum AnyScoreWatcher {
+            $(
+                #[cfg(feature = $feature)]
+                $game(ScoreWatcher<crate::$game>),
+            )*
+        }
+
+        /// A [`ScoreUpdate`] from an [`AnyScoreWatcher`].
+        #[derive(Debug)]
+        #[non_exhaustive]
+        pub enum AnyScoreUpdate {
+            $(
+                #[cfg(feature = $feature)]
+                $game(ScoreUpdate<crate::$game>),
+            )*
+        }
+
+        impl AnyScoreWatcher {
+            /// Create a new watcher for the given game's score file at the given path.
+            ///
+            /// Returns `None` if support for the given game has not been enabled in crate features.
+            pub fn new<P: Into<PathBuf>>(game: GameId, path: P) -> Option<Self> {
+                let path = path.into();
+                match game {
+                    $(
+                        #[cfg(feature = $feature)]
+                        GameId::$id => Some(Self::$game(ScoreWatcher::new(path))),
+                    )*
+                    #[allow(unreachable_patterns)]
+                    _ => None,
+                }
+            }
+
+            pub fn game_id(&self) -> GameId {
+                match *self {
+                    $(
+                        #[cfg(feature = $feature)]
+                        Self::$game(_) => GameId::$id,
+                    )*
+                }
+            }
+
+            pub fn path(&self) -> &Path {
+                match self {
+                    $(
+                        #[cfg(feature = $feature)]
+                        Self::$game(watcher) => watcher.path(),
+                    )*
+                }
+            }
+
+            /// See [`ScoreWatcher::with_poll_interval`].
+            pub fn with_poll_interval(self, poll_interval: Duration) -> Self {
+                match self {
+                    $(
+                        #[cfg(feature = $feature)]
+                        Self::$game(watcher) => Self::$game(watcher.with_poll_interval(poll_interval)),
+                    )*
+                }
+            }
+
+            /// See [`ScoreWatcher::with_debounce`].
+            pub fn with_debounce(self, debounce: Duration) -> Self {
+                match self {
+                    $(
+                        #[cfg(feature = $feature)]
+                        Self::$game(watcher) => Self::$game(watcher.with_debounce(debounce)),
+                    )*
+                }
+            }
+
+            /// See [`ScoreWatcher::with_max_failures`].
+            pub fn with_max_failures(self, max_failures: u32) -> Self {
+                match self {
+                    $(
+                        #[cfg(feature = $feature)]
+                        Self::$game(watcher) => Self::$game(watcher.with_max_failures(max_failures)),
+                    )*
+                }
+            }
+
+            /// See [`ScoreWatcher::poll`].
+            pub fn poll(&mut self) -> io::Result<Option<AnyScoreUpdate>> {
+                match self {
+                    $(
+                        #[cfg(feature = $feature)]
+                        Self::$game(watcher) => watcher.poll().map(|update| update.map(AnyScoreUpdate::$game)),
+                    )*
+                }
+            }
+        }
+
+        impl AnyScoreUpdate {
+            pub fn game_id(&self) -> GameId {
+                match *self {
+                    $(
+                        #[cfg(feature = $feature)]
+                        Self::$game(_) => GameId::$id,
+                    )*
+                }
+            }
+        }
+
+        impl Iterator for AnyScoreWatcher {
+            type Item = io::Result<AnyScoreUpdate>;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                match self {
+                    $(
+                        #[cfg(feature = $feature)]
+                        Self::$game(watcher) => watcher.next().map(|update| update.map(AnyScoreUpdate::$game)),
+                    )*
+                }
+            }
+        }
+    };
+}
+
+define_any_watcher!(
+    "th07" => Touhou7(PCB),
+    "th08" => Touhou8(IN),
+    "th10" => Touhou10(MoF),
+    "th15" => Touhou15(LoLK)
+);
+
+#[cfg(feature = "find-process")]
+impl AnyScoreWatcher {
+    /// Create a new watcher for the score file used by a running game process, if one can be found.
+    pub fn find(game: GameId, system: &System) -> Option<Self> {
+        find_score_path(game, system).and_then(|path| Self::new(game, path))
+    }
+}
+
+#[cfg(all(test, feature = "th07"))]
+mod tests {
+    use std::fs::File;
+
+    use super::*;
+    use crate::th07::score::test_data::{test_segments, updated_segments, write_segments};
+    use crate::th07::score::Segment;
+    use crate::Touhou7;
+
+    #[test]
+    fn watch_score_file() {
+        let dir = std::env::temp_dir().join(format!("thstats-watch-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("score.dat");
+
+        let write_file = |segments: &[Segment], mtime: SystemTime| {
+            fs::write(&path, write_segments(segments)).unwrap();
+            File::options()
+                .write(true)
+                .open(&path)
+                .and_then(|file| file.set_modified(mtime))
+                .unwrap();
+        };
+
+        let mut watcher = ScoreWatcher::<Touhou7>::new(&path)
+            .with_debounce(Duration::ZERO)
+            .with_max_failures(2);
+        assert!(watcher.poll().unwrap().is_none());
+
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        write_file(&test_segments(), start);
+
+        let update = watcher.poll().unwrap().unwrap();
+        assert!(update.is_initial());
+        assert!(update.events().is_empty());
+        assert!(watcher.poll().unwrap().is_none());
+
+        write_file(&updated_segments(), start + Duration::from_secs(60));
+
+        let update = watcher.poll().unwrap().unwrap();
+        assert!(!update.is_initial());
+        assert_eq!(update.events().len(), 4);
+
+        // Partially-written files are retried on later polls...
+        fs::write(&path, [0u8; 4]).unwrap();
+        assert!(watcher.poll().unwrap().is_none());
+        write_file(&updated_segments(), start + Duration::from_secs(120));
+        assert!(watcher.poll().unwrap().unwrap().events().is_empty());
+
+        // ...but files that stay unreadable are eventually reported, and not retried until they change again.
+        fs::write(&path, [0u8; 8]).unwrap();
+        assert!(watcher.poll().unwrap().is_none());
+        assert!(watcher.poll().is_err());
+        assert!(watcher.poll().unwrap().is_none());
+        write_file(&updated_segments(), start + Duration::from_secs(180));
+        assert!(watcher.poll().unwrap().is_some());
+
+        let mut watcher =
+            ScoreWatcher::<Touhou7>::new(&path).with_debounce(Duration::from_secs(3600));
+        assert!(watcher.poll().unwrap().is_none());
+        assert!(watcher.poll().unwrap().is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,507 @@
+//! A SQLite-backed store for spell card attempt history.
+//!
+//! A [`Storage`] database records runs, the stage segments played within each run, individual
+//! spell card attempts, and periodic snapshots of score file statistics. Records for all games
+//! are stored in the same database, keyed by [`GameId`](crate::types::GameId) and the raw IDs
+//! of each game-specific value.
+//!
+//! Capture rates can then be queried over rolling windows of time or attempt counts; see
+//! [`HistoryFilter`] and [`Window`].
+
+use std::path::Path;
+
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use time::OffsetDateTime;
+
+#[cfg(feature = "score-file")]
+use crate::score::{ScoreFile, SpellCardRecord};
+use crate::types::{Difficulty, Game, GameValue, ShotType, SpellCard, Stage};
+
+mod error;
+mod query;
+
+pub use error::StorageError;
+pub use query::{CaptureRate, HistoryFilter, RollingCaptureRate, Window};
+
+const SCHEMA: &str = include_str!("storage/schema.sql");
+const SCHEMA_VERSION: i64 = 1;
+
+/// Identifies a run stored in a [`Storage`] database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RunId(i64);
+
+/// Identifies a stage segment stored in a [`Storage`] database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SegmentId(i64);
+
+/// A single attempt at a spell card.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attempt<G: Game> {
+    pub time: OffsetDateTime,
+    pub card: SpellCard<G>,
+    pub shot_type: ShotType<G>,
+    pub difficulty: Difficulty<G>,
+    pub captured: bool,
+}
+
+/// Score file statistics for a single spell card and shot type at a point in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CardSnapshot<G: Game> {
+    pub time: OffsetDateTime,
+    pub card: SpellCard<G>,
+    pub shot_type: ShotType<G>,
+    pub attempts: u32,
+    pub captures: u32,
+    pub max_bonus: u32,
+}
+
+fn to_millis(time: OffsetDateTime) -> i64 {
+    (time.unix_timestamp_nanos() / 1_000_000) as i64
+}
+
+fn from_millis<G: Game>(value: i64) -> Result<OffsetDateTime, StorageError> {
+    OffsetDateTime::from_unix_timestamp_nanos((value as i128) * 1_000_000).map_err(|_| {
+        StorageError::InvalidValue {
+            game: G::GAME_ID,
+            column: "time",
+            value,
+        }
+    })
+}
+
+fn decode_value<G, T>(column: &'static str, value: i64) -> Result<T, StorageError>
+where
+    G: Game,
+    T: GameValue,
+    T::RawValue: TryFrom<i64>,
+{
+    T::RawValue::try_from(value)
+        .ok()
+        .and_then(|raw| T::from_raw(raw, G::GAME_ID).ok())
+        .ok_or(StorageError::InvalidValue {
+            game: G::GAME_ID,
+            column,
+            value,
+        })
+}
+
+/// A connection to a capture statistics database.
+#[derive(Debug)]
+pub struct Storage {
+    conn: Connection,
+}
+
+impl Storage {
+    /// Open or create a database at the given path.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, StorageError> {
+        Self::init(Connection::open(path)?)
+    }
+
+    /// Create a new, empty database held entirely in memory.
+    pub fn open_in_memory() -> Result<Self, StorageError> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, StorageError> {
+        let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        if version > SCHEMA_VERSION {
+            return Err(StorageError::UnsupportedVersion {
+                found: version,
+                supported: SCHEMA_VERSION,
+            });
+        }
+
+        conn.pragma_update(None, "foreign_keys", true)?;
+        conn.execute_batch(SCHEMA)?;
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+
+        Ok(Self { conn })
+    }
+
+    /// Get the underlying SQLite connection, for running queries not covered by this type.
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// Record the start of a new run.
+    pub fn start_run<G: Game>(
+        &self,
+        shot_type: ShotType<G>,
+        difficulty: Difficulty<G>,
+        practice: bool,
+        time: OffsetDateTime,
+    ) -> Result<RunId, StorageError> {
+        self.conn.execute(
+            "INSERT INTO runs (game, shot_type, difficulty, practice, start_time) VALUES (?, ?, ?, ?, ?)",
+            params![
+                G::GAME_ID.number(),
+                shot_type.unwrap().raw_id(),
+                difficulty.unwrap().raw_id(),
+                practice,
+                to_millis(time)
+            ],
+        )?;
+
+        Ok(RunId(self.conn.last_insert_rowid()))
+    }
+
+    /// Record the end of a run.
+    pub fn end_run(&self, run: RunId, time: OffsetDateTime) -> Result<(), StorageError> {
+        self.conn
+            .execute(
+                "UPDATE runs SET end_time = ? WHERE id = ?",
+                params![to_millis(time), run.0],
+            )
+            .map(|_| ())
+            .map_err(StorageError::from)
+    }
+
+    /// Record the start of a new stage segment within a run.
+    ///
+    /// The stage should belong to the same game as the run.
+    pub fn start_segment<G: Game>(
+        &self,
+        run: RunId,
+        stage: Stage<G>,
+        time: OffsetDateTime,
+    ) -> Result<SegmentId, StorageError> {
+        self.conn.execute(
+            "INSERT INTO segments (run_id, stage, start_time) VALUES (?, ?, ?)",
+            params![run.0, stage.unwrap().raw_id(), to_millis(time)],
+        )?;
+
+        Ok(SegmentId(self.conn.last_insert_rowid()))
+    }
+
+    /// Record the end of a stage segment.
+    pub fn end_segment(
+        &self,
+        segment: SegmentId,
+        time: OffsetDateTime,
+    ) -> Result<(), StorageError> {
+        self.conn
+            .execute(
+                "UPDATE segments SET end_time = ? WHERE id = ?",
+                params![to_millis(time), segment.0],
+            )
+            .map(|_| ())
+            .map_err(StorageError::from)
+    }
+
+    /// Get the run that a segment belongs to.
+    pub fn segment_run(&self, segment: SegmentId) -> Result<Option<RunId>, StorageError> {
+        self.conn
+            .query_row(
+                "SELECT run_id FROM segments WHERE id = ?",
+                [segment.0],
+                |row| row.get(0),
+            )
+            .optional()
+            .map(|id| id.map(RunId))
+            .map_err(StorageError::from)
+    }
+
+    /// Record a spell card attempt, optionally as part of a stage segment.
+    pub fn record_attempt<G: Game>(
+        &self,
+        segment: Option<SegmentId>,
+        attempt: &Attempt<G>,
+    ) -> Result<(), StorageError> {
+        self.conn
+            .execute(
+                "
+                INSERT INTO attempts (segment_id, game, card_id, shot_type, difficulty, captured, time)
+                VALUES (?, ?, ?, ?, ?, ?, ?)
+                ",
+                params![
+                    segment.map(|s| s.0),
+                    G::GAME_ID.number(),
+                    attempt.card.id(),
+                    attempt.shot_type.unwrap().raw_id(),
+                    attempt.difficulty.unwrap().raw_id(),
+                    attempt.captured,
+                    to_millis(attempt.time)
+                ],
+            )
+            .map(|_| ())
+            .map_err(StorageError::from)
+    }
+
+    /// Record a snapshot of the score file statistics for a spell card.
+    pub fn record_snapshot<G: Game>(&self, snapshot: &CardSnapshot<G>) -> Result<(), StorageError> {
+        self.conn
+            .execute(
+                "
+                INSERT INTO score_snapshots (game, card_id, shot_type, difficulty, attempts, captures, max_bonus, time)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                ",
+                params![
+                    G::GAME_ID.number(),
+                    snapshot.card.id(),
+                    snapshot.shot_type.unwrap().raw_id(),
+                    snapshot.card.difficulty().unwrap().raw_id(),
+                    snapshot.attempts,
+                    snapshot.captures,
+                    snapshot.max_bonus,
+                    to_millis(snapshot.time)
+                ],
+            )
+            .map(|_| ())
+            .map_err(StorageError::from)
+    }
+
+    /// Record snapshots for every spell card and shot type in a score file, returning the number of
+    /// snapshots recorded.
+    #[cfg(feature = "score-file")]
+    pub fn record_score_file<G, F>(
+        &mut self,
+        time: OffsetDateTime,
+        score_file: &F,
+    ) -> Result<usize, StorageError>
+    where
+        G: Game,
+        F: ScoreFile<G>,
+    {
+        let tx = self.conn.transaction()?;
+        let mut count = 0;
+
+        {
+            let mut stmt = tx.prepare(
+                "
+                INSERT INTO score_snapshots (game, card_id, shot_type, difficulty, attempts, captures, max_bonus, time)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                ",
+            )?;
+
+            for record in score_file.spell_cards() {
+                let card = record.card();
+                for shot_type in record.shot_types() {
+                    stmt.execute(params![
+                        G::GAME_ID.number(),
+                        card.id(),
+                        shot_type.unwrap().raw_id(),
+                        card.difficulty().unwrap().raw_id(),
+                        record.attempts(shot_type),
+                        record.captures(shot_type),
+                        record.max_bonus(shot_type),
+                        to_millis(time)
+                    ])?;
+                    count += 1;
+                }
+            }
+        }
+
+        tx.commit()?;
+        Ok(count)
+    }
+
+    /// Get all recorded attempts matching a filter, in chronological order.
+    pub fn attempts<G: Game>(
+        &self,
+        filter: &HistoryFilter<G>,
+    ) -> Result<Vec<Attempt<G>>, StorageError> {
+        let (cond, values) = filter.condition();
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT time, card_id, shot_type, difficulty, captured FROM attempts WHERE {} ORDER BY time, id",
+            cond
+        ))?;
+
+        let rows = stmt
+            .query_map(params_from_iter(values.iter()), |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, i64>(3)?,
+                    row.get::<_, bool>(4)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        rows.into_iter()
+            .map(|(time, card_id, shot_type, difficulty, captured)| {
+                Ok(Attempt {
+                    time: from_millis::<G>(time)?,
+                    card: SpellCard::new(decode_value::<G, G::SpellID>("card_id", card_id)?),
+                    shot_type: ShotType::new(decode_value::<G, G::ShotTypeID>(
+                        "shot_type",
+                        shot_type,
+                    )?),
+                    difficulty: Difficulty::new(decode_value::<G, G::DifficultyID>(
+                        "difficulty",
+                        difficulty,
+                    )?),
+                    captured,
+                })
+            })
+            .collect()
+    }
+
+    /// Get all recorded score file snapshots matching a filter, in chronological order.
+    pub fn snapshots<G: Game>(
+        &self,
+        filter: &HistoryFilter<G>,
+    ) -> Result<Vec<CardSnapshot<G>>, StorageError> {
+        let (cond, values) = filter.condition();
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT time, card_id, shot_type, attempts, captures, max_bonus FROM score_snapshots WHERE {} ORDER BY time, id",
+            cond
+        ))?;
+
+        let rows = stmt
+            .query_map(params_from_iter(values.iter()), |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, u32>(3)?,
+                    row.get::<_, u32>(4)?,
+                    row.get::<_, u32>(5)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        rows.into_iter()
+            .map(
+                |(time, card_id, shot_type, attempts, captures, max_bonus)| {
+                    Ok(CardSnapshot {
+                        time: from_millis::<G>(time)?,
+                        card: SpellCard::new(decode_value::<G, G::SpellID>("card_id", card_id)?),
+                        shot_type: ShotType::new(decode_value::<G, G::ShotTypeID>(
+                            "shot_type",
+                            shot_type,
+                        )?),
+                        attempts,
+                        captures,
+                        max_bonus,
+                    })
+                },
+            )
+            .collect()
+    }
+}
+
+#[cfg(all(test, feature = "th07"))]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::th07::{
+        Difficulty as Th07Difficulty, ShotType as Th07Shot, SpellId, Stage as Th07Stage,
+    };
+    use crate::Touhou7;
+
+    #[test]
+    fn rolling_capture_rates() {
+        let storage = Storage::open_in_memory().unwrap();
+        let start = OffsetDateTime::from_unix_timestamp(1_000_000).unwrap();
+        let card = SpellCard::<Touhou7>::new(SpellId::new(42).unwrap());
+        let other_card = SpellCard::<Touhou7>::new(SpellId::new(43).unwrap());
+        let shot_type = ShotType::new(Th07Shot::ReimuA);
+        let difficulty = Difficulty::new(Th07Difficulty::Normal);
+
+        let run = storage
+            .start_run(shot_type, difficulty, true, start)
+            .unwrap();
+        let segment = storage
+            .start_segment(run, Stage::<Touhou7>::new(Th07Stage::Four), start)
+            .unwrap();
+        assert_eq!(storage.segment_run(segment).unwrap(), Some(run));
+
+        // Attempts one minute apart: miss, capture, capture, miss, capture
+        for (i, captured) in [false, true, true, false, true].into_iter().enumerate() {
+            let attempt = Attempt {
+                time: start + Duration::from_secs(60 * i as u64),
+                card,
+                shot_type,
+                difficulty,
+                captured,
+            };
+            storage.record_attempt(Some(segment), &attempt).unwrap();
+        }
+
+        let other = Attempt {
+            time: start,
+            card: other_card,
+            shot_type,
+            difficulty,
+            captured: true,
+        };
+        storage.record_attempt(None, &other).unwrap();
+        storage
+            .end_run(run, start + Duration::from_secs(600))
+            .unwrap();
+
+        let filter = HistoryFilter::new().with_card(card);
+        let attempts = storage.attempts(&filter).unwrap();
+        assert_eq!(attempts.len(), 5);
+        assert_eq!(attempts[1].card, card);
+        assert!(attempts[1].captured);
+
+        let rolling = storage
+            .rolling_capture_rates(&filter, Window::Attempts(2))
+            .unwrap();
+        let captures: Vec<_> = rolling.iter().map(|r| r.rate.captures).collect();
+        assert_eq!(captures, [0, 1, 2, 1, 1]);
+        assert_eq!(rolling[0].rate.attempts, 1);
+        assert_eq!(rolling[4].rate.attempts, 2);
+
+        let rolling = storage
+            .rolling_capture_rates(&filter, Window::Time(Duration::from_secs(150)))
+            .unwrap();
+        let attempts: Vec<_> = rolling.iter().map(|r| r.rate.attempts).collect();
+        assert_eq!(attempts, [1, 2, 3, 3, 3]);
+
+        let rate = storage.capture_rate(&filter, Window::All).unwrap();
+        assert_eq!(
+            rate,
+            CaptureRate {
+                attempts: 5,
+                captures: 3
+            }
+        );
+        let rate = storage.capture_rate(&filter, Window::Attempts(3)).unwrap();
+        assert_eq!(
+            rate,
+            CaptureRate {
+                attempts: 3,
+                captures: 2
+            }
+        );
+        let rate = storage
+            .capture_rate(&filter, Window::Time(Duration::from_secs(60)))
+            .unwrap();
+        assert_eq!(
+            rate,
+            CaptureRate {
+                attempts: 2,
+                captures: 1
+            }
+        );
+
+        let rate = storage
+            .capture_rate(&HistoryFilter::new().with_shot_type(shot_type), Window::All)
+            .unwrap();
+        assert_eq!(
+            rate,
+            CaptureRate {
+                attempts: 6,
+                captures: 4
+            }
+        );
+
+        let rate = storage
+            .capture_rate(
+                &filter.clone().since(start + Duration::from_secs(120)),
+                Window::All,
+            )
+            .unwrap();
+        assert_eq!(
+            rate,
+            CaptureRate {
+                attempts: 3,
+                captures: 2
+            }
+        );
+    }
+}
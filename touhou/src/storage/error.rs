@@ -0,0 +1,59 @@
+use std::error::Error;
+use std::fmt::Display;
+
+use crate::types::GameId;
+
+/// An error encountered while reading from or writing to a [`Storage`](super::Storage) database.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum StorageError {
+    /// An error reported by SQLite.
+    Sqlite(rusqlite::Error),
+    /// The database was created by a newer version of this crate.
+    UnsupportedVersion { found: i64, supported: i64 },
+    /// A stored value could not be converted back into a game-specific type.
+    InvalidValue {
+        game: GameId,
+        column: &'static str,
+        value: i64,
+    },
+}
+
+impl From<rusqlite::Error> for StorageError {
+    fn from(value: rusqlite::Error) -> Self {
+        Self::Sqlite(value)
+    }
+}
+
+impl Display for StorageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sqlite(err) => write!(f, "database error: {}", err),
+            Self::UnsupportedVersion { found, supported } => write!(
+                f,
+                "unsupported database schema version {} (latest supported version is {})",
+                found, supported
+            ),
+            Self::InvalidValue {
+                game,
+                column,
+                value,
+            } => write!(
+                f,
+                "invalid {} value {} stored for {}",
+                column,
+                value,
+                game.abbreviation()
+            ),
+        }
+    }
+}
+
+impl Error for StorageError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Sqlite(err) => Some(err),
+            Self::UnsupportedVersion { .. } | Self::InvalidValue { .. } => None,
+        }
+    }
+}
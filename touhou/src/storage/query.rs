@@ -0,0 +1,261 @@
+use std::time::Duration;
+
+use rusqlite::params_from_iter;
+use time::OffsetDateTime;
+
+use super::{from_millis, to_millis, Storage, StorageError};
+use crate::types::{Difficulty, Game, GameValue, ShotType, SpellCard};
+
+/// Selects which attempts or snapshots are included in a query.
+///
+/// An empty filter matches every record for the game `G`.
+#[derive(Debug, Clone)]
+pub struct HistoryFilter<G: Game> {
+    cards: Vec<SpellCard<G>>,
+    shot_types: Vec<ShotType<G>>,
+    difficulties: Vec<Difficulty<G>>,
+    since: Option<OffsetDateTime>,
+    until: Option<OffsetDateTime>,
+}
+
+impl<G: Game> Default for HistoryFilter<G> {
+    fn default() -> Self {
+        Self {
+            cards: Vec::new(),
+            shot_types: Vec::new(),
+            difficulties: Vec::new(),
+            since: None,
+            until: None,
+        }
+    }
+}
+
+fn push_any(cond: &mut Vec<String>, values: &mut Vec<i64>, column: &str, ids: &[i64]) {
+    if !ids.is_empty() {
+        cond.push(format!(
+            "{} IN ({})",
+            column,
+            vec!["?"; ids.len()].join(", ")
+        ));
+        values.extend_from_slice(ids);
+    }
+}
+
+impl<G: Game> HistoryFilter<G> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_card(mut self, card: SpellCard<G>) -> Self {
+        self.cards.push(card);
+        self
+    }
+
+    pub fn with_shot_type(mut self, shot_type: ShotType<G>) -> Self {
+        self.shot_types.push(shot_type);
+        self
+    }
+
+    pub fn with_difficulty(mut self, difficulty: Difficulty<G>) -> Self {
+        self.difficulties.push(difficulty);
+        self
+    }
+
+    /// Only include records from at or after the given time.
+    pub fn since(mut self, time: OffsetDateTime) -> Self {
+        self.since = Some(time);
+        self
+    }
+
+    /// Only include records from before the given time.
+    pub fn until(mut self, time: OffsetDateTime) -> Self {
+        self.until = Some(time);
+        self
+    }
+
+    /// Build an SQL condition (and its parameters) that matches this filter.
+    ///
+    /// This assumes that the queried table has `game`, `card_id`, `shot_type`, `difficulty`,
+    /// and `time` columns.
+    pub(super) fn condition(&self) -> (String, Vec<i64>) {
+        let mut cond = vec![String::from("game = ?")];
+        let mut values = vec![G::GAME_ID.number() as i64];
+
+        let cards: Vec<i64> = self.cards.iter().map(|c| c.id() as i64).collect();
+        let shot_types: Vec<i64> = self
+            .shot_types
+            .iter()
+            .map(|s| s.unwrap().raw_id() as i64)
+            .collect();
+        let difficulties: Vec<i64> = self
+            .difficulties
+            .iter()
+            .map(|d| d.unwrap().raw_id() as i64)
+            .collect();
+
+        push_any(&mut cond, &mut values, "card_id", &cards);
+        push_any(&mut cond, &mut values, "shot_type", &shot_types);
+        push_any(&mut cond, &mut values, "difficulty", &difficulties);
+
+        if let Some(since) = self.since {
+            cond.push(String::from("time >= ?"));
+            values.push(to_millis(since));
+        }
+
+        if let Some(until) = self.until {
+            cond.push(String::from("time < ?"));
+            values.push(to_millis(until));
+        }
+
+        (cond.join(" AND "), values)
+    }
+}
+
+/// The span of attempts that a capture rate is computed over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Window {
+    /// Every matching attempt.
+    All,
+    /// The most recent `n` matching attempts.
+    Attempts(u32),
+    /// Matching attempts made within the given span of time.
+    Time(Duration),
+}
+
+impl Window {
+    fn frame(&self) -> String {
+        match self {
+            Self::All => String::from("ROWS BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW"),
+            Self::Attempts(n) => format!(
+                "ROWS BETWEEN {} PRECEDING AND CURRENT ROW",
+                n.saturating_sub(1)
+            ),
+            Self::Time(span) => format!(
+                "RANGE BETWEEN {} PRECEDING AND CURRENT ROW",
+                span.as_millis().min(i64::MAX as u128)
+            ),
+        }
+    }
+}
+
+/// Attempt and capture counts over some set of attempts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CaptureRate {
+    pub attempts: u32,
+    pub captures: u32,
+}
+
+impl CaptureRate {
+    /// Get the fraction of attempts that were captures, or `None` if there were no attempts.
+    pub fn rate(&self) -> Option<f64> {
+        if self.attempts > 0 {
+            Some((self.captures as f64) / (self.attempts as f64))
+        } else {
+            None
+        }
+    }
+}
+
+/// The capture rate over a window ending at a specific attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RollingCaptureRate {
+    /// The time of the attempt ending this window.
+    pub time: OffsetDateTime,
+    /// Whether the attempt ending this window was a capture.
+    pub captured: bool,
+    pub rate: CaptureRate,
+}
+
+impl Storage {
+    /// Compute the capture rate over the most recent window of attempts matching a filter.
+    ///
+    /// Time windows end at the most recent matching attempt, rather than at the current time.
+    pub fn capture_rate<G: Game>(
+        &self,
+        filter: &HistoryFilter<G>,
+        window: Window,
+    ) -> Result<CaptureRate, StorageError> {
+        let (cond, mut values) = filter.condition();
+        let query = match window {
+            Window::All => format!("SELECT captured FROM attempts WHERE {}", cond),
+            Window::Attempts(n) => format!(
+                "SELECT captured FROM attempts WHERE {} ORDER BY time DESC, id DESC LIMIT {}",
+                cond, n
+            ),
+            Window::Time(span) => {
+                values.extend_from_within(..);
+                format!(
+                    "SELECT captured FROM attempts WHERE {0} AND time >= (SELECT MAX(time) FROM attempts WHERE {0}) - {1}",
+                    cond,
+                    span.as_millis().min(i64::MAX as u128)
+                )
+            }
+        };
+
+        self.conn
+            .query_row(
+                &format!(
+                    "SELECT COUNT(*), COALESCE(SUM(captured), 0) FROM ({})",
+                    query
+                ),
+                params_from_iter(values.iter()),
+                |row| {
+                    Ok(CaptureRate {
+                        attempts: row.get(0)?,
+                        captures: row.get(1)?,
+                    })
+                },
+            )
+            .map_err(StorageError::from)
+    }
+
+    /// Compute the capture rate over a rolling window ending at each attempt matching a filter,
+    /// in chronological order.
+    ///
+    /// Only attempts matching the filter are included in each window.
+    pub fn rolling_capture_rates<G: Game>(
+        &self,
+        filter: &HistoryFilter<G>,
+        window: Window,
+    ) -> Result<Vec<RollingCaptureRate>, StorageError> {
+        let (cond, values) = filter.condition();
+        let order = match window {
+            Window::Time(_) => "time",
+            _ => "time, id",
+        };
+
+        let mut stmt = self.conn.prepare(&format!(
+            "
+            SELECT time, captured, COUNT(*) OVER w, SUM(captured) OVER w
+            FROM attempts
+            WHERE {}
+            WINDOW w AS (ORDER BY {} {})
+            ORDER BY time, id
+            ",
+            cond,
+            order,
+            window.frame()
+        ))?;
+
+        let rows = stmt
+            .query_map(params_from_iter(values.iter()), |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, bool>(1)?,
+                    row.get::<_, u32>(2)?,
+                    row.get::<_, u32>(3)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        rows.into_iter()
+            .map(|(time, captured, attempts, captures)| {
+                Ok(RollingCaptureRate {
+                    time: from_millis::<G>(time)?,
+                    captured,
+                    rate: CaptureRate { attempts, captures },
+                })
+            })
+            .collect()
+    }
+}
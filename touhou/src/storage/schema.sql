@@ -0,0 +1,53 @@
+-- Schema for the capture statistics store.
+--
+-- All timestamps are stored as milliseconds since the Unix epoch (UTC).
+-- Game-specific values (cards, shot types, stages, difficulties) are stored as raw IDs,
+-- and are always paired with a game number.
+
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    game INTEGER NOT NULL,
+    shot_type INTEGER NOT NULL,
+    difficulty INTEGER NOT NULL,
+    practice INTEGER NOT NULL,
+    start_time INTEGER NOT NULL,
+    end_time INTEGER
+);
+
+CREATE TABLE IF NOT EXISTS segments (
+    id INTEGER PRIMARY KEY,
+    run_id INTEGER NOT NULL REFERENCES runs (id) ON DELETE CASCADE,
+    stage INTEGER NOT NULL,
+    start_time INTEGER NOT NULL,
+    end_time INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS segments_run ON segments (run_id);
+
+CREATE TABLE IF NOT EXISTS attempts (
+    id INTEGER PRIMARY KEY,
+    segment_id INTEGER REFERENCES segments (id) ON DELETE CASCADE,
+    game INTEGER NOT NULL,
+    card_id INTEGER NOT NULL,
+    shot_type INTEGER NOT NULL,
+    difficulty INTEGER NOT NULL,
+    captured INTEGER NOT NULL,
+    time INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS attempts_card ON attempts (game, card_id, shot_type, time);
+CREATE INDEX IF NOT EXISTS attempts_time ON attempts (game, time);
+
+CREATE TABLE IF NOT EXISTS score_snapshots (
+    id INTEGER PRIMARY KEY,
+    game INTEGER NOT NULL,
+    card_id INTEGER NOT NULL,
+    shot_type INTEGER NOT NULL,
+    difficulty INTEGER NOT NULL,
+    attempts INTEGER NOT NULL,
+    captures INTEGER NOT NULL,
+    max_bonus INTEGER NOT NULL,
+    time INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS score_snapshots_card ON score_snapshots (game, card_id, shot_type, time);
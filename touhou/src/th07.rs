@@ -9,6 +9,8 @@ pub mod memory;
 
 mod spellcards;
 
+#[cfg(feature = "replay-file")]
+pub mod replay;
 #[cfg(feature = "score-file")]
 pub mod score;
 
@@ -16,6 +18,8 @@ pub mod score;
 pub use memory::tracking::Touhou7Event;
 #[cfg(feature = "memory")]
 pub use memory::{GameMemory, Location};
+#[cfg(feature = "replay-file")]
+pub use replay::ReplayFile;
 #[cfg(feature = "score-file")]
 pub use score::{PracticeData, ScoreFile, SpellCardData};
 pub use spellcards::SpellId;
@@ -75,6 +79,22 @@ impl Touhou7 {
     }
 }
 
+#[cfg(feature = "score-file")]
+impl crate::score::HasScoreFile for Touhou7 {
+    type ScoreFile = ScoreFile;
+
+    fn load_score_file<R: std::io::Read>(src: R) -> Result<ScoreFile, std::io::Error> {
+        ScoreFile::new(src)
+    }
+}
+
+#[cfg(feature = "replay-file")]
+impl Touhou7 {
+    pub fn load_replay_file<R: std::io::Read>(src: R) -> Result<ReplayFile, std::io::Error> {
+        ReplayFile::new(src)
+    }
+}
+
 #[cfg(feature = "find-process")]
 impl Touhou7 {
     pub fn find_process(system: &System) -> Option<&Process> {
@@ -82,17 +102,16 @@ impl Touhou7 {
             .processes()
             .iter()
             .map(|(_, process)| process)
-            .find(|&process| {
-                process
-                    .exe()
-                    .file_stem()
-                    .and_then(|s| s.to_str())
-                    .map(|s| s.starts_with("th07"))
-                    .unwrap_or(false)
-            })
+            .find(|&process| crate::process::is_game_process(process, &["th07"]))
     }
 
+    /// Get the path to the game's score file.
+    ///
+    /// For games running under Wine, this resolves the game's real directory within the Wine prefix; if that
+    /// can't be determined, this falls back to `score.dat` next to the reported executable.
     pub fn find_score_file(proc: &Process) -> std::path::PathBuf {
-        proc.exe().with_file_name("score.dat")
+        crate::process::GamePaths::from_process(GameId::PCB, proc)
+            .map(|paths| paths.score_file())
+            .unwrap_or_else(|| proc.exe().with_file_name("score.dat"))
     }
 }
@@ -4,7 +4,6 @@ use crate::th07::{SpellId, Stage, Touhou7};
 
 define_locations! {
     #[game = "Touhou7"]
-    #[exclude_stages = "Extra, Phantasm"]
     Location {
         One: {
             540 => Section,
@@ -112,6 +111,85 @@ define_locations! {
                 Spells(109..=112),
                 Spells(113..=116)
             ]
+        },
+        Extra: {
+            60 => Section,
+            2210 => Midboss [
+                Nonspell,
+                Spells(117..=117),
+                Spells(118..=118)
+            ],
+            2270 => Section,
+            9150 => Boss [
+                Nonspell,
+                Spells(119..=119),
+                Nonspell,
+                Spells(120..=120),
+                Nonspell,
+                Spells(121..=121),
+                Nonspell,
+                Spells(122..=122),
+                Nonspell,
+                Spells(123..=123),
+                Nonspell,
+                Spells(124..=124),
+                Nonspell,
+                Spells(125..=125),
+                Spells(126..=126),
+                Spells(127..=127),
+                Spells(128..=128)
+            ]
+        },
+        Phantasm: {
+            60 => Section,
+            2390 => Midboss [
+                Nonspell,
+                Spells(129..=129),
+                Spells(130..=130)
+            ],
+            2450 => Section,
+            9570 => Boss [
+                Nonspell,
+                Spells(131..=131),
+                Nonspell,
+                Spells(132..=132),
+                Nonspell,
+                Spells(133..=133),
+                Nonspell,
+                Spells(134..=134),
+                Nonspell,
+                Spells(135..=135),
+                Nonspell,
+                Spells(136..=136),
+                Nonspell,
+                Spells(137..=137),
+                Spells(138..=138),
+                Spells(139..=139),
+                Spells(140..=140),
+                Spells(141..=141)
+            ]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::Location as LocationWrapper;
+    use crate::types::{AllIterable, SpellCard};
+
+    #[test]
+    fn extra_phantasm_spell_locations() {
+        let spells = SpellId::iter_all()
+            .map(SpellCard::<Touhou7>::new)
+            .filter(|spell| matches!(spell.stage().unwrap(), Stage::Extra | Stage::Phantasm));
+
+        for spell in spells {
+            let location = LocationWrapper::from_spell(spell)
+                .unwrap_or_else(|| panic!("no location for {}", spell.name()));
+
+            assert_eq!(location.stage(), spell.stage());
+            assert_eq!(location.spell(), Some(spell));
         }
     }
 }
@@ -8,7 +8,7 @@ use crate::tracking::builder::TrackerBuilder;
 use crate::tracking::state::{ContinuesUsed, CurrentPause, TotalBombsUsed, TotalMisses};
 use crate::tracking::{
     DriveTracker, EventTime, GameTracker, IntoGameTracker, TrackGame, TrackRun, TrackStagePractice,
-    TrackableGame, TrackerState, TrackingType, UpdateStatus,
+    TrackableGame, TrackerState, TrackingType, UpdateResult, UpdateStatus,
 };
 use crate::Touhou7;
 
@@ -45,7 +45,8 @@ impl Deref for TrackedState {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum Touhou7Event {
     BorderStart,
     BorderEnd { broken: bool },
@@ -201,11 +202,13 @@ where
         })
     }
 
-    fn update(
-        mut self,
-        access: &MemoryAccess,
-    ) -> Result<UpdateStatus<Touhou7, T, Self>, MemoryReadError<Touhou7>> {
-        match GameState::new(access)? {
+    fn update(mut self, access: &MemoryAccess) -> UpdateResult<Touhou7, T, Self> {
+        let state = match GameState::new(access) {
+            Ok(state) => state,
+            Err(e) => return Err((self, e)),
+        };
+
+        match state {
             GameState::InGame { run } => {
                 self.update_state(run);
                 Ok(UpdateStatus::Continuing(self))
@@ -221,6 +224,10 @@ where
     fn terminate(self) -> T::Output {
         self.finish(false, None)
     }
+
+    fn tracker_mut(&mut self) -> &mut T {
+        self.tracker.tracker_mut()
+    }
 }
 
 impl<T> GameTracker<Touhou7, T, ActiveRun<T>>
@@ -243,3 +250,153 @@ where
         GameTracker::new(self)
     }
 }
+
+#[cfg(all(test, feature = "replay"))]
+mod tests {
+    use std::path::PathBuf;
+
+    use serde::Serialize;
+
+    use super::*;
+    use crate::memory::replay::{MemoryFrame, MemoryRecorder};
+    use crate::memory::ResolveLocation;
+    use crate::th07::{Difficulty, ShotType};
+    use crate::tracking::recorder::RecordKind;
+    use crate::tracking::{Event, RunRecorder};
+
+    fn set<T: Serialize>(frame: &mut MemoryFrame, field: &str, value: T) {
+        frame.insert(field, Ok::<T, &str>(value));
+    }
+
+    /// A frame from an unpaused Reimu A game on Normal, partway through stage 1.
+    fn in_game(ecl_time: u32, misses: u8) -> MemoryFrame {
+        let mut frame = MemoryFrame::new();
+        set(&mut frame, "stage", 1u32);
+        set(&mut frame, "menu_state", 0u32);
+        set(&mut frame, "game_state", 2u32);
+        set(&mut frame, "game_mode", 0x04u8);
+        set(&mut frame, "difficulty", 1u32);
+        set(&mut frame, "ecl_time", ecl_time);
+        set(&mut frame, "spell_active", 0u32);
+        set(&mut frame, "spell_captured", 0u32);
+        set(&mut frame, "current_spell_id", 0u32);
+        set(&mut frame, "boss_flag", 0u32);
+        set(&mut frame, "midboss_flag", 0u8);
+        set(&mut frame, "boss_id", 0u8);
+        set(&mut frame, "boss_healthbars", 0u32);
+        set(&mut frame, "player_character", 0u8);
+        set(&mut frame, "player_lives", 2.0f32 - (misses as f32));
+        set(&mut frame, "player_bombs", 3.0f32);
+        set(&mut frame, "player_power", 0.0f32);
+        set(&mut frame, "player_misses", misses as f32);
+        set(&mut frame, "player_bombs_used", 0.0f32);
+        set(&mut frame, "player_continues", 0u8);
+        set(&mut frame, "border_state", 0u8);
+        set(&mut frame, "score", 1_000_000u32);
+        set(&mut frame, "graze", 0u32);
+        set(&mut frame, "cherry_base", 0u32);
+        set(&mut frame, "cherry", 0u32);
+        set(&mut frame, "cherry_max", 50_000u32);
+        set(&mut frame, "cherry_plus", 0u32);
+        frame
+    }
+
+    fn with_game_state(mut frame: MemoryFrame, game_state: u32) -> MemoryFrame {
+        set(&mut frame, "game_state", game_state);
+        frame
+    }
+
+    fn recorded_frames() -> Vec<MemoryFrame> {
+        let mut title_screen = with_game_state(in_game(0, 0), 1);
+        set(&mut title_screen, "menu_state", 130u32);
+
+        vec![
+            title_screen.clone(),
+            in_game(600, 0),
+            in_game(610, 0),
+            in_game(1400, 0),
+            in_game(1410, 1),
+            in_game(1420, 1),
+            with_game_state(in_game(1430, 1), 6),
+            title_screen,
+        ]
+    }
+
+    fn write_recording(frames: &[MemoryFrame]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "thstats-th07-recording-{}.jsonl",
+            std::process::id()
+        ));
+
+        let mut recorder = MemoryRecorder::create(&path).unwrap();
+        for frame in frames {
+            recorder.record(frame);
+        }
+        recorder.finish().unwrap();
+
+        path
+    }
+
+    #[test]
+    fn replay_resolves_locations() {
+        let mut memory = GameMemory::from_frames(recorded_frames());
+        let mut locations = Vec::new();
+
+        while memory.advance() {
+            // reading fields within a poll shouldn't move on to the next frame
+            let ecl_time = memory.ecl_time().unwrap();
+            assert_eq!(memory.ecl_time().unwrap(), ecl_time);
+
+            if let GameState::InGame { run } = GameState::new(memory.access().unwrap()).unwrap() {
+                assert_eq!(Some(run.stage().ecl_time()), ecl_time);
+                locations.push(run.resolve_location().map(|location| location.name()));
+            }
+        }
+
+        assert_eq!(
+            locations,
+            [
+                Some("First Half 1"),
+                Some("First Half 1"),
+                Some("First Half 2"),
+                Some("First Half 2"),
+                Some("First Half 2"),
+            ]
+        );
+        assert!(memory.access().is_none());
+    }
+
+    #[test]
+    fn replay_drives_tracker() {
+        let frames = recorded_frames();
+        let path = write_recording(&frames);
+        let memory = GameMemory::from_recording(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut tracker: GameTracker<Touhou7, RunRecorder<Touhou7>, _> =
+            memory.track_games().with_init_delay(Duration::ZERO);
+        let mut polls = 0;
+        let mut runs = Vec::new();
+
+        while tracker.is_running() {
+            polls += 1;
+            runs.extend(tracker.update().unwrap());
+        }
+
+        assert_eq!(polls, frames.len());
+        assert_eq!(runs.len(), 1);
+
+        let run = &runs[0];
+        assert_eq!(run.tracking_type(), TrackingType::FullRun);
+        assert_eq!(run.shot().unwrap(), ShotType::ReimuA);
+        assert_eq!(run.difficulty().unwrap(), Difficulty::Normal);
+        assert!(!run.cleared());
+
+        let misses = run
+            .records()
+            .iter()
+            .filter(|record| matches!(record.kind, RecordKind::Event(Event::Miss)))
+            .count();
+        assert_eq!(misses, 1);
+    }
+}
@@ -0,0 +1,270 @@
+use std::io::{self, Cursor, ErrorKind, Read};
+
+use byteorder::{LittleEndian, ReadBytesExt};
+
+use super::{Difficulty, ShotType as Th07Shot, Stage, Touhou7};
+use crate::replay::*;
+use crate::score::ShortDate;
+use crate::types::{Difficulty as DifficultyWrapper, ShotType, Stage as StageWrapper};
+
+const MAGIC: &[u8; 4] = b"T7RP";
+const KEY_OFFSET: usize = 0x0D;
+const ENCRYPTED_OFFSET: usize = 0x10;
+const DATA_OFFSET: usize = 0x54;
+const STAGE_HEADER_SZ: usize = 0x28;
+const INPUT_SZ: usize = 8;
+
+fn try_into_or_io_error<T, U>(kind: ErrorKind) -> impl FnOnce(T) -> io::Result<U>
+where
+    T: TryInto<U>,
+    T::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    move |val| {
+        val.try_into()
+            .map_err(move |error| io::Error::new(kind, error))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplayFile {
+    version: u16,
+    shot_type: Th07Shot,
+    difficulty: Difficulty,
+    date: ShortDate,
+    name: [u8; 9],
+    score: u32,
+    stages: Vec<StageReplay<Touhou7>>,
+}
+
+impl ReplayFile {
+    pub fn new<R: Read>(mut src: R) -> io::Result<Self> {
+        let mut data = Vec::new();
+        src.read_to_end(&mut data)?;
+
+        if data.len() < DATA_OFFSET || &data[..4] != MAGIC {
+            return Err(invalid_data("not a Touhou 7 replay file"));
+        }
+
+        let version = u16::from_le_bytes([data[4], data[5]]);
+        let key = data[KEY_OFFSET];
+        decrypt_additive(&mut data[ENCRYPTED_OFFSET..], key);
+
+        let mut header = Cursor::new(&data[ENCRYPTED_OFFSET..DATA_OFFSET]);
+        header.read_u32::<LittleEndian>()?;
+        let comp_size = header.read_u32::<LittleEndian>()? as usize;
+        let decomp_size = header.read_u32::<LittleEndian>()? as usize;
+
+        let mut stage_offsets = [0u32; 7];
+        for offset in stage_offsets.iter_mut() {
+            *offset = header.read_u32::<LittleEndian>()?;
+        }
+
+        let compressed = data
+            .get(DATA_OFFSET..(DATA_OFFSET + comp_size))
+            .ok_or_else(|| invalid_data("replay file is truncated"))?;
+        let body = decompress_body(compressed, decomp_size)?;
+
+        let mut src = Cursor::new(&body[..]);
+        src.read_u16::<LittleEndian>()?;
+
+        let shot_type: Th07Shot = src
+            .read_u8()
+            .and_then(try_into_or_io_error(ErrorKind::InvalidData))?;
+        let difficulty: Difficulty = src
+            .read_u8()
+            .and_then(try_into_or_io_error(ErrorKind::InvalidData))?;
+        let date = ShortDate::read_from(&mut src)?;
+
+        let mut name = [0; 9];
+        src.read_exact(&mut name)?;
+
+        let mut padding = [0; 5];
+        src.read_exact(&mut padding)?;
+        let score = src.read_u32::<LittleEndian>()?;
+
+        let stage_starts: Vec<(Stage, usize)> = stage_offsets
+            .iter()
+            .zip([
+                Stage::One,
+                Stage::Two,
+                Stage::Three,
+                Stage::Four,
+                Stage::Five,
+                Stage::Six,
+                // The Extra and Phantasm stages share the same slot.
+                if difficulty == Difficulty::Phantasm {
+                    Stage::Phantasm
+                } else {
+                    Stage::Extra
+                },
+            ])
+            .filter(|(offset, _)| **offset != 0)
+            .map(|(offset, stage)| {
+                (*offset as usize)
+                    .checked_sub(DATA_OFFSET)
+                    .map(|start| (stage, start))
+                    .ok_or_else(|| invalid_data(format!("invalid stage offset {:#x}", offset)))
+            })
+            .collect::<io::Result<_>>()?;
+
+        let mut stages = Vec::with_capacity(stage_starts.len());
+        for (i, &(stage, start)) in stage_starts.iter().enumerate() {
+            let end = stage_starts
+                .get(i + 1)
+                .map(|(_, next)| *next)
+                .unwrap_or(body.len());
+
+            let block = body
+                .get(start..end)
+                .filter(|block| block.len() >= STAGE_HEADER_SZ)
+                .ok_or_else(|| invalid_data(format!("invalid data for {}", stage)))?;
+
+            stages.push(Self::read_stage(stage, block)?);
+        }
+
+        Ok(Self {
+            version,
+            shot_type,
+            difficulty,
+            date,
+            name,
+            score,
+            stages,
+        })
+    }
+
+    fn read_stage(stage: Stage, block: &[u8]) -> io::Result<StageReplay<Touhou7>> {
+        let mut src = Cursor::new(block);
+
+        let score = src.read_u32::<LittleEndian>()?;
+        src.set_position(0x22);
+        let power = src.read_u8()?;
+        let lives = src.read_u8()?;
+        let bombs = src.read_u8()?;
+
+        let inputs = block[STAGE_HEADER_SZ..]
+            .chunks_exact(INPUT_SZ)
+            .map(|input| {
+                let frame = u32::from_le_bytes(input[..4].try_into().unwrap());
+                let keys = u16::from_le_bytes(input[4..6].try_into().unwrap());
+                InputFrame::new(frame, keys)
+            })
+            .collect();
+
+        Ok(StageReplay::new(
+            StageWrapper::new(stage),
+            (score as u64) * 10,
+            power as u16,
+            lives,
+            Some(bombs),
+            inputs,
+        ))
+    }
+
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    pub fn date(&self) -> ShortDate {
+        self.date
+    }
+}
+
+impl crate::replay::ReplayFile<Touhou7> for ReplayFile {
+    fn name(&self) -> &[u8] {
+        trim_name(&self.name)
+    }
+
+    fn shot_type(&self) -> ShotType<Touhou7> {
+        ShotType::new(self.shot_type)
+    }
+
+    fn difficulty(&self) -> DifficultyWrapper<Touhou7> {
+        DifficultyWrapper::new(self.difficulty)
+    }
+
+    fn score(&self) -> u64 {
+        (self.score as u64) * 10
+    }
+
+    fn stages(&self) -> &[StageReplay<Touhou7>] {
+        &self.stages[..]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::ReplayFile as _;
+
+    /// A complete replay file, built by hand with the additive cipher and an LZSS compressor written independently
+    /// of this crate's decoders.
+    ///
+    /// This is a Reimu B replay on Normal, covering stages 1 and 2.
+    const HAND_BUILT_REPLAY: [u8; 157] = [
+        0x54, 0x37, 0x52, 0x50, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+        0x00, 0x5a, 0x00, 0x00, 0x5a, 0x61, 0x68, 0x6f, 0xbf, 0x7d, 0x84, 0x8b, //
+        0x26, 0x99, 0xa0, 0xa7, 0x1e, 0xb5, 0xbc, 0xc3, 0x7a, 0xd1, 0xd8, 0xdf, //
+        0xe6, 0xed, 0xf4, 0xfb, 0x02, 0x09, 0x10, 0x17, 0x1e, 0x25, 0x2c, 0x33, //
+        0x3a, 0x41, 0x48, 0x4f, 0x56, 0x5d, 0x64, 0x6b, 0x72, 0x79, 0x80, 0x87, //
+        0x8e, 0x95, 0x9c, 0xa3, 0xaa, 0xb1, 0xb8, 0xbf, 0xc6, 0xcd, 0xd4, 0xdb, //
+        0xe2, 0xe9, 0xf0, 0xf7, 0xfe, 0x05, 0x0c, 0x13, 0x1a, 0x21, 0x28, 0x2f, //
+        0xb6, 0x7d, 0x64, 0x7b, 0x6b, 0xdd, 0x42, 0xc6, 0x9f, 0x0f, 0x3c, 0xad, //
+        0x9e, 0xcb, 0xad, 0xf1, 0x31, 0x00, 0x5e, 0xfb, 0xc2, 0xdb, 0x0c, 0x16, //
+        0x38, 0x09, 0xec, 0x85, 0xfa, 0x3a, 0xe8, 0x1d, 0x5a, 0x27, 0x2a, 0x2b, //
+        0x16, 0x88, 0x40, 0x59, 0x56, 0x5d, 0x5e, 0xa4, 0xa7, 0x71, 0xc0, 0xa1, //
+        0xa6, 0x96, 0x99, 0xde, 0x28, 0xa9, 0x97, 0x37, 0xf7, 0x3d, 0xd4, 0xdf, //
+        0xe2, 0xe2, 0x0c, 0xf0, 0x3a, 0x62, 0x84, 0x33, 0x9a, 0x1b, 0x60, 0x27, //
+        0x2e, //
+    ];
+
+    #[test]
+    fn decode_hand_built_replay() {
+        let replay = ReplayFile::new(&HAND_BUILT_REPLAY[..]).unwrap();
+        assert_eq!(replay.version(), 0x0100);
+        assert_eq!(replay.name(), b"PCBTEST");
+        assert_eq!(replay.shot_type().unwrap(), Th07Shot::ReimuB);
+        assert_eq!(replay.difficulty().unwrap(), Difficulty::Normal);
+        assert_eq!(replay.date().to_string(), "08/15");
+        assert_eq!(replay.score(), 12_345_670);
+
+        let stages = replay.stages();
+        assert_eq!(stages.len(), 2);
+
+        assert_eq!(stages[0].stage().unwrap(), Stage::One);
+        assert_eq!(stages[0].score(), 0);
+        assert_eq!(stages[0].power(), 0);
+        assert_eq!(stages[0].lives(), 2);
+        assert_eq!(stages[0].bombs(), Some(3));
+        assert_eq!(
+            stages[0].inputs(),
+            [
+                InputFrame::new(0, 0),
+                InputFrame::new(60, 0x01),
+                InputFrame::new(61, 0x11)
+            ]
+        );
+
+        assert_eq!(stages[1].stage().unwrap(), Stage::Two);
+        assert_eq!(stages[1].score(), 500_000);
+        assert_eq!(stages[1].power(), 128);
+        assert_eq!(stages[1].lives(), 1);
+        assert_eq!(stages[1].bombs(), Some(2));
+        assert_eq!(
+            stages[1].inputs(),
+            [InputFrame::new(0, 0x01), InputFrame::new(300, 0)]
+        );
+    }
+
+    #[test]
+    fn invalid_replays() {
+        let mut file = HAND_BUILT_REPLAY.to_vec();
+        file[..4].copy_from_slice(b"T8RP");
+        let err = ReplayFile::new(&file[..]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        let truncated = &HAND_BUILT_REPLAY[..(HAND_BUILT_REPLAY.len() - 1)];
+        let err = ReplayFile::new(truncated).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
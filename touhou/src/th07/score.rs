@@ -1,11 +1,11 @@
 use std::convert::TryInto;
 use std::fmt::Debug;
-use std::io::{self, Cursor, ErrorKind, Read};
+use std::io::{self, Cursor, ErrorKind, Read, Write};
 use std::str;
 
-use byteorder::{LittleEndian, ReadBytesExt};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 
-use super::{Difficulty, ShotType as Th07Shot, Stage, Touhou7};
+use super::{Difficulty, ShotType as Th07Shot, SpellId, Stage, Touhou7};
 use crate::score::*;
 use crate::types::{
     Difficulty as DifficultyWrapper, ShotType, SpellCard, Stage as StageWrapper, StageProgress,
@@ -80,6 +80,13 @@ macro_rules! read_try_into {
     }};
 }
 
+macro_rules! write_try_into {
+    ($t:ty : $x:expr) => {{
+        use std::io::{self, ErrorKind};
+        <$t as TryFrom<_>>::try_from($x).map_err(|e| io::Error::new(ErrorKind::InvalidInput, e))
+    }};
+}
+
 macro_rules! return_none_on_eof {
     ($x:expr) => {
         match $x {
@@ -112,6 +119,13 @@ impl StoredTime {
             milliseconds: src.read_u32::<LittleEndian>()?,
         })
     }
+
+    pub fn write_to<W: WriteBytesExt>(&self, dst: &mut W) -> Result<(), io::Error> {
+        dst.write_u32::<LittleEndian>(self.hours)?;
+        dst.write_u32::<LittleEndian>(self.minutes)?;
+        dst.write_u32::<LittleEndian>(self.seconds)?;
+        dst.write_u32::<LittleEndian>(self.milliseconds)
+    }
 }
 
 impl_getters! {
@@ -122,7 +136,7 @@ impl_getters! {
     milliseconds: u32
 }
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct PlayCount {
     total_attempts: u32,
     attempts: [u32; 6],
@@ -143,6 +157,17 @@ impl PlayCount {
             practices: src.read_u32::<LittleEndian>()?,
         })
     }
+
+    pub fn write_to<W: WriteBytesExt>(&self, dst: &mut W) -> Result<(), io::Error> {
+        dst.write_u32::<LittleEndian>(self.total_attempts)?;
+        for attempts in self.attempts {
+            dst.write_u32::<LittleEndian>(attempts)?;
+        }
+        dst.write_u32::<LittleEndian>(self.retries)?;
+        dst.write_u32::<LittleEndian>(self.clears)?;
+        dst.write_u32::<LittleEndian>(self.continues)?;
+        dst.write_u32::<LittleEndian>(self.practices)
+    }
 }
 
 access_by_shot! {
@@ -159,7 +184,7 @@ impl_getters! {
     practices: u32
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct HighScore {
     score: u32,
     slow: f32,
@@ -220,6 +245,39 @@ impl HighScore {
             continues,
         })
     }
+
+    pub fn write_to<W: WriteBytesExt>(&self, dst: &mut W) -> Result<(), io::Error> {
+        let progress = match self.progress {
+            StageProgress::NotStarted => 0,
+            StageProgress::LostAt(stage) => match stage.unwrap() {
+                Stage::One => 1,
+                Stage::Two => 2,
+                Stage::Three => 3,
+                Stage::Four => 4,
+                Stage::Five => 5,
+                Stage::Six => 6,
+                Stage::Extra => 7,
+                Stage::Phantasm => 8,
+            },
+            StageProgress::AllClear => 99,
+            StageProgress::StageCleared(_) => {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("cannot write stage progress value {}", self.progress),
+                ));
+            }
+        };
+
+        dst.write_u32::<LittleEndian>(0)?;
+        dst.write_u32::<LittleEndian>(self.score)?;
+        dst.write_f32::<LittleEndian>(self.slow)?;
+        dst.write_u8(self.shot_type.into())?;
+        dst.write_u8(self.difficulty.into())?;
+        dst.write_u8(progress)?;
+        dst.write_all(&self.name)?;
+        self.date.write_to(dst)?;
+        dst.write_u16::<LittleEndian>(self.continues)
+    }
 }
 
 impl_getters! {
@@ -233,7 +291,7 @@ impl_getters! {
     continues: u16
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ClearData {
     story_flags: [u8; 6],
     practice_flags: [u8; 6],
@@ -256,6 +314,13 @@ impl ClearData {
             shot_type,
         })
     }
+
+    pub fn write_to<W: WriteBytesExt>(&self, dst: &mut W) -> Result<(), io::Error> {
+        dst.write_u32::<LittleEndian>(0)?;
+        dst.write_all(&self.story_flags)?;
+        dst.write_all(&self.practice_flags)?;
+        dst.write_u32::<LittleEndian>(u8::from(self.shot_type) as u32)
+    }
 }
 
 impl_getters! {
@@ -265,7 +330,7 @@ impl_getters! {
 
 access_by_difficulty! { ClearData, story_flags: u8, practice_flags: u8 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SpellCardData {
     max_bonuses: [u32; 7],
     card_id: u16,
@@ -306,6 +371,7 @@ impl SpellCardData {
 
         let max_bonuses = read_array![src.read_u32::<LittleEndian>()?; 7];
         let card_id: u16 = src.read_u16::<LittleEndian>()? + 1;
+        read_try_into!(u32 as SpellId : card_id as u32)?;
 
         src.read_u8()?;
         src.read_exact(&mut card_name)?;
@@ -322,6 +388,24 @@ impl SpellCardData {
             captures,
         })
     }
+
+    pub fn write_to<W: WriteBytesExt>(&self, dst: &mut W) -> Result<(), io::Error> {
+        dst.write_u32::<LittleEndian>(0)?;
+        for max_bonus in self.max_bonuses {
+            dst.write_u32::<LittleEndian>(max_bonus)?;
+        }
+
+        dst.write_u16::<LittleEndian>(self.card_id - 1)?;
+        dst.write_u8(0)?;
+        dst.write_all(&self.card_name)?;
+        dst.write_u8(0)?;
+
+        for value in self.attempts.into_iter().chain(self.captures) {
+            dst.write_u16::<LittleEndian>(value)?;
+        }
+
+        Ok(())
+    }
 }
 
 impl_getters! {
@@ -370,7 +454,7 @@ impl SpellCardRecord<Touhou7> for SpellCardData {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct PracticeData {
     attempts: u32,
     high_score: u32,
@@ -406,6 +490,16 @@ impl PracticeData {
             stage,
         })
     }
+
+    pub fn write_to<W: WriteBytesExt>(&self, dst: &mut W) -> Result<(), io::Error> {
+        dst.write_u32::<LittleEndian>(0)?;
+        dst.write_u32::<LittleEndian>(self.attempts)?;
+        dst.write_u32::<LittleEndian>(self.high_score)?;
+        dst.write_u8(self.shot_type.into())?;
+        dst.write_u8(self.difficulty.into())?;
+        dst.write_u8(self.stage.into())?;
+        dst.write_u8(0)
+    }
 }
 
 impl PracticeRecord<Touhou7> for PracticeData {
@@ -430,7 +524,7 @@ impl PracticeRecord<Touhou7> for PracticeData {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PlayData {
     running_time: StoredTime,
     play_time: StoredTime,
@@ -470,6 +564,18 @@ impl PlayData {
             play_counts,
         })
     }
+
+    pub fn write_to<W: WriteBytesExt>(&self, dst: &mut W) -> Result<(), io::Error> {
+        dst.write_u32::<LittleEndian>(0)?;
+        self.running_time.write_to(dst)?;
+        self.play_time.write_to(dst)?;
+
+        for play_count in self.play_counts.iter() {
+            play_count.write_to(dst)?;
+        }
+
+        Ok(())
+    }
 }
 
 impl_getters! {
@@ -537,22 +643,91 @@ impl<R: Read> Read for Decryptor<R> {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// Compute the checksum stored at the start of a score file over the unencrypted file data.
+pub fn checksum(data: &[u8]) -> u16 {
+    data.iter().fold(0u16, |acc, x| acc.wrapping_add(*x as u16))
+}
+
+/// Encrypts data in the format read by [`Decryptor`].
+///
+/// Since the checksum is written before any data, it must be computed ahead of time using [`checksum`].
+#[derive(Debug)]
+pub struct Encryptor<W> {
+    dst: W,
+    key: u8,
+}
+
+impl<W: Write> Encryptor<W> {
+    pub fn new(mut dst: W, key: u8, checksum: u16) -> Result<Self, io::Error> {
+        dst.write_all(&[0, key])?;
+
+        let mut ret = Self {
+            dst,
+            key: key.rotate_left(3),
+        };
+
+        ret.write_all(&checksum.to_le_bytes())?;
+        Ok(ret)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.dst
+    }
+}
+
+impl<W: Write> Write for Encryptor<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let encrypted: Vec<u8> = buf
+            .iter()
+            .map(|x| {
+                let ret = *x ^ self.key;
+                self.key = self.key.wrapping_add(*x).rotate_left(3);
+                ret
+            })
+            .collect();
+
+        self.dst.write_all(&encrypted)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.dst.flush()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct FileHeader {
     version: u16,
+    unknown_1: u16,
     header_sz: u32,
+    unknown_2: u32,
     decomp_full_sz: usize,
     decomp_body_sz: usize,
     encoded_body_sz: usize,
 }
 
 impl FileHeader {
+    /// Create a header for a new score file.
+    ///
+    /// The size fields are filled in when the file is written out by [`ScoreWriter`].
+    pub fn new(version: u16) -> Self {
+        Self {
+            version,
+            unknown_1: 0,
+            header_sz: 0x1C,
+            unknown_2: 0,
+            decomp_full_sz: 0,
+            decomp_body_sz: 0,
+            encoded_body_sz: 0,
+        }
+    }
+
     pub fn read_from<R: ReadBytesExt>(src: &mut R) -> Result<Self, io::Error> {
         let version = src.read_u16::<LittleEndian>()?;
-        src.read_u16::<LittleEndian>()?;
+        let unknown_1 = src.read_u16::<LittleEndian>()?;
 
         let header_sz = src.read_u32::<LittleEndian>()?;
-        src.read_u32::<LittleEndian>()?;
+        let unknown_2 = src.read_u32::<LittleEndian>()?;
 
         let decomp_full_sz = src.read_u32::<LittleEndian>()? as usize;
         let decomp_body_sz = src.read_u32::<LittleEndian>()? as usize;
@@ -560,12 +735,43 @@ impl FileHeader {
 
         Ok(Self {
             version,
+            unknown_1,
             header_sz,
+            unknown_2,
             decomp_full_sz,
             decomp_body_sz,
             encoded_body_sz,
         })
     }
+
+    pub fn write_to<W: WriteBytesExt>(&self, dst: &mut W) -> Result<(), io::Error> {
+        dst.write_u16::<LittleEndian>(self.version)?;
+        dst.write_u16::<LittleEndian>(self.unknown_1)?;
+        dst.write_u32::<LittleEndian>(self.header_sz)?;
+        dst.write_u32::<LittleEndian>(self.unknown_2)?;
+        dst.write_u32::<LittleEndian>(write_try_into!(u32: self.decomp_full_sz)?)?;
+        dst.write_u32::<LittleEndian>(write_try_into!(u32: self.decomp_body_sz)?)?;
+        dst.write_u32::<LittleEndian>(write_try_into!(u32: self.encoded_body_sz)?)
+    }
+
+    /// Compress a decompressed file body, and prepend a copy of this header with updated size fields to it.
+    pub(crate) fn encode_body(&self, body: &[u8]) -> Result<Vec<u8>, io::Error> {
+        let mut compressor = StreamCompressor::new(Vec::new());
+        compressor.write_all(body)?;
+        let (compressed, encoded_body_sz) = compressor.finish()?;
+
+        let header = Self {
+            decomp_full_sz: (self.header_sz as usize) + body.len(),
+            decomp_body_sz: body.len(),
+            encoded_body_sz,
+            ..*self
+        };
+
+        let mut ret = Vec::with_capacity(0x18 + compressed.len());
+        header.write_to(&mut ret)?;
+        ret.extend_from_slice(&compressed);
+        Ok(ret)
+    }
 }
 
 impl_getters! {
@@ -577,9 +783,9 @@ impl_getters! {
     encoded_body_sz: usize
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub enum Segment {
-    Header,
+    Header(Box<[u8]>),
     HighScore(HighScore),
     Clear(ClearData),
     SpellCard(SpellCardData),
@@ -590,10 +796,15 @@ pub enum Segment {
     Unknown([u8; 4], usize, usize, Box<[u8]>),
 }
 
+/// The signatures of all segment types known to appear in Touhou 7 score files.
+const SIGNATURES: [[u8; 4]; 8] = [
+    *b"TH7K", *b"HSCR", *b"CLRD", *b"CATK", *b"PSCR", *b"PLST", *b"LSNM", *b"VRSM",
+];
+
 impl Segment {
     pub fn signature(&self) -> &[u8; 4] {
         match self {
-            Self::Header => b"TH7K",
+            Self::Header(_) => b"TH7K",
             Self::HighScore(_) => b"HSCR",
             Self::Clear(_) => b"CLRD",
             Self::SpellCard(_) => b"CATK",
@@ -618,9 +829,18 @@ impl Segment {
         let mut data = vec![0u8; size1 - 8];
         return_none_on_eof!(src.read_exact(&mut data));
 
+        Self::from_raw(signature, size1, size2, data).map(Some)
+    }
+
+    fn from_raw(
+        signature: [u8; 4],
+        size1: usize,
+        size2: usize,
+        data: Vec<u8>,
+    ) -> Result<Self, io::Error> {
         let mut reader = Cursor::new(data);
         match &signature {
-            b"TH7K" => Ok(Self::Header),
+            b"TH7K" => Ok(Self::Header(reader.into_inner().into())),
             b"HSCR" => HighScore::read_from(&mut reader).map(Self::HighScore),
             b"CLRD" => ClearData::read_from(&mut reader).map(Self::Clear),
             b"CATK" => SpellCardData::read_from(&mut reader).map(Self::SpellCard),
@@ -628,18 +848,18 @@ impl Segment {
             b"PLST" => PlayData::read_from(&mut reader).map(Self::PlayStatus),
             b"LSNM" => {
                 let mut name = [0; 12];
-                src.read_u32::<LittleEndian>()?;
-                src.read_exact(&mut name)?;
+                reader.read_u32::<LittleEndian>()?;
+                reader.read_exact(&mut name)?;
                 Ok(Self::LastName(name))
             }
             b"VRSM" => {
                 let mut version = [0; 6];
-                src.read_u16::<LittleEndian>()?;
-                src.read_u16::<LittleEndian>()?;
-                src.read_exact(&mut version)?;
-                src.read_u32::<LittleEndian>()?;
-                src.read_u32::<LittleEndian>()?;
-                src.read_u16::<LittleEndian>()?;
+                reader.read_u16::<LittleEndian>()?;
+                reader.read_u16::<LittleEndian>()?;
+                reader.read_exact(&mut version)?;
+                reader.read_u32::<LittleEndian>()?;
+                reader.read_u32::<LittleEndian>()?;
+                reader.read_u16::<LittleEndian>()?;
                 Ok(Self::Version(version))
             }
             _ => Ok(Self::Unknown(
@@ -649,16 +869,53 @@ impl Segment {
                 reader.into_inner().into(),
             )),
         }
-        .map(Some)
+    }
+
+    pub fn write_to<W: WriteBytesExt>(&self, dst: &mut W) -> Result<(), io::Error> {
+        let mut data = Vec::new();
+
+        match self {
+            Self::Header(d) => data.extend_from_slice(d),
+            Self::HighScore(d) => d.write_to(&mut data)?,
+            Self::Clear(d) => d.write_to(&mut data)?,
+            Self::SpellCard(d) => d.write_to(&mut data)?,
+            Self::PracticeScore(d) => d.write_to(&mut data)?,
+            Self::PlayStatus(d) => d.write_to(&mut data)?,
+            Self::LastName(name) => {
+                data.write_u32::<LittleEndian>(0)?;
+                data.write_all(name)?;
+            }
+            Self::Version(version) => {
+                data.write_u16::<LittleEndian>(0)?;
+                data.write_u16::<LittleEndian>(0)?;
+                data.write_all(version)?;
+                data.write_u32::<LittleEndian>(0)?;
+                data.write_u32::<LittleEndian>(0)?;
+                data.write_u16::<LittleEndian>(0)?;
+            }
+            Self::Unknown(sig, size1, size2, d) => {
+                dst.write_all(sig)?;
+                dst.write_u16::<LittleEndian>(write_try_into!(u16: *size1)?)?;
+                dst.write_u16::<LittleEndian>(write_try_into!(u16: *size2)?)?;
+                return dst.write_all(d);
+            }
+        }
+
+        let size = write_try_into!(u16: data.len() + 8)?;
+        dst.write_all(self.signature())?;
+        dst.write_u16::<LittleEndian>(size)?;
+        dst.write_u16::<LittleEndian>(size)?;
+        dst.write_all(&data)
     }
 }
 
 impl Debug for Segment {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Header => f
+            Self::Header(d) => f
                 .debug_struct("Segment::Header")
                 .field("signature", self.signature())
+                .field("data", &format!("[{} bytes]", d.len()))
                 .finish(),
             Self::HighScore(d) => f
                 .debug_struct("Segment::HighScore")
@@ -723,6 +980,13 @@ impl<R: Read> ScoreReader<R> {
     pub fn header(&self) -> &FileHeader {
         &self.header
     }
+
+    /// Check whether the data read so far matches the checksum stored in the file.
+    ///
+    /// This is only meaningful after all segments have been read.
+    pub fn valid_checksum(&self) -> bool {
+        self.src.get_ref().is_valid()
+    }
 }
 
 impl<R: Read> Iterator for ScoreReader<R> {
@@ -733,6 +997,39 @@ impl<R: Read> Iterator for ScoreReader<R> {
     }
 }
 
+/// Writes segments out to an encrypted score file, in the format read by [`ScoreReader`].
+///
+/// Segments are buffered in memory until [`finish`](ScoreWriter::finish) is called, since the
+/// checksum and size fields at the start of the file depend on the entire file body.
+#[derive(Debug)]
+pub struct ScoreWriter<W> {
+    header: FileHeader,
+    body: Vec<u8>,
+    dst: W,
+}
+
+impl<W: Write> ScoreWriter<W> {
+    pub fn new(dst: W, header: &FileHeader) -> Self {
+        Self {
+            header: *header,
+            body: Vec::new(),
+            dst,
+        }
+    }
+
+    pub fn write_segment(&mut self, segment: &Segment) -> Result<(), io::Error> {
+        segment.write_to(&mut self.body)
+    }
+
+    pub fn finish(self) -> Result<W, io::Error> {
+        let data = self.header.encode_body(&self.body)?;
+        let mut encryptor = Encryptor::new(self.dst, 0, checksum(&data))?;
+        encryptor.write_all(&data)?;
+        encryptor.flush()?;
+        Ok(encryptor.into_inner())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ScoreFile {
     cards: Vec<SpellCardData>,
@@ -741,19 +1038,71 @@ pub struct ScoreFile {
 
 impl ScoreFile {
     pub fn new<R: Read>(src: R) -> Result<Self, io::Error> {
+        Self::parse(src).map_err(io::Error::from)
+    }
+
+    /// Load a score file, failing on the first problem found.
+    pub fn parse<R: Read>(src: R) -> Result<Self, ScoreParseError<Touhou7>> {
+        Self::parse_with(src, false).map(|(score_file, _)| score_file)
+    }
+
+    /// Load as much data as possible from a possibly-damaged score file.
+    ///
+    /// Damaged segments are skipped, and every problem found (including checksum mismatches and unknown segments)
+    /// is returned alongside the recovered data. An error is only returned if the file header can't be read.
+    pub fn parse_lenient<R: Read>(
+        src: R,
+    ) -> Result<(Self, Vec<ScoreParseError<Touhou7>>), ScoreParseError<Touhou7>> {
+        Self::parse_with(src, true)
+    }
+
+    fn parse_with<R: Read>(
+        src: R,
+        lenient: bool,
+    ) -> Result<(Self, Vec<ScoreParseError<Touhou7>>), ScoreParseError<Touhou7>> {
+        let mut decryptor = Decryptor::new(src)?;
+        FileHeader::read_from(&mut decryptor)?;
+
+        let mut compressed = Vec::new();
+        decryptor.read_to_end(&mut compressed)?;
+
+        let mut errors = Vec::new();
+        if !decryptor.is_valid() {
+            let err = ScoreParseError::ChecksumMismatch {
+                expected: decryptor.target_checksum(),
+                actual: decryptor.checksum(),
+            };
+
+            if lenient {
+                errors.push(err);
+            } else {
+                return Err(err);
+            }
+        }
+
+        let mut body = Vec::new();
+        StreamDecompressor::new(&compressed[..]).read_to_end(&mut body)?;
+
+        let (segments, segment_errors) = parse_segments(
+            &body,
+            &SIGNATURES,
+            ShortSegments::EndOfFile,
+            lenient,
+            |raw| Segment::from_raw(raw.signature, raw.size1, raw.size2, raw.data.to_vec()),
+        )?;
+        errors.extend(segment_errors);
+
         let mut cards = Vec::with_capacity(141);
         let mut practices = Vec::new();
-
-        for segment in ScoreReader::new(src)? {
+        for segment in segments {
             match segment {
-                Ok(Segment::SpellCard(data)) => cards.push(data),
-                Ok(Segment::PracticeScore(data)) => practices.push(data),
-                Ok(_) => continue,
-                Err(e) => return Err(e),
+                Segment::SpellCard(data) => cards.push(data),
+                Segment::PracticeScore(data) => practices.push(data),
+                _ => continue,
             }
         }
 
-        Ok(Self { cards, practices })
+        Ok((Self { cards, practices }, errors))
     }
 }
 
@@ -769,3 +1118,154 @@ impl crate::score::ScoreFile<Touhou7> for ScoreFile {
         &self.practices[..]
     }
 }
+
+/// Score file fixtures, shared with the tests for the game-independent score file code.
+#[cfg(test)]
+pub(crate) mod test_data {
+    use super::*;
+
+    pub(crate) fn test_segments() -> Vec<Segment> {
+        let mut name = [0; 9];
+        name[..8].copy_from_slice(b"TESTNAME");
+
+        let mut card_name = [0; 0x30];
+        card_name[..9].copy_from_slice(b"Test Card");
+
+        let mut play_counts = Box::new([PlayCount::default(); 7]);
+        for (i, play_count) in play_counts.iter_mut().enumerate() {
+            play_count.total_attempts = i as u32 * 10;
+            play_count.attempts = [i as u32; 6];
+            play_count.clears = i as u32;
+        }
+
+        vec![
+            Segment::Header(vec![1, 0, 0, 0].into()),
+            Segment::HighScore(HighScore {
+                score: 123456789,
+                slow: 1.25,
+                shot_type: Th07Shot::SakuyaB,
+                difficulty: Difficulty::Lunatic,
+                progress: StageProgress::LostAt(StageWrapper::new(Stage::Six)),
+                name,
+                date: "07/14".parse().unwrap(),
+                continues: 2,
+            }),
+            Segment::HighScore(HighScore {
+                score: 50000,
+                slow: 0.0,
+                shot_type: Th07Shot::ReimuA,
+                difficulty: Difficulty::Phantasm,
+                progress: StageProgress::AllClear,
+                name,
+                date: "12/01".parse().unwrap(),
+                continues: 0,
+            }),
+            Segment::Clear(ClearData {
+                story_flags: [1, 1, 1, 0, 0, 0],
+                practice_flags: [2, 2, 1, 0, 0, 0],
+                shot_type: Th07Shot::MarisaA,
+            }),
+            Segment::SpellCard(SpellCardData {
+                max_bonuses: [100, 200, 300, 400, 500, 600, 600],
+                card_id: 42,
+                card_name,
+                attempts: [1, 2, 3, 4, 5, 6, 21],
+                captures: [0, 1, 2, 3, 4, 5, 15],
+            }),
+            Segment::PracticeScore(PracticeData {
+                attempts: 17,
+                high_score: 9876540,
+                shot_type: Th07Shot::ReimuB,
+                difficulty: Difficulty::Hard,
+                stage: Stage::Four,
+            }),
+            Segment::PlayStatus(PlayData {
+                running_time: StoredTime {
+                    hours: 12,
+                    minutes: 34,
+                    seconds: 56,
+                    milliseconds: 789,
+                },
+                play_time: StoredTime {
+                    hours: 1,
+                    minutes: 2,
+                    seconds: 3,
+                    milliseconds: 4,
+                },
+                play_counts,
+            }),
+            Segment::LastName(*b"TESTNAME\0\0\0\0"),
+            Segment::Version(*b"1.00b\0"),
+            Segment::Unknown(*b"ABCD", 12, 12, vec![1, 2, 3, 4].into()),
+        ]
+    }
+
+    /// The test segments after one more attempt and capture of the test card with Reimu A,
+    /// and one more practice play.
+    pub(crate) fn updated_segments() -> Vec<Segment> {
+        let mut segments = test_segments();
+        for segment in segments.iter_mut() {
+            match segment {
+                Segment::SpellCard(card) => {
+                    card.attempts[0] += 1;
+                    card.captures[0] += 1;
+                    card.max_bonuses[0] += 50;
+                    card.attempts[6] += 1;
+                    card.captures[6] += 1;
+                }
+                Segment::PracticeScore(practice) => {
+                    practice.attempts += 1;
+                }
+                _ => {}
+            }
+        }
+        segments
+    }
+
+    pub(crate) fn write_segments(segments: &[Segment]) -> Vec<u8> {
+        let mut writer = ScoreWriter::new(Vec::new(), &FileHeader::new(0x0B));
+        for segment in segments {
+            writer.write_segment(segment).unwrap();
+        }
+        writer.finish().unwrap()
+    }
+
+    /// Writes segments out to an unencrypted file body, along with the offset of each segment.
+    pub(crate) fn write_body(segments: &[Segment]) -> (Vec<u8>, Vec<([u8; 4], usize)>) {
+        let mut body = Vec::new();
+        let mut offsets = Vec::new();
+        for segment in segments {
+            offsets.push((*segment.signature(), body.len()));
+            segment.write_to(&mut body).unwrap();
+        }
+        (body, offsets)
+    }
+
+    /// Compresses and encrypts a file body, adding `checksum_offset` to the stored checksum.
+    pub(crate) fn encode_body(body: &[u8], checksum_offset: u16) -> Vec<u8> {
+        let data = FileHeader::new(0x0B).encode_body(body).unwrap();
+        let checksum = checksum(&data).wrapping_add(checksum_offset);
+        let mut encryptor = Encryptor::new(Vec::new(), 0, checksum).unwrap();
+        encryptor.write_all(&data).unwrap();
+        encryptor.into_inner()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_data::*;
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let segments = test_segments();
+        let written = write_segments(&segments);
+
+        let mut reader = ScoreReader::new(&written[..]).unwrap();
+        let read_back: Vec<Segment> = reader.by_ref().collect::<Result<_, _>>().unwrap();
+
+        assert!(reader.valid_checksum());
+        assert_eq!(segments, read_back);
+        assert_eq!(written, write_segments(&read_back));
+    }
+}
@@ -5,6 +5,8 @@ use serde::{Deserialize, Serialize};
 use sysinfo::{Process, ProcessExt, System, SystemExt};
 use touhou_macros::define_game;
 
+#[cfg(feature = "replay-file")]
+pub mod replay;
 #[cfg(feature = "score-file")]
 pub mod score;
 
@@ -13,8 +15,12 @@ mod spellcards;
 #[cfg(feature = "memory")]
 pub mod memory;
 
+#[cfg(feature = "tracking")]
+pub use memory::tracking::Touhou8Event;
 #[cfg(feature = "memory")]
 pub use memory::{GameMemory, Location};
+#[cfg(feature = "replay-file")]
+pub use replay::ReplayFile;
 #[cfg(feature = "score-file")]
 pub use score::ScoreFile;
 pub use spellcards::SpellId;
@@ -74,6 +80,22 @@ define_game! {
     }
 }
 
+#[cfg(feature = "score-file")]
+impl Touhou8 {
+    pub fn load_score_file<R: std::io::Read>(src: R) -> Result<score::ScoreFile, std::io::Error> {
+        ScoreFile::new(src)
+    }
+}
+
+#[cfg(feature = "score-file")]
+impl crate::score::HasScoreFile for Touhou8 {
+    type ScoreFile = ScoreFile;
+
+    fn load_score_file<R: std::io::Read>(src: R) -> Result<ScoreFile, std::io::Error> {
+        ScoreFile::new(src)
+    }
+}
+
 #[cfg(feature = "find-process")]
 impl Touhou8 {
     pub fn find_process(system: &System) -> Option<&Process> {
@@ -81,17 +103,16 @@ impl Touhou8 {
             .processes()
             .iter()
             .map(|(_, process)| process)
-            .find(|&process| {
-                process
-                    .exe()
-                    .file_stem()
-                    .and_then(|s| s.to_str())
-                    .map(|s| s.starts_with("th08"))
-                    .unwrap_or(false)
-            })
+            .find(|&process| crate::process::is_game_process(process, &["th08"]))
     }
 
+    /// Get the path to the game's score file.
+    ///
+    /// For games running under Wine, this resolves the game's real directory within the Wine prefix; if that
+    /// can't be determined, this falls back to `score.dat` next to the reported executable.
     pub fn find_score_file(proc: &Process) -> std::path::PathBuf {
-        proc.exe().with_file_name("score.dat")
+        crate::process::GamePaths::from_process(GameId::IN, proc)
+            .map(|paths| paths.score_file())
+            .unwrap_or_else(|| proc.exe().with_file_name("score.dat"))
     }
 }
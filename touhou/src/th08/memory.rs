@@ -2,6 +2,9 @@ pub mod location;
 pub mod process;
 pub mod state;
 
+#[cfg(feature = "tracking")]
+pub mod tracking;
+
 pub use location::*;
 pub use process::GameMemory;
 pub use state::{BossState, GameState, GameType, PlayerState, ReadResult, RunState, StageState};
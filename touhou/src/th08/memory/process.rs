@@ -65,7 +65,9 @@ define_memory! {
 }
 
 impl GameMemory {
+    /// Advance to a new poll of the game's memory, and read the current state of the game.
     pub fn read_state(&mut self) -> Result<Option<GameState>, MemoryReadError<Touhou8>> {
+        self.advance();
         self.access().map(GameState::new).transpose()
     }
 }
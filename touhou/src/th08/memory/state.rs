@@ -272,6 +272,41 @@ pub enum GameType {
     SpellPractice(PlayerState, SpellState<Touhou8>, bool),
 }
 
+impl GameType {
+    pub fn player(&self) -> PlayerState {
+        match self {
+            Self::Main(state) | Self::StagePractice(state) => state.player(),
+            Self::SpellPractice(player, _, _) => *player,
+        }
+    }
+
+    pub fn difficulty(&self) -> Difficulty<Touhou8> {
+        match self {
+            Self::Main(state) | Self::StagePractice(state) => state.difficulty(),
+            Self::SpellPractice(player, _, _) => player.difficulty(),
+        }
+    }
+
+    /// Gets the spell card that is currently active, if any.
+    pub fn active_spell(&self) -> Option<SpellState<Touhou8>> {
+        match self {
+            Self::Main(state) | Self::StagePractice(state) => {
+                state.stage().active_boss().and_then(BossData::active_spell)
+            }
+            Self::SpellPractice(_, spell, _) => Some(*spell),
+        }
+    }
+}
+
+impl ResolveLocation<Touhou8> for GameType {
+    fn resolve_location(&self) -> Option<LocationWrapper<Touhou8>> {
+        match self {
+            Self::Main(state) | Self::StagePractice(state) => state.resolve_location(),
+            Self::SpellPractice(_, spell, _) => LocationWrapper::from_spell(spell.spell()),
+        }
+    }
+}
+
 impl PauseState for GameType {
     fn paused(&self) -> bool {
         match self {
@@ -312,13 +347,47 @@ pub enum GameState {
     },
 }
 
+/// The `program_state` value while a game or replay is being played ([`GameState::InGame`] / [`GameState::InReplay`]).
+const PROGRAM_STATE_IN_GAME: u32 = 2;
+
+/// The `program_state` value while the next stage is loading ([`GameState::LoadingStage`]).
+const PROGRAM_STATE_LOADING_STAGE: u32 = 3;
+
+/// The `program_state` values for the screens shown once a game has ended ([`GameState::GameOver`]).
+///
+/// The final state of the run is still readable from memory on all of these.
+const PROGRAM_STATES_GAME_OVER: [u32; 3] = [6, 7, 9];
+
+/// The `program_state` value while a game is being restarted from the pause menu ([`GameState::RetryingGame`]).
+const PROGRAM_STATE_RETRYING: u32 = 10;
+
 impl GameState {
+    /// Checks whether the game is currently running a non-replay game of any type, including spell practice.
+    ///
+    /// This includes the stage loading, game over and retry states, so that drivers can see how a game ended.
+    pub fn game_is_active(proc: &MemoryAccess) -> ReadResult<bool> {
+        let mode = proc.game_mode()?;
+        let state = proc.program_state()?;
+        let replay = (mode & 0x08) != 0;
+        let active = matches!(
+            state,
+            PROGRAM_STATE_IN_GAME | PROGRAM_STATE_LOADING_STAGE | PROGRAM_STATE_RETRYING
+        ) || PROGRAM_STATES_GAME_OVER.contains(&state);
+
+        Ok(active && !replay)
+    }
+
     pub fn run_is_active(proc: &MemoryAccess) -> ReadResult<bool> {
         let mode = proc.game_mode()?;
         let state = proc.program_state()?;
         let replay = (mode & 0x08) != 0;
         let spell_practice = ((mode & 0x0180) != 0) || ((mode & 0x4000) != 0);
-        Ok((state == 2 || state == 3 || state == 10) && !replay && !spell_practice)
+        let active = matches!(
+            state,
+            PROGRAM_STATE_IN_GAME | PROGRAM_STATE_LOADING_STAGE | PROGRAM_STATE_RETRYING
+        );
+
+        Ok(active && !replay && !spell_practice)
     }
 
     pub fn new(proc: &MemoryAccess) -> ReadResult<Self> {
@@ -343,7 +412,7 @@ impl GameState {
                 }
                 other => GameState::UnknownMenu { menu_state: other },
             }),
-            2 => {
+            PROGRAM_STATE_IN_GAME => {
                 let game = if spell_practice {
                     if let Some(active_spell) = BossState::read_active_spell(proc)? {
                         GameType::SpellPractice(PlayerState::new(proc)?, active_spell, paused)
@@ -362,7 +431,7 @@ impl GameState {
                     Ok(GameState::InGame { game, paused })
                 }
             }
-            3 => Ok(GameState::LoadingStage),
+            PROGRAM_STATE_LOADING_STAGE => Ok(GameState::LoadingStage),
             6 | 7 | 9 => {
                 if replay {
                     Ok(GameState::ReplayEnded)
@@ -382,7 +451,7 @@ impl GameState {
                     Ok(GameState::GameOver { cleared, game })
                 }
             }
-            10 => Ok(GameState::RetryingGame),
+            PROGRAM_STATE_RETRYING => Ok(GameState::RetryingGame),
             state_id @ (5 | 8 | 11..=12) => Ok(GameState::Unknown { state_id, mode }), // used by the game, but unidentified for now
             0xFFFFFFFF => Err(MemoryReadError::other("game is not ready")), // set during startup and shutdown
             other @ (0 | 4 | 13..=0xFFFFFFFE) => Err(MemoryReadError::other(format_args!(
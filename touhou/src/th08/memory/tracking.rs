@@ -0,0 +1,360 @@
+use std::time::Duration;
+
+use super::process::MemoryAccess;
+use super::{GameMemory, GameState, GameType};
+use crate::memory::{Location, MemoryReadError, PlayerData};
+use crate::tracking::builder::TrackerBuilder;
+use crate::tracking::state::{ContinuesUsed, CurrentPause, TotalBombsUsed, TotalMisses};
+use crate::tracking::{
+    DriveTracker, GameTracker, IntoGameTracker, TrackRun, TrackSpellPractice, TrackStagePractice,
+    TrackableGame, TrackerState, TrackingType, UpdateResult, UpdateStatus,
+};
+use crate::types::{SpellCard, SpellType};
+use crate::Touhou8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Touhou8Event {
+    /// A boss declared one of their Last Spells.
+    LastSpellDeclared(SpellCard<Touhou8>),
+    /// The player's time counter increased, usually from collecting time orbs.
+    TimeGained { amount: u32 },
+    /// The night counter changed.
+    NightChanged { night: u8 },
+}
+
+impl std::fmt::Display for Touhou8Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::LastSpellDeclared(spell) => write!(f, "Last Spell: {}", spell),
+            Self::TimeGained { amount } => write!(f, "Time +{}", amount),
+            Self::NightChanged { night } => write!(f, "Night {}", night),
+        }
+    }
+}
+
+impl TrackableGame for Touhou8 {
+    type State = GameType;
+    type Event = Touhou8Event;
+}
+
+#[derive(Debug)]
+pub struct ActiveRun<T> {
+    tracker: TrackerState<Touhou8, T, TotalMisses, TotalBombsUsed, ContinuesUsed, CurrentPause>,
+    prev_state: GameType,
+}
+
+impl<T> ActiveRun<T>
+where
+    T: TrackRun<Touhou8> + TrackStagePractice<Touhou8> + TrackSpellPractice<Touhou8>,
+{
+    fn new(state: GameType) -> Option<Self> {
+        let player = state.player();
+        let builder = TrackerBuilder::new()
+            .track_total_misses(&player)
+            .track_total_bombs_used(&player)
+            .track_continues(&player)
+            .track_pause(&state);
+
+        let tracker = match state {
+            GameType::Main(_) => builder.start_run(
+                player.shot(),
+                state.difficulty(),
+                state,
+                Duration::from_millis(750),
+            ),
+            GameType::StagePractice(run) => builder.start_stage_practice(
+                player.shot(),
+                state.difficulty(),
+                run.stage().stage(),
+                state,
+                Duration::from_millis(750),
+            ),
+            GameType::SpellPractice(_, spell, _) => {
+                // Last Words don't have locations defined, so they can't be tracked for now.
+                let location = Location::from_spell(spell.spell())?;
+                builder.start_spell_practice(
+                    player.shot(),
+                    state.difficulty(),
+                    location,
+                    state,
+                    Duration::from_millis(750),
+                )
+            }
+        };
+
+        Some(Self {
+            tracker,
+            prev_state: state,
+        })
+    }
+
+    fn update_state(&mut self, state: GameType) {
+        let player = state.player();
+        let prev_player = self.prev_state.player();
+        let mut update = self.tracker.begin_update_with_location(state, &state);
+
+        if let Some(spell) = state.active_spell() {
+            let prev_spell = self.prev_state.active_spell().map(|prev| prev.spell());
+            if spell.spell_type == SpellType::LastSpell && prev_spell != Some(spell.spell()) {
+                update.push_game_specific_event(Touhou8Event::LastSpellDeclared(spell.spell()));
+            }
+        }
+
+        if player.time() > prev_player.time() {
+            update.push_game_specific_event(Touhou8Event::TimeGained {
+                amount: player.time() - prev_player.time(),
+            });
+        }
+
+        if player.night() != prev_player.night() {
+            update.push_game_specific_event(Touhou8Event::NightChanged {
+                night: player.night(),
+            });
+        }
+
+        update
+            .update_total_misses(&player)
+            .update_total_bombs_used(&player)
+            .update_continues_used(&player)
+            .update_pause(&state)
+            .finish();
+
+        self.prev_state = state;
+    }
+
+    fn finish(mut self, cleared: bool, end_state: Option<GameType>) -> T::Output {
+        if let Some(end_state) = end_state {
+            self.update_state(end_state);
+        }
+
+        match self.tracker.tracking_type() {
+            TrackingType::StagePractice => self.tracker.finish_stage_practice(self.prev_state),
+            TrackingType::SpellPractice => self.tracker.finish_spell_practice(self.prev_state),
+            TrackingType::FullRun => {
+                if cleared {
+                    self.tracker.run_cleared(self.prev_state)
+                } else {
+                    self.tracker.run_exited(self.prev_state)
+                }
+            }
+        }
+    }
+}
+
+impl<T> DriveTracker<Touhou8, T> for ActiveRun<T>
+where
+    T: TrackRun<Touhou8> + TrackStagePractice<Touhou8> + TrackSpellPractice<Touhou8>,
+{
+    type Memory = GameMemory;
+
+    fn game_is_active(access: &MemoryAccess) -> Result<bool, MemoryReadError<Touhou8>> {
+        GameState::game_is_active(access)
+    }
+
+    fn init(access: &MemoryAccess) -> Result<Option<Self>, MemoryReadError<Touhou8>> {
+        GameState::new(access).map(|state| {
+            if let GameState::InGame { game, .. } = state {
+                Self::new(game)
+            } else {
+                None
+            }
+        })
+    }
+
+    fn update(mut self, access: &MemoryAccess) -> UpdateResult<Touhou8, T, Self> {
+        let state = match GameState::new(access) {
+            Ok(state) => state,
+            Err(e) => return Err((self, e)),
+        };
+
+        match state {
+            GameState::InGame { game, .. } => {
+                self.update_state(game);
+                Ok(UpdateStatus::Continuing(self))
+            }
+            GameState::LoadingStage => Ok(UpdateStatus::Continuing(self)),
+            GameState::GameOver { cleared, game } => {
+                Ok(UpdateStatus::Finished(self.finish(cleared, Some(game))))
+            }
+            _ => Ok(UpdateStatus::Finished(self.finish(false, None))),
+        }
+    }
+
+    fn terminate(self) -> T::Output {
+        self.finish(false, None)
+    }
+
+    fn tracker_mut(&mut self) -> &mut T {
+        self.tracker.tracker_mut()
+    }
+}
+
+impl<T> GameTracker<Touhou8, T, ActiveRun<T>>
+where
+    T: TrackRun<Touhou8> + TrackStagePractice<Touhou8> + TrackSpellPractice<Touhou8>,
+{
+    pub fn new_th08(memory: GameMemory) -> Self {
+        Self::new(memory)
+    }
+}
+
+impl<T> IntoGameTracker<Touhou8, T> for GameMemory
+where
+    T: TrackRun<Touhou8> + TrackStagePractice<Touhou8> + TrackSpellPractice<Touhou8>,
+    ActiveRun<T>: DriveTracker<Touhou8, T, Memory = GameMemory>,
+{
+    type Driver = ActiveRun<T>;
+
+    fn track_games(self) -> GameTracker<Touhou8, T, ActiveRun<T>> {
+        GameTracker::new(self)
+    }
+}
+
+#[cfg(all(test, feature = "replay"))]
+mod tests {
+    use serde::Serialize;
+
+    use super::*;
+    use crate::memory::replay::MemoryFrame;
+    use crate::th08::{Difficulty, ShotType};
+    use crate::tracking::recorder::RecordKind;
+    use crate::tracking::{Event, RunRecorder};
+
+    fn set<T: Serialize>(frame: &mut MemoryFrame, field: &str, value: T) {
+        frame.insert(field, Ok::<T, &str>(value));
+    }
+
+    /// A frame from an unpaused Reimu & Yukari game on Normal, partway through stage 1.
+    fn in_game(frame_time: u32, misses: u8, night: u8, time: u32) -> MemoryFrame {
+        let mut frame = MemoryFrame::new();
+        set(&mut frame, "program_state", 2u32);
+        set(&mut frame, "menu_state", 0u32);
+        set(&mut frame, "game_mode", 0x04u32);
+        set(&mut frame, "character", 0u8);
+        set(&mut frame, "difficulty", 1u8);
+        set(&mut frame, "score_1", 100_000u32);
+        set(&mut frame, "score_2", 100_000u32);
+        set(&mut frame, "player_lives", 2.0f32 - (misses as f32));
+        set(&mut frame, "player_bombs", 3.0f32);
+        set(&mut frame, "player_power", 0.0f32);
+        set(&mut frame, "misses", misses as f32);
+        set(&mut frame, "bombs_used", 0.0f32);
+        set(&mut frame, "continues_used", 0u8);
+        set(&mut frame, "graze_1", 0u32);
+        set(&mut frame, "graze_2", 0u32);
+        set(&mut frame, "time_1", time);
+        set(&mut frame, "time_2", time);
+        set(&mut frame, "time_3", time);
+        set(&mut frame, "value", 0u32);
+        set(&mut frame, "gauge", 0u16);
+        set(&mut frame, "night", night);
+        set(&mut frame, "rank", 0u32);
+        set(&mut frame, "frame", frame_time);
+        set(&mut frame, "stage", 0u8);
+        set(&mut frame, "boss_active", 0u8);
+        set(&mut frame, "boss_healthbars", 0u32);
+        set(&mut frame, "boss_dmg_multiplier", 1.0f32);
+        set(&mut frame, "spell_prac_id", 0u16);
+        set(&mut frame, "cur_spell_state", 0u32);
+        set(&mut frame, "cur_spell_id", 0u32);
+        frame
+    }
+
+    fn with_state(mut frame: MemoryFrame, program_state: u32, game_mode: u32) -> MemoryFrame {
+        set(&mut frame, "program_state", program_state);
+        set(&mut frame, "game_mode", game_mode);
+        frame
+    }
+
+    fn main_menu() -> MemoryFrame {
+        let mut frame = with_state(in_game(0, 0, 0, 0), 1, 0);
+        set(&mut frame, "menu_state", 1u32);
+        frame
+    }
+
+    #[test]
+    fn active_program_states() {
+        let states = 1..=12;
+        let frames: Vec<_> = states
+            .clone()
+            .flat_map(|state| {
+                [
+                    with_state(in_game(0, 0, 0, 0), state, 0x04),
+                    with_state(in_game(0, 0, 0, 0), state, 0x0C),
+                ]
+            })
+            .collect();
+
+        let mut memory = GameMemory::from_frames(frames);
+        let mut active = Vec::new();
+
+        for state in states {
+            for replay in [false, true] {
+                assert!(memory.advance());
+                let access = memory.access().unwrap();
+                if GameState::game_is_active(access).unwrap() {
+                    assert!(!replay, "replay counted as active in state {state}");
+                    active.push(state);
+                }
+            }
+        }
+
+        assert_eq!(active, [2, 3, 6, 7, 9, 10]);
+    }
+
+    #[test]
+    fn replay_drives_tracker() {
+        let frames = vec![
+            main_menu(),
+            in_game(600, 0, 0, 0),
+            in_game(610, 0, 0, 0),
+            in_game(620, 0, 0, 50),
+            in_game(630, 1, 1, 50),
+            with_state(in_game(640, 1, 1, 50), 3, 0x04),
+            in_game(650, 1, 1, 50),
+            with_state(in_game(660, 1, 1, 50), 6, 0x14),
+            // watching a replay afterwards shouldn't be tracked
+            with_state(in_game(600, 0, 0, 0), 2, 0x0C),
+            with_state(in_game(610, 0, 0, 0), 2, 0x0C),
+            main_menu(),
+        ];
+
+        let mut tracker: GameTracker<Touhou8, RunRecorder<Touhou8>, _> =
+            GameMemory::from_frames(frames.clone())
+                .track_games()
+                .with_init_delay(Duration::ZERO);
+        let mut polls = 0;
+        let mut runs = Vec::new();
+
+        while tracker.is_running() {
+            polls += 1;
+            runs.extend(tracker.update().unwrap());
+        }
+
+        assert_eq!(polls, frames.len());
+        assert_eq!(runs.len(), 1);
+
+        let run = &runs[0];
+        assert_eq!(run.tracking_type(), TrackingType::FullRun);
+        assert_eq!(run.shot().unwrap(), ShotType::BarrierTeam);
+        assert_eq!(run.difficulty().unwrap(), Difficulty::Normal);
+        assert!(run.cleared());
+
+        let events: Vec<_> = run
+            .records()
+            .iter()
+            .filter_map(|record| match &record.kind {
+                RecordKind::Event(event) => Some(*event),
+                RecordKind::Location(_) => None,
+            })
+            .collect();
+
+        let time_gained = Touhou8Event::TimeGained { amount: 50 };
+        let night_changed = Touhou8Event::NightChanged { night: 1 };
+
+        assert!(events.contains(&Event::Miss));
+        assert!(events.contains(&Event::GameSpecific(time_gained)));
+        assert!(events.contains(&Event::GameSpecific(night_changed)));
+    }
+}
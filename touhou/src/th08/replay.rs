@@ -0,0 +1,267 @@
+use std::io::{self, Cursor, ErrorKind, Read};
+
+use byteorder::{LittleEndian, ReadBytesExt};
+
+use super::{Difficulty, ShotType as Th08Shot, Stage, Touhou8};
+use crate::replay::*;
+use crate::score::ShortDate;
+use crate::types::{Difficulty as DifficultyWrapper, ShotType, Stage as StageWrapper};
+
+const MAGIC: &[u8; 4] = b"T8RP";
+const KEY_OFFSET: usize = 0x15;
+const ENCRYPTED_OFFSET: usize = 0x18;
+const DATA_OFFSET: usize = 0x68;
+const STAGE_HEADER_SZ: usize = 0x20;
+const INPUT_SZ: usize = 8;
+
+fn try_into_or_io_error<T, U>(kind: ErrorKind) -> impl FnOnce(T) -> io::Result<U>
+where
+    T: TryInto<U>,
+    T::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    move |val| {
+        val.try_into()
+            .map_err(move |error| io::Error::new(kind, error))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplayFile {
+    version: u16,
+    shot_type: Th08Shot,
+    difficulty: Difficulty,
+    date: ShortDate,
+    name: [u8; 9],
+    score: u32,
+    stages: Vec<StageReplay<Touhou8>>,
+}
+
+impl ReplayFile {
+    pub fn new<R: Read>(mut src: R) -> io::Result<Self> {
+        let mut data = Vec::new();
+        src.read_to_end(&mut data)?;
+
+        if data.len() < DATA_OFFSET || &data[..4] != MAGIC {
+            return Err(invalid_data("not a Touhou 8 replay file"));
+        }
+
+        let version = u16::from_le_bytes([data[4], data[5]]);
+        let key = data[KEY_OFFSET];
+        decrypt_additive(&mut data[ENCRYPTED_OFFSET..], key);
+
+        let mut header = Cursor::new(&data[ENCRYPTED_OFFSET..DATA_OFFSET]);
+        let comp_size = header.read_u32::<LittleEndian>()? as usize;
+        let decomp_size = header.read_u32::<LittleEndian>()? as usize;
+
+        let mut stage_offsets = [0u32; 9];
+        for offset in stage_offsets.iter_mut() {
+            *offset = header.read_u32::<LittleEndian>()?;
+        }
+
+        let compressed = data
+            .get(DATA_OFFSET..(DATA_OFFSET + comp_size))
+            .ok_or_else(|| invalid_data("replay file is truncated"))?;
+        let body = decompress_body(compressed, decomp_size)?;
+
+        let mut src = Cursor::new(&body[..]);
+        src.read_u16::<LittleEndian>()?;
+
+        let shot_type: Th08Shot = src
+            .read_u8()
+            .and_then(try_into_or_io_error(ErrorKind::InvalidData))?;
+        let difficulty: Difficulty = src
+            .read_u8()
+            .and_then(try_into_or_io_error(ErrorKind::InvalidData))?;
+        let date = ShortDate::read_from(&mut src)?;
+
+        let mut name = [0; 9];
+        src.read_exact(&mut name)?;
+
+        let mut padding = [0; 5];
+        src.read_exact(&mut padding)?;
+        let score = src.read_u32::<LittleEndian>()?;
+
+        let stage_starts: Vec<(Stage, usize)> = stage_offsets
+            .iter()
+            .zip([
+                Stage::One,
+                Stage::Two,
+                Stage::Three,
+                Stage::FourA,
+                Stage::FourB,
+                Stage::Five,
+                Stage::FinalA,
+                Stage::FinalB,
+                Stage::Extra,
+            ])
+            .filter(|(offset, _)| **offset != 0)
+            .map(|(offset, stage)| {
+                (*offset as usize)
+                    .checked_sub(DATA_OFFSET)
+                    .map(|start| (stage, start))
+                    .ok_or_else(|| invalid_data(format!("invalid stage offset {:#x}", offset)))
+            })
+            .collect::<io::Result<_>>()?;
+
+        let mut stages = Vec::with_capacity(stage_starts.len());
+        for (i, &(stage, start)) in stage_starts.iter().enumerate() {
+            let end = stage_starts
+                .get(i + 1)
+                .map(|(_, next)| *next)
+                .unwrap_or(body.len());
+
+            let block = body
+                .get(start..end)
+                .filter(|block| block.len() >= STAGE_HEADER_SZ)
+                .ok_or_else(|| invalid_data(format!("invalid data for {}", stage)))?;
+
+            stages.push(Self::read_stage(stage, block)?);
+        }
+
+        Ok(Self {
+            version,
+            shot_type,
+            difficulty,
+            date,
+            name,
+            score,
+            stages,
+        })
+    }
+
+    fn read_stage(stage: Stage, block: &[u8]) -> io::Result<StageReplay<Touhou8>> {
+        let mut src = Cursor::new(block);
+
+        let score = src.read_u32::<LittleEndian>()?;
+        src.set_position(0x18);
+        let power = src.read_u8()?;
+        let lives = src.read_u8()?;
+        let bombs = src.read_u8()?;
+
+        let inputs = block[STAGE_HEADER_SZ..]
+            .chunks_exact(INPUT_SZ)
+            .map(|input| {
+                let frame = u32::from_le_bytes(input[..4].try_into().unwrap());
+                let keys = u16::from_le_bytes(input[4..6].try_into().unwrap());
+                InputFrame::new(frame, keys)
+            })
+            .collect();
+
+        Ok(StageReplay::new(
+            StageWrapper::new(stage),
+            (score as u64) * 10,
+            power as u16,
+            lives,
+            Some(bombs),
+            inputs,
+        ))
+    }
+
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    pub fn date(&self) -> ShortDate {
+        self.date
+    }
+}
+
+impl crate::replay::ReplayFile<Touhou8> for ReplayFile {
+    fn name(&self) -> &[u8] {
+        trim_name(&self.name)
+    }
+
+    fn shot_type(&self) -> ShotType<Touhou8> {
+        ShotType::new(self.shot_type)
+    }
+
+    fn difficulty(&self) -> DifficultyWrapper<Touhou8> {
+        DifficultyWrapper::new(self.difficulty)
+    }
+
+    fn score(&self) -> u64 {
+        (self.score as u64) * 10
+    }
+
+    fn stages(&self) -> &[StageReplay<Touhou8>] {
+        &self.stages[..]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::ReplayFile as _;
+
+    /// A complete replay file, built by hand with the additive cipher and an LZSS compressor written independently
+    /// of this crate's decoders.
+    ///
+    /// This is a Magic Team replay on Hard, covering stages 4B and 6A.
+    const HAND_BUILT_REPLAY: [u8; 178] = [
+        0x54, 0x38, 0x52, 0x50, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x33, 0x00, 0x00, //
+        0x7d, 0x3a, 0x41, 0x48, 0xd3, 0x56, 0x5d, 0x64, 0x6b, 0x72, 0x79, 0x80, //
+        0x87, 0x8e, 0x95, 0x9c, 0xa3, 0xaa, 0xb1, 0xb8, 0xbf, 0xc6, 0xcd, 0xd4, //
+        0x5f, 0xe2, 0xe9, 0xf0, 0xf7, 0xfe, 0x05, 0x0c, 0xc7, 0x1a, 0x21, 0x28, //
+        0x2f, 0x36, 0x3d, 0x44, 0x4b, 0x52, 0x59, 0x60, 0x67, 0x6e, 0x75, 0x7c, //
+        0x83, 0x8a, 0x91, 0x98, 0x9f, 0xa6, 0xad, 0xb4, 0xbb, 0xc2, 0xc9, 0xd0, //
+        0xd7, 0xde, 0xe5, 0xec, 0xf3, 0xfa, 0x01, 0x08, 0x0f, 0x16, 0x1d, 0x24, //
+        0x2b, 0x32, 0x39, 0x40, 0x47, 0x4e, 0x55, 0x5c, 0xe3, 0xaa, 0x91, 0xa8, //
+        0xa8, 0x12, 0x57, 0xf3, 0xce, 0x3a, 0x69, 0xd9, 0xeb, 0xa8, 0x6a, 0xe2, //
+        0x7a, 0x2e, 0x61, 0xe8, 0x11, 0x8d, 0x85, 0x5b, 0x99, 0x1d, 0x19, 0xa4, //
+        0xf6, 0x6e, 0x46, 0x8c, 0x4b, 0x75, 0xe2, 0x5b, 0xe0, 0x66, 0xae, 0xc4, //
+        0x1b, 0x86, 0xc9, 0x93, 0x08, 0x3e, 0xfa, 0xdd, 0xd3, 0xca, 0x39, 0xca, //
+        0xf0, 0x56, 0x5d, 0x24, 0x2b, 0x12, 0x61, 0x20, 0x09, 0x2f, 0x6f, 0x1c, //
+        0x67, 0x4a, 0xd1, 0x40, 0xc4, 0xb2, 0x4e, 0x68, 0x5b, 0x62, //
+    ];
+
+    #[test]
+    fn decode_hand_built_replay() {
+        let replay = ReplayFile::new(&HAND_BUILT_REPLAY[..]).unwrap();
+        assert_eq!(replay.version(), 0x0100);
+        assert_eq!(replay.name(), b"INTEST");
+        assert_eq!(replay.shot_type().unwrap(), Th08Shot::MagicTeam);
+        assert_eq!(replay.difficulty().unwrap(), Difficulty::Hard);
+        assert_eq!(replay.date().to_string(), "12/31");
+        assert_eq!(replay.score(), 987_654_320);
+
+        let stages = replay.stages();
+        assert_eq!(stages.len(), 2);
+
+        assert_eq!(stages[0].stage().unwrap(), Stage::FourB);
+        assert_eq!(stages[0].score(), 40_000_000);
+        assert_eq!(stages[0].power(), 200);
+        assert_eq!(stages[0].lives(), 3);
+        assert_eq!(stages[0].bombs(), Some(2));
+        assert_eq!(
+            stages[0].inputs(),
+            [InputFrame::new(0, 0), InputFrame::new(10, 0x02)]
+        );
+
+        assert_eq!(stages[1].stage().unwrap(), Stage::FinalA);
+        assert_eq!(stages[1].score(), 90_000_000);
+        assert_eq!(stages[1].power(), 128);
+        assert_eq!(stages[1].lives(), 0);
+        assert_eq!(stages[1].bombs(), Some(1));
+        assert_eq!(
+            stages[1].inputs(),
+            [
+                InputFrame::new(0, 0x04),
+                InputFrame::new(90, 0x05),
+                InputFrame::new(91, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_replays() {
+        let mut file = HAND_BUILT_REPLAY.to_vec();
+        file[..4].copy_from_slice(b"T7RP");
+        let err = ReplayFile::new(&file[..]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        let truncated = &HAND_BUILT_REPLAY[..(HAND_BUILT_REPLAY.len() - 1)];
+        let err = ReplayFile::new(truncated).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
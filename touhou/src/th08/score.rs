@@ -1,12 +1,12 @@
 use std::collections::HashMap;
 use std::fmt::Debug;
-use std::io::{self, Cursor, ErrorKind, Read};
+use std::io::{self, Cursor, ErrorKind, Read, Write};
 
-use byteorder::{LittleEndian, ReadBytesExt};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 
 use super::{Difficulty, ShotType, SpellId, Stage, Touhou8};
 use crate::score::*;
-use crate::th07::score::FileHeader;
+use crate::th07::score::{checksum, Encryptor, FileHeader};
 use crate::types::{
     AllIterable, Difficulty as DifficultyWrapper, ShotType as ShotWrapper, SpellCard,
     Stage as StageWrapper, StageProgress,
@@ -31,6 +31,10 @@ macro_rules! read_then_skip_bytes {
     }};
 }
 
+fn write_zeros<const N: usize, W: Write>(mut dst: W) -> io::Result<()> {
+    dst.write_all(&[0u8; N])
+}
+
 macro_rules! return_none_on_eof {
     ($x:expr) => {
         match $x {
@@ -57,6 +61,13 @@ where
     }
 }
 
+fn invalid_input<E>(error: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(ErrorKind::InvalidInput, error)
+}
+
 #[derive(Debug)]
 enum DecryptorState<R> {
     ThcryptActive(ThCrypt<R>),
@@ -98,6 +109,14 @@ impl<R: Read> Decryptor<R> {
     pub fn valid_checksum(&self) -> bool {
         self.checksum == self.target_checksum
     }
+
+    pub fn checksum(&self) -> u16 {
+        self.checksum
+    }
+
+    pub fn target_checksum(&self) -> u16 {
+        self.target_checksum
+    }
 }
 
 impl<R: Read> Read for Decryptor<R> {
@@ -131,7 +150,7 @@ impl<R: Read> Read for Decryptor<R> {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct HighScore {
     score: u32,
     slow: f32,
@@ -283,9 +302,63 @@ impl HighScore {
             ),
         })
     }
+
+    pub fn write_to<W: Write>(&self, mut dst: W) -> io::Result<()> {
+        let progress = match self.progress {
+            StageProgress::LostAt(stage) => match stage.unwrap() {
+                Stage::One => 0,
+                Stage::Two => 1,
+                Stage::Three => 2,
+                Stage::FourA => 3,
+                Stage::FourB => 4,
+                Stage::Five => 5,
+                Stage::FinalA => 6,
+                Stage::FinalB => 7,
+                Stage::Extra => 8,
+                Stage::LastWord => {
+                    return Err(invalid_input("cannot write Last Word stage progress"));
+                }
+            },
+            StageProgress::AllClear => 99,
+            progress => {
+                return Err(invalid_input(format!(
+                    "cannot write stage progress value {}",
+                    progress
+                )));
+            }
+        };
+
+        if self.card_flags.len() != 222 {
+            return Err(invalid_input("card flags must be 222 bytes long"));
+        }
+
+        write_zeros::<4, _>(&mut dst)?;
+        dst.write_u32::<LittleEndian>(self.score)?;
+        dst.write_f32::<LittleEndian>(self.slow)?;
+        dst.write_u8(self.shot_type.into())?;
+        dst.write_u8(self.difficulty.into())?;
+        dst.write_u8(progress)?;
+        dst.write_all(&self.name)?;
+        self.date.write_to(&mut dst)?;
+        dst.write_u16::<LittleEndian>(self.continues)?;
+        write_zeros::<0x1C, _>(&mut dst)?;
+        dst.write_u8(self.player_num)?;
+        write_zeros::<0x1F, _>(&mut dst)?;
+        dst.write_u32::<LittleEndian>(self.play_time)?;
+        dst.write_u32::<LittleEndian>(self.point_item)?;
+        write_zeros::<4, _>(&mut dst)?;
+        dst.write_u32::<LittleEndian>(self.miss_count)?;
+        dst.write_u32::<LittleEndian>(self.bomb_count)?;
+        dst.write_u32::<LittleEndian>(self.last_spells)?;
+        dst.write_u32::<LittleEndian>(self.pause_count)?;
+        dst.write_u32::<LittleEndian>(self.time_points)?;
+        dst.write_u32::<LittleEndian>(self.human_rate)?;
+        dst.write_all(&self.card_flags)?;
+        write_zeros::<2, _>(&mut dst)
+    }
 }
 
-#[derive(Debug, Copy, Clone, Default)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
 pub struct SpellCardCareer {
     max_bonus: (u32, u32), // pairs of story/practice values
     attempts: (u32, u32),
@@ -318,7 +391,7 @@ impl SpellCardCareer {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SpellCardData {
     card_id: SpellId,
     difficulty: Difficulty,
@@ -415,6 +488,53 @@ impl SpellCardData {
             total_stats,
         })
     }
+
+    pub fn write_to<W: Write>(&self, mut dst: W) -> io::Result<()> {
+        if self.career_stats.len() != 12 {
+            return Err(invalid_input(
+                "spell card data must have stats for 12 shot types",
+            ));
+        }
+
+        if self.card_name.len() != 0x30 || self.enemy_name.len() != 0x30 {
+            return Err(invalid_input(
+                "card and enemy names must be 0x30 bytes long",
+            ));
+        }
+
+        if self.comment.len() != 0x80 {
+            return Err(invalid_input("card comment must be 0x80 bytes long"));
+        }
+
+        write_zeros::<4, _>(&mut dst)?;
+        dst.write_u16::<LittleEndian>(u16::from(self.card_id) - 1)?;
+        dst.write_u8(0)?;
+        dst.write_u8(self.difficulty.into())?;
+        dst.write_all(&self.card_name)?;
+        dst.write_all(&self.enemy_name)?;
+        dst.write_all(&self.comment)?;
+
+        let all_stats = || {
+            self.career_stats
+                .iter()
+                .chain(std::iter::once(&self.total_stats))
+        };
+        for practice in [false, true] {
+            for stat in all_stats() {
+                dst.write_u32::<LittleEndian>(stat.max_bonus(practice))?;
+            }
+
+            for stat in all_stats() {
+                dst.write_u32::<LittleEndian>(stat.attempts(practice))?;
+            }
+
+            for stat in all_stats() {
+                dst.write_u32::<LittleEndian>(stat.captures(practice))?;
+            }
+        }
+
+        write_zeros::<4, _>(&mut dst)
+    }
 }
 
 impl SpellCardRecord<Touhou8> for SpellCardData {
@@ -483,7 +603,7 @@ impl SpellPracticeRecord<Touhou8> for SpellCardData {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PracticeScore {
     shot_type: ShotType,
     stage: Stage,
@@ -492,7 +612,7 @@ pub struct PracticeScore {
     attempts: u32,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PracticeData {
     practice_data: HashMap<(Stage, Difficulty), PracticeScore>,
     shot_type: ShotType,
@@ -515,8 +635,9 @@ impl PracticeData {
         self.practice_data.values()
     }
 
-    pub fn read_from<R: Read>(mut src: R) -> io::Result<Self> {
-        static READ_STAGES: [Stage; 9] = [
+    /// Iterate over the (stage, difficulty) keys for practice records, in the order they're stored in the score file.
+    fn stored_keys() -> impl Iterator<Item = (Stage, Difficulty)> {
+        static STORED_STAGES: [Stage; 9] = [
             Stage::One,
             Stage::Two,
             Stage::Three,
@@ -528,7 +649,7 @@ impl PracticeData {
             Stage::Extra,
         ];
 
-        static READ_DIFFICULTIES: [Difficulty; 5] = [
+        static STORED_DIFFICULTIES: [Difficulty; 5] = [
             Difficulty::Easy,
             Difficulty::Normal,
             Difficulty::Hard,
@@ -536,6 +657,14 @@ impl PracticeData {
             Difficulty::Extra,
         ];
 
+        STORED_STAGES.into_iter().flat_map(|stage| {
+            STORED_DIFFICULTIES
+                .into_iter()
+                .map(move |difficulty| (stage, difficulty))
+        })
+    }
+
+    pub fn read_from<R: Read>(mut src: R) -> io::Result<Self> {
         skip_bytes::<4, _>(&mut src)?;
 
         let mut play_counts = [0u32; 45];
@@ -553,14 +682,8 @@ impl PracticeData {
 
         skip_bytes::<3, _>(&mut src)?;
 
-        let keys = READ_STAGES.into_iter().flat_map(|stage| {
-            READ_DIFFICULTIES
-                .into_iter()
-                .map(move |difficulty| (stage, difficulty))
-        });
-
         let mut practice_data = HashMap::new();
-        for (key, pair) in keys.zip(play_counts.into_iter().zip(high_scores)) {
+        for (key, pair) in Self::stored_keys().zip(play_counts.into_iter().zip(high_scores)) {
             practice_data.insert(
                 key,
                 PracticeScore {
@@ -578,6 +701,23 @@ impl PracticeData {
             shot_type,
         })
     }
+
+    pub fn write_to<W: Write>(&self, mut dst: W) -> io::Result<()> {
+        write_zeros::<4, _>(&mut dst)?;
+
+        for key in Self::stored_keys() {
+            let attempts = self.practice_data.get(&key).map_or(0, |p| p.attempts);
+            dst.write_u32::<LittleEndian>(attempts)?;
+        }
+
+        for key in Self::stored_keys() {
+            let high_score = self.practice_data.get(&key).map_or(0, |p| p.high_score);
+            dst.write_u32::<LittleEndian>(high_score)?;
+        }
+
+        dst.write_u8(self.shot_type.into())?;
+        write_zeros::<3, _>(&mut dst)
+    }
 }
 
 impl PracticeRecord<Touhou8> for PracticeScore {
@@ -602,9 +742,12 @@ impl PracticeRecord<Touhou8> for PracticeScore {
     }
 }
 
-#[derive(Clone)]
+/// The signatures of all segment types known to appear in Touhou 8 score files.
+const SIGNATURES: [[u8; 4]; 4] = [*b"TH8K", *b"HSCR", *b"CATK", *b"PSCR"];
+
+#[derive(Clone, PartialEq)]
 pub enum Segment {
-    Header,
+    Header(Box<[u8]>),
     HighScore(HighScore),
     SpellCard(SpellCardData),
     Practice(PracticeData),
@@ -614,7 +757,7 @@ pub enum Segment {
 impl Segment {
     pub fn signature(&self) -> &[u8; 4] {
         match self {
-            Self::Header => b"TH8K",
+            Self::Header(_) => b"TH8K",
             Self::HighScore(_) => b"HSCR",
             Self::SpellCard(_) => b"CATK",
             Self::Practice(_) => b"PSCR",
@@ -645,9 +788,13 @@ impl Segment {
         let mut data = vec![0u8; size1 - 8];
         return_none_on_eof!(src.read_exact(&mut data));
 
+        Self::from_raw(signature, size1, size2, data).map(Some)
+    }
+
+    fn from_raw(signature: [u8; 4], size1: usize, size2: usize, data: Vec<u8>) -> io::Result<Self> {
         let mut reader = Cursor::new(data);
         match &signature {
-            b"TH8K" => Ok(Self::Header),
+            b"TH8K" => Ok(Self::Header(reader.into_inner().into())),
             b"HSCR" => HighScore::read_from(&mut reader).map(Self::HighScore),
             b"CATK" => SpellCardData::read_from(&mut reader).map(Self::SpellCard),
             b"PSCR" => PracticeData::read_from(&mut reader).map(Self::Practice),
@@ -658,16 +805,39 @@ impl Segment {
                 reader.into_inner().into(),
             )),
         }
-        .map(Some)
+    }
+
+    pub fn write_to<W: Write>(&self, mut dst: W) -> io::Result<()> {
+        let mut data = Vec::new();
+
+        match self {
+            Self::Header(d) => data.extend_from_slice(d),
+            Self::HighScore(d) => d.write_to(&mut data)?,
+            Self::SpellCard(d) => d.write_to(&mut data)?,
+            Self::Practice(d) => d.write_to(&mut data)?,
+            Self::Unknown(sig, size1, size2, d) => {
+                dst.write_all(sig)?;
+                dst.write_u16::<LittleEndian>((*size1).try_into().map_err(invalid_input)?)?;
+                dst.write_u16::<LittleEndian>((*size2).try_into().map_err(invalid_input)?)?;
+                return dst.write_all(d);
+            }
+        }
+
+        let size: u16 = (data.len() + 8).try_into().map_err(invalid_input)?;
+        dst.write_all(self.signature())?;
+        dst.write_u16::<LittleEndian>(size)?;
+        dst.write_u16::<LittleEndian>(size)?;
+        dst.write_all(&data)
     }
 }
 
 impl Debug for Segment {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Header => f
+            Self::Header(d) => f
                 .debug_struct("Segment::Header")
                 .field("signature", &self.signature_string())
+                .field("data", &format!("[{} bytes]", d.len()))
                 .finish(),
             Self::HighScore(d) => f
                 .debug_struct("Segment::HighScore")
@@ -714,6 +884,46 @@ impl<R: Read> ScoreReader<R> {
     pub fn header(&self) -> &FileHeader {
         &self.header
     }
+
+    /// Check whether the data read so far matches the checksum stored in the file.
+    ///
+    /// This is only meaningful after all segments have been read.
+    pub fn valid_checksum(&self) -> bool {
+        self.src.get_ref().valid_checksum()
+    }
+}
+
+/// Writes segments out to an encrypted score file, in the format read by [`ScoreReader`].
+///
+/// Segments are buffered in memory until [`finish`](ScoreWriter::finish) is called, since the
+/// checksum and size fields at the start of the file depend on the entire file body.
+#[derive(Debug)]
+pub struct ScoreWriter<W> {
+    header: FileHeader,
+    body: Vec<u8>,
+    dst: W,
+}
+
+impl<W: Write> ScoreWriter<W> {
+    pub fn new(dst: W, header: &FileHeader) -> Self {
+        Self {
+            header: *header,
+            body: Vec::new(),
+            dst,
+        }
+    }
+
+    pub fn write_segment(&mut self, segment: &Segment) -> io::Result<()> {
+        segment.write_to(&mut self.body)
+    }
+
+    pub fn finish(self) -> io::Result<W> {
+        let data = self.header.encode_body(&self.body)?;
+        let crypt = ThEncrypt::new(self.dst, 0x59, 0x79, 0x0100, Some(0x0C00));
+        let mut encryptor = Encryptor::new(crypt, 0, checksum(&data))?;
+        encryptor.write_all(&data)?;
+        encryptor.into_inner().finish()
+    }
 }
 
 impl<R: Read> Iterator for ScoreReader<R> {
@@ -732,19 +942,67 @@ pub struct ScoreFile {
 
 impl ScoreFile {
     pub fn new<R: Read>(src: R) -> Result<Self, io::Error> {
+        Self::parse(src).map_err(io::Error::from)
+    }
+
+    /// Load a score file, failing on the first problem found.
+    pub fn parse<R: Read>(src: R) -> Result<Self, ScoreParseError<Touhou8>> {
+        Self::parse_with(src, false).map(|(score_file, _)| score_file)
+    }
+
+    /// Load as much data as possible from a possibly-damaged score file.
+    ///
+    /// See [`th07::ScoreFile::parse_lenient`](crate::th07::ScoreFile::parse_lenient) for details.
+    pub fn parse_lenient<R: Read>(
+        src: R,
+    ) -> Result<(Self, Vec<ScoreParseError<Touhou8>>), ScoreParseError<Touhou8>> {
+        Self::parse_with(src, true)
+    }
+
+    fn parse_with<R: Read>(
+        src: R,
+        lenient: bool,
+    ) -> Result<(Self, Vec<ScoreParseError<Touhou8>>), ScoreParseError<Touhou8>> {
+        let mut decryptor = Decryptor::new(src)?;
+        FileHeader::read_from(&mut decryptor)?;
+
+        let mut compressed = Vec::new();
+        decryptor.read_to_end(&mut compressed)?;
+
+        let mut errors = Vec::new();
+        if !decryptor.valid_checksum() {
+            let err = ScoreParseError::ChecksumMismatch {
+                expected: decryptor.target_checksum(),
+                actual: decryptor.checksum(),
+            };
+
+            if lenient {
+                errors.push(err);
+            } else {
+                return Err(err);
+            }
+        }
+
+        let mut body = Vec::new();
+        StreamDecompressor::new(&compressed[..]).read_to_end(&mut body)?;
+
+        let (segments, segment_errors) =
+            parse_segments(&body, &SIGNATURES, ShortSegments::Skip, lenient, |raw| {
+                Segment::from_raw(raw.signature, raw.size1, raw.size2, raw.data.to_vec())
+            })?;
+        errors.extend(segment_errors);
+
         let mut cards = Vec::with_capacity(141);
         let mut practices = Vec::new();
-
-        for segment in ScoreReader::new(src)? {
+        for segment in segments {
             match segment {
-                Ok(Segment::SpellCard(data)) => cards.push(data),
-                Ok(Segment::Practice(data)) => practices.extend(data.practice_data.into_values()),
-                Ok(_) => continue,
-                Err(e) => return Err(e),
+                Segment::SpellCard(data) => cards.push(data),
+                Segment::Practice(data) => practices.extend(data.practice_data.into_values()),
+                _ => continue,
             }
         }
 
-        Ok(Self { cards, practices })
+        Ok((Self { cards, practices }, errors))
     }
 }
 
@@ -760,3 +1018,186 @@ impl crate::score::ScoreFile<Touhou8> for ScoreFile {
         &self.practices[..]
     }
 }
+
+/// Score file fixtures, shared with the tests for the game-independent score file code.
+#[cfg(test)]
+pub(crate) mod test_data {
+    use super::*;
+
+    /// Generates varied filler data, so that the compressed test file is long enough to extend
+    /// past the block-encrypted section at the start of the file.
+    fn filler<const N: usize>(seed: u32) -> Box<[u8]> {
+        let mut state = seed;
+        (0..N)
+            .map(|_| {
+                state = state.wrapping_mul(1103515245).wrapping_add(12345);
+                (state >> 16) as u8
+            })
+            .collect()
+    }
+
+    fn spell_card(id: u16, difficulty: Difficulty) -> SpellCardData {
+        let career_stats = (0..12)
+            .map(|i| SpellCardCareer {
+                max_bonus: (i * 1000, i * 2000),
+                attempts: (i + 1, i + 2),
+                captures: (i, i + 1),
+            })
+            .collect();
+
+        SpellCardData {
+            card_id: SpellId::new(id).unwrap(),
+            difficulty,
+            card_name: filler::<0x30>(id as u32),
+            enemy_name: filler::<0x30>(id as u32 + 1000),
+            comment: filler::<0x80>(id as u32 + 2000),
+            career_stats,
+            total_stats: SpellCardCareer {
+                max_bonus: (11000, 22000),
+                attempts: (78, 90),
+                captures: (66, 78),
+            },
+        }
+    }
+
+    pub(crate) fn test_segments() -> Vec<Segment> {
+        let mut segments = vec![
+            Segment::Header(vec![1, 0, 0, 0].into()),
+            Segment::HighScore(HighScore {
+                score: 987654321,
+                slow: 0.5,
+                shot_type: ShotType::MagicTeam,
+                difficulty: Difficulty::Lunatic,
+                progress: StageProgress::LostAt(StageWrapper::new(Stage::FinalB)),
+                name: *b"TESTNAME\0",
+                date: "02/29".parse().unwrap(),
+                continues: 1,
+                player_num: 3,
+                play_time: 123456,
+                point_item: 2345,
+                miss_count: 4,
+                bomb_count: 5,
+                last_spells: 6,
+                pause_count: 7,
+                time_points: 8,
+                human_rate: 5000,
+                card_flags: filler::<222>(1),
+            }),
+            Segment::HighScore(HighScore {
+                score: 12345,
+                slow: 0.0,
+                shot_type: ShotType::Yukari,
+                difficulty: Difficulty::Extra,
+                progress: StageProgress::AllClear,
+                name: *b"ABCDEFGH\0",
+                date: "10/31".parse().unwrap(),
+                continues: 0,
+                player_num: 1,
+                play_time: 0,
+                point_item: 0,
+                miss_count: 0,
+                bomb_count: 0,
+                last_spells: 0,
+                pause_count: 0,
+                time_points: 0,
+                human_rate: 0,
+                card_flags: vec![0; 222].into(),
+            }),
+            Segment::Practice(PracticeData {
+                practice_data: PracticeData::stored_keys()
+                    .enumerate()
+                    .map(|(i, (stage, difficulty))| {
+                        let score = PracticeScore {
+                            shot_type: ShotType::BarrierTeam,
+                            stage,
+                            difficulty,
+                            high_score: i as u32 * 100000,
+                            attempts: i as u32,
+                        };
+                        ((stage, difficulty), score)
+                    })
+                    .collect(),
+                shot_type: ShotType::BarrierTeam,
+            }),
+            Segment::Unknown(*b"ABCD", 12, 12, vec![1, 2, 3, 4].into()),
+        ];
+
+        segments.extend((1..=20).map(|id| Segment::SpellCard(spell_card(id, Difficulty::Normal))));
+        segments
+    }
+
+    /// The test segments after one more attempt and capture of card #1 with the Barrier Team,
+    /// and a new Stage 1 Normal practice high score.
+    pub(crate) fn updated_segments() -> Vec<Segment> {
+        let mut segments = test_segments();
+        for segment in segments.iter_mut() {
+            match segment {
+                Segment::SpellCard(card) if card.card_id == SpellId::new(1).unwrap() => {
+                    card.career_stats[0].attempts.0 += 1;
+                    card.career_stats[0].captures.0 += 1;
+                    card.total_stats.attempts.0 += 1;
+                    card.total_stats.captures.0 += 1;
+                }
+                Segment::Practice(practice) => {
+                    let score = practice
+                        .practice_data
+                        .get_mut(&(Stage::One, Difficulty::Normal))
+                        .unwrap();
+                    score.attempts += 1;
+                    score.high_score += 50000;
+                }
+                _ => {}
+            }
+        }
+        segments
+    }
+
+    pub(crate) fn write_segments(segments: &[Segment]) -> Vec<u8> {
+        let mut writer = ScoreWriter::new(Vec::new(), &FileHeader::new(1));
+        for segment in segments {
+            writer.write_segment(segment).unwrap();
+        }
+        writer.finish().unwrap()
+    }
+
+    /// Writes segments out to an unencrypted file body, along with the offset of each segment.
+    pub(crate) fn write_body(segments: &[Segment]) -> (Vec<u8>, Vec<([u8; 4], usize)>) {
+        let mut body = Vec::new();
+        let mut offsets = Vec::new();
+        for segment in segments {
+            offsets.push((*segment.signature(), body.len()));
+            segment.write_to(&mut body).unwrap();
+        }
+        (body, offsets)
+    }
+
+    /// Compresses and encrypts a file body, adding `checksum_offset` to the stored checksum.
+    pub(crate) fn encode_body(body: &[u8], checksum_offset: u16) -> Vec<u8> {
+        let data = FileHeader::new(1).encode_body(body).unwrap();
+        let checksum = checksum(&data).wrapping_add(checksum_offset);
+        let crypt = ThEncrypt::new(Vec::new(), 0x59, 0x79, 0x0100, Some(0x0C00));
+        let mut encryptor = Encryptor::new(crypt, 0, checksum).unwrap();
+        encryptor.write_all(&data).unwrap();
+        encryptor.into_inner().finish().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_data::*;
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let segments = test_segments();
+        let written = write_segments(&segments);
+        assert!(written.len() > 0x0C00);
+
+        let mut reader = ScoreReader::new(&written[..]).unwrap();
+        let read_back: Vec<Segment> = reader.by_ref().collect::<Result<_, _>>().unwrap();
+
+        assert!(reader.valid_checksum());
+        assert_eq!(segments, read_back);
+        assert_eq!(written, write_segments(&read_back));
+    }
+}
@@ -13,8 +13,17 @@ use crate::types::PowerValue;
 #[cfg(feature = "memory")]
 pub mod memory;
 
+#[cfg(feature = "replay-file")]
+pub mod replay;
+#[cfg(feature = "score-file")]
+pub mod score;
+
 #[cfg(feature = "memory")]
 pub use memory::*;
+#[cfg(feature = "replay-file")]
+pub use replay::ReplayFile;
+#[cfg(feature = "score-file")]
+pub use score::ScoreFile;
 
 mod spellcards;
 
@@ -65,6 +74,15 @@ define_game! {
     }
 }
 
+#[cfg(feature = "score-file")]
+impl crate::score::HasScoreFile for Touhou10 {
+    type ScoreFile = ScoreFile;
+
+    fn load_score_file<R: std::io::Read>(src: R) -> Result<ScoreFile, std::io::Error> {
+        ScoreFile::new(src)
+    }
+}
+
 /// Represents a shot power value from Touhou 10.
 ///
 /// Similarly to later games in the series, shot power in this game is a decimal value; unlike other games, however, shot power in MoF is always a multiple of 0.05.
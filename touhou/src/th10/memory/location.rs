@@ -96,10 +96,13 @@ impl BossSection {
     Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
 )]
 #[serde(tag = "type", content = "spell")]
-
 pub enum Section {
-    Stage,
+    /// The start of the stage, up until the midboss appears.
+    #[serde(alias = "Stage")]
+    Opening,
     Midboss(BossSection),
+    /// The rest of the stage after the midboss leaves, up until the boss dialogue.
+    PostMidboss,
     Boss(BossSection),
 }
 
@@ -115,12 +118,12 @@ impl Location {
     pub(super) fn stage_section(stage: Stage) -> Self {
         Self {
             stage,
-            section: Section::Stage,
+            section: Section::Opening,
         }
     }
 
     pub fn is_stage_section(&self) -> bool {
-        self.section == Section::Stage
+        matches!(self.section, Section::Opening | Section::PostMidboss)
     }
 
     pub fn resolve(state: &RunState) -> Option<Self> {
@@ -129,7 +132,11 @@ impl Location {
         let difficulty = state.difficulty().unwrap();
 
         match stage_state.activity() {
-            Activity::StageSection => Some(Section::Stage),
+            Activity::StageSection { post_midboss } => Some(if post_midboss {
+                Section::PostMidboss
+            } else {
+                Section::Opening
+            }),
             Activity::Midboss(boss) => {
                 BossSection::resolve_midboss(stage, difficulty, &boss).map(Section::Midboss)
             }
@@ -143,7 +150,8 @@ impl Location {
 impl GameLocation<Touhou10> for Location {
     fn name(&self) -> &'static str {
         match self.section {
-            Section::Stage => self.stage.name(),
+            Section::Opening => "Opening",
+            Section::PostMidboss => "Post-Midboss",
             Section::Midboss(boss) => {
                 if let Some(spell) = &boss.spell {
                     spell.name
@@ -168,7 +176,8 @@ impl GameLocation<Touhou10> for Location {
     fn index(&self) -> u64 {
         let stage_bits: u64 = self.stage().unwrap().into();
         let (section_bits, spell_bits) = match self.section {
-            Section::Stage => (0u64, 0),
+            Section::Opening => (0u64, 0),
+            Section::PostMidboss => (5, 0),
             Section::Midboss(boss) => {
                 if let Some(spell) = &boss.spell {
                     (2, spell.id() as u64)
@@ -195,7 +204,7 @@ impl GameLocation<Touhou10> for Location {
     fn spell(&self) -> Option<SpellCard<Touhou10>> {
         match self.section {
             Section::Midboss(boss) | Section::Boss(boss) => boss.spell,
-            Section::Stage => None,
+            Section::Opening | Section::PostMidboss => None,
         }
     }
 
@@ -228,7 +237,29 @@ impl Default for Location {
     fn default() -> Self {
         Self {
             stage: Stage::One,
-            section: Section::Stage,
+            section: Section::Opening,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::th10::SpellId;
+
+    #[test]
+    fn stage_section_order() {
+        let spell = SpellCard::new(SpellId::new(1).unwrap());
+        let midboss = Location::from_spell(spell).unwrap();
+        let opening = Location::stage_section(midboss.stage);
+        let post_midboss = Location {
+            stage: midboss.stage,
+            section: Section::PostMidboss,
+        };
+
+        assert!(opening < midboss);
+        assert!(midboss < post_midboss);
+        assert!(post_midboss.is_stage_section());
+        assert_ne!(opening.index(), post_midboss.index());
+    }
+}
@@ -158,7 +158,7 @@ impl BossLifebars<Touhou10> for BossState {
 
 #[derive(Debug, Clone, Copy)]
 pub enum Activity {
-    StageSection,
+    StageSection { post_midboss: bool },
     StageDialogue,
     PostDialogue,
     Midboss(BossState),
@@ -168,7 +168,10 @@ pub enum Activity {
 impl Activity {
     pub fn new(stage: StageID, proc: &MemoryAccess) -> ReadResult<Self> {
         match proc.game_state()? {
-            0 | 4..=5 => Ok(Activity::StageSection),
+            0 => Ok(Activity::StageSection {
+                post_midboss: false,
+            }),
+            4..=5 => Ok(Activity::StageSection { post_midboss: true }),
             2 => Ok(Activity::PostDialogue),
             1 | 3 => Ok(Activity::StageDialogue),
             6..=23 => {
@@ -177,7 +180,8 @@ impl Activity {
                 {
                     BossState::new(proc).map(Self::Midboss)
                 } else {
-                    Ok(Self::StageSection)
+                    // the stage 2 and 4 midbosses leave on a timer without the state changing
+                    Ok(Self::StageSection { post_midboss: true })
                 }
             }
             _ => BossState::new(proc).map(Self::Boss),
@@ -8,7 +8,7 @@ use crate::tracking::builder::TrackerBuilder;
 use crate::tracking::state::{ContinuesUsed, CurrentLives, CurrentPower, NotTracked};
 use crate::tracking::{
     DriveTracker, GameTracker, IntoGameTracker, TrackRun, TrackStagePractice, TrackableGame,
-    TrackerState, TrackingType, UpdateStatus,
+    TrackerState, TrackingType, UpdateResult, UpdateStatus,
 };
 use crate::Touhou10;
 
@@ -104,11 +104,13 @@ where
         })
     }
 
-    fn update(
-        mut self,
-        access: &MemoryAccess,
-    ) -> Result<UpdateStatus<Touhou10, T, Self>, MemoryReadError<Touhou10>> {
-        match GameState::new(access)? {
+    fn update(mut self, access: &MemoryAccess) -> UpdateResult<Touhou10, T, Self> {
+        let state = match GameState::new(access) {
+            Ok(state) => state,
+            Err(e) => return Err((self, e)),
+        };
+
+        match state {
             GameState::InGame(run) => {
                 self.update_state(run);
                 Ok(UpdateStatus::Continuing(self))
@@ -122,6 +124,10 @@ where
     fn terminate(self) -> T::Output {
         self.finish(false, None)
     }
+
+    fn tracker_mut(&mut self) -> &mut T {
+        self.tracker.tracker_mut()
+    }
 }
 
 impl<T> GameTracker<Touhou10, T, ActiveRun<T>>
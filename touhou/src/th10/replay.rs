@@ -0,0 +1,167 @@
+use std::io::{self, Cursor, ErrorKind, Read};
+
+use byteorder::{LittleEndian, ReadBytesExt};
+use time::OffsetDateTime;
+
+use super::{Difficulty, ShotType as Th10Shot, Stage, Touhou10};
+use crate::replay::*;
+use crate::score::ThCrypt;
+use crate::types::{Difficulty as DifficultyWrapper, ShotType, Stage as StageWrapper};
+
+const MAGIC: &[u8; 4] = b"t10r";
+const DATA_OFFSET: usize = 0x24;
+const STAGES_OFFSET: u64 = 0x64;
+const STAGE_HEADER_SZ: usize = 0x1C4;
+const INPUT_SZ: usize = 6;
+
+fn try_into_or_io_error<T, U>(kind: ErrorKind) -> impl FnOnce(T) -> io::Result<U>
+where
+    T: TryInto<U>,
+    T::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    move |val| {
+        val.try_into()
+            .map_err(move |error| io::Error::new(kind, error))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplayFile {
+    version: u32,
+    name: [u8; 12],
+    date: OffsetDateTime,
+    score: u32,
+    shot_type: Th10Shot,
+    difficulty: Difficulty,
+    stages: Vec<StageReplay<Touhou10>>,
+}
+
+impl ReplayFile {
+    pub fn new<R: Read>(mut src: R) -> io::Result<Self> {
+        let mut header = [0u8; DATA_OFFSET];
+        src.read_exact(&mut header)?;
+
+        if &header[..4] != MAGIC {
+            return Err(invalid_data("not a Touhou 10 replay file"));
+        }
+
+        let mut header = Cursor::new(&header[..]);
+        header.set_position(0x04);
+        let version = header.read_u32::<LittleEndian>()?;
+        header.set_position(0x1C);
+        let comp_size = header.read_u32::<LittleEndian>()?;
+        let decomp_size = header.read_u32::<LittleEndian>()? as usize;
+
+        let limit = Some(comp_size as usize);
+        let src = src.take(comp_size as u64);
+        let src = ThCrypt::new(src, 0x5E, 0xE1, 0x400, limit);
+        let src = ThCrypt::new(src, 0x7D, 0x3A, 0x100, limit);
+        let body = decompress_body(src, decomp_size)?;
+
+        let mut src = Cursor::new(&body[..]);
+
+        let mut name = [0; 12];
+        src.read_exact(&mut name)?;
+
+        let date = src.read_u32::<LittleEndian>().and_then(|timestamp| {
+            OffsetDateTime::from_unix_timestamp(timestamp as i64).map_err(invalid_data)
+        })?;
+        let score = src.read_u32::<LittleEndian>()?;
+
+        src.set_position(0x4C);
+        let n_stages = src.read_u32::<LittleEndian>()?;
+        let shot_type: Th10Shot = src
+            .read_u32::<LittleEndian>()
+            .and_then(try_into_or_io_error(ErrorKind::InvalidData))?;
+        let difficulty: Difficulty = src
+            .read_u32::<LittleEndian>()
+            .and_then(try_into_or_io_error(ErrorKind::InvalidData))?;
+
+        src.set_position(STAGES_OFFSET);
+        let mut stages = Vec::with_capacity(n_stages as usize);
+        for _ in 0..n_stages {
+            stages.push(Self::read_stage(&mut src)?);
+        }
+
+        Ok(Self {
+            version,
+            name,
+            date,
+            score,
+            shot_type,
+            difficulty,
+            stages,
+        })
+    }
+
+    fn read_stage(src: &mut Cursor<&[u8]>) -> io::Result<StageReplay<Touhou10>> {
+        let start = src.position();
+
+        let stage: Stage = src
+            .read_u16::<LittleEndian>()
+            .and_then(|value| {
+                value
+                    .checked_sub(1)
+                    .ok_or_else(|| invalid_data("invalid stage number 0"))
+            })
+            .and_then(try_into_or_io_error(ErrorKind::InvalidData))?;
+
+        src.read_u16::<LittleEndian>()?;
+        let n_frames = src.read_u32::<LittleEndian>()? as usize;
+        src.read_u32::<LittleEndian>()?;
+
+        let score = src.read_u32::<LittleEndian>()?;
+        let power = src.read_u32::<LittleEndian>()?;
+        src.read_u32::<LittleEndian>()?;
+        let lives = src.read_u32::<LittleEndian>()?;
+
+        src.set_position(start + STAGE_HEADER_SZ as u64);
+        let mut inputs = Vec::with_capacity(n_frames);
+        let mut buf = [0u8; INPUT_SZ];
+
+        for frame in 0..n_frames {
+            src.read_exact(&mut buf)?;
+            let keys = u16::from_le_bytes([buf[0], buf[1]]);
+            inputs.push(InputFrame::new(frame as u32, keys));
+        }
+
+        Ok(StageReplay::new(
+            StageWrapper::new(stage),
+            (score as u64) * 10,
+            power.try_into().map_err(invalid_data)?,
+            lives.try_into().map_err(invalid_data)?,
+            None,
+            inputs,
+        ))
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn date(&self) -> OffsetDateTime {
+        self.date
+    }
+}
+
+impl crate::replay::ReplayFile<Touhou10> for ReplayFile {
+    fn name(&self) -> &[u8] {
+        trim_name(&self.name)
+    }
+
+    fn shot_type(&self) -> ShotType<Touhou10> {
+        ShotType::new(self.shot_type)
+    }
+
+    fn difficulty(&self) -> DifficultyWrapper<Touhou10> {
+        DifficultyWrapper::new(self.difficulty)
+    }
+
+    fn score(&self) -> u64 {
+        (self.score as u64) * 10
+    }
+
+    fn stages(&self) -> &[StageReplay<Touhou10>] {
+        &self.stages[..]
+    }
+}
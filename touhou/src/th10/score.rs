@@ -0,0 +1,886 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::io::{self, Cursor, ErrorKind, Read};
+
+use byteorder::{LittleEndian, ReadBytesExt};
+use time::OffsetDateTime;
+
+use super::{Difficulty, ShotType, SpellId, Stage, Touhou10};
+use crate::score::*;
+use crate::types::{
+    AllIterable, Difficulty as DifficultyWrapper, ShotType as ShotWrapper, SpellCard,
+    Stage as StageWrapper, StageProgress,
+};
+
+const SIGNATURE: &[u8; 4] = b"TH10";
+
+fn read_raw_buffer<const N: usize, R: Read>(mut src: R) -> io::Result<Box<[u8]>> {
+    let mut buf = vec![0u8; N];
+    src.read_exact(&mut buf[..])?;
+    Ok(buf.into())
+}
+
+macro_rules! return_none_on_eof {
+    ($x:expr) => {
+        match $x {
+            Ok(v) => v,
+            Err(e) => {
+                if e.kind() == std::io::ErrorKind::UnexpectedEof {
+                    return Ok(None);
+                } else {
+                    return Err(e.into());
+                }
+            }
+        }
+    };
+}
+
+fn try_into_or_io_error<T, U>(kind: ErrorKind) -> impl FnOnce(T) -> io::Result<U>
+where
+    T: TryInto<U>,
+    T::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    move |val| {
+        val.try_into()
+            .map_err(move |error| io::Error::new(kind, error))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FileHeader {
+    encoded_full_sz: usize,
+    encoded_body_sz: usize,
+    decoded_body_sz: usize,
+}
+
+impl FileHeader {
+    pub fn encoded_full_sz(&self) -> usize {
+        self.encoded_full_sz
+    }
+
+    pub fn encoded_body_sz(&self) -> usize {
+        self.encoded_body_sz
+    }
+
+    pub fn decoded_body_sz(&self) -> usize {
+        self.decoded_body_sz
+    }
+
+    pub fn read_from<R: ReadBytesExt>(src: &mut R) -> Result<Self, io::Error> {
+        let mut signature = [0u8; 4];
+        src.read_exact(&mut signature[..])?;
+
+        if signature != *SIGNATURE {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "invalid score file signature",
+            ));
+        }
+
+        let encoded_full_sz = src.read_u32::<LittleEndian>()? as usize;
+
+        src.read_u32::<LittleEndian>()?;
+        src.read_u32::<LittleEndian>()?;
+
+        let encoded_body_sz = src.read_u32::<LittleEndian>()? as usize;
+        let decoded_body_sz = src.read_u32::<LittleEndian>()? as usize;
+
+        Ok(Self {
+            encoded_full_sz,
+            encoded_body_sz,
+            decoded_body_sz,
+        })
+    }
+}
+
+/// An entry in one of the high score lists stored within the score file.
+#[derive(Debug, Clone)]
+pub struct HighScore {
+    score: u32,
+    progress: StageProgress<Touhou10>,
+    continues: u8,
+    name: [u8; 10],
+    timestamp: u32,
+    slow: f32,
+}
+
+impl HighScore {
+    /// The score for this entry, as displayed in-game.
+    ///
+    /// MoF stores scores divided by 10, since the last digit of a score is always the number of continues used.
+    pub fn score(&self) -> u64 {
+        (self.score as u64) * 10 + (self.continues as u64)
+    }
+
+    pub fn progress(&self) -> StageProgress<Touhou10> {
+        self.progress
+    }
+
+    pub fn continues(&self) -> u8 {
+        self.continues
+    }
+
+    pub fn name(&self) -> &[u8] {
+        &self.name[..]
+    }
+
+    /// The raw UNIX timestamp for when this score was achieved.
+    pub fn timestamp(&self) -> u32 {
+        self.timestamp
+    }
+
+    /// The date and time when this score was achieved.
+    pub fn date(&self) -> Option<OffsetDateTime> {
+        OffsetDateTime::from_unix_timestamp(self.timestamp as i64).ok()
+    }
+
+    pub fn slow(&self) -> f32 {
+        self.slow
+    }
+
+    pub fn read_from<R: Read>(mut src: R) -> io::Result<Self> {
+        Ok(Self {
+            score: src.read_u32::<LittleEndian>()?,
+            progress: match src.read_u8()? {
+                0 => StageProgress::NotStarted,
+                value @ 1..=7 => (value - 1)
+                    .try_into()
+                    .map(|stage: Stage| StageProgress::LostAt(StageWrapper::new(stage)))
+                    .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?,
+                99 => StageProgress::AllClear,
+                value => {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Invalid stage progress value {}", value),
+                    ));
+                }
+            },
+            continues: src.read_u8()?,
+            name: {
+                let mut buf = [0u8; 10];
+                src.read_exact(&mut buf[..])?;
+                buf
+            },
+            timestamp: src.read_u32::<LittleEndian>()?,
+            slow: src.read_f32::<LittleEndian>()?,
+        })
+    }
+}
+
+/// A spell card entry stored within a single shot type's clear data.
+#[derive(Debug, Clone)]
+pub struct SpellCardEntry {
+    card_id: SpellId,
+    difficulty: Difficulty,
+    card_name: Box<[u8]>, // 0x80 bytes, CP932
+    captures: u32,
+    attempts: u32,
+}
+
+impl SpellCardEntry {
+    pub fn card_id(&self) -> SpellId {
+        self.card_id
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    pub fn card_name(&self) -> &[u8] {
+        &self.card_name[..]
+    }
+
+    pub fn captures(&self) -> u32 {
+        self.captures
+    }
+
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    pub fn read_from<R: Read>(mut src: R) -> io::Result<Self> {
+        let card_name = read_raw_buffer::<0x80, _>(&mut src)?;
+        let captures = src.read_u32::<LittleEndian>()?;
+        let attempts = src.read_u32::<LittleEndian>()?;
+
+        let card_id = src
+            .read_u32::<LittleEndian>()
+            .map(|x| x + 1)
+            .and_then(try_into_or_io_error(ErrorKind::InvalidData))?;
+
+        let difficulty = src
+            .read_u32::<LittleEndian>()
+            .and_then(try_into_or_io_error::<u32, u8>(ErrorKind::InvalidData))
+            .and_then(try_into_or_io_error(ErrorKind::InvalidData))?;
+
+        Ok(Self {
+            card_id,
+            difficulty,
+            card_name,
+            captures,
+            attempts,
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct SpellCardCareer {
+    attempts: u32,
+    captures: u32,
+}
+
+impl SpellCardCareer {
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    pub fn captures(&self) -> u32 {
+        self.captures
+    }
+}
+
+/// Spell card history for a single card, collected across all shot types.
+#[derive(Debug, Clone)]
+pub struct SpellCardData {
+    card_id: SpellId,
+    difficulty: Difficulty,
+    card_name: Box<[u8]>,
+    career_stats: Vec<SpellCardCareer>,
+}
+
+impl SpellCardData {
+    fn new(entry: &SpellCardEntry) -> Self {
+        Self {
+            card_id: entry.card_id,
+            difficulty: entry.difficulty,
+            card_name: entry.card_name.clone(),
+            career_stats: vec![SpellCardCareer::default(); Touhou10::SHOT_TYPES.len()],
+        }
+    }
+
+    pub fn card_id(&self) -> SpellId {
+        self.card_id
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    pub fn card_name(&self) -> &[u8] {
+        &self.card_name[..]
+    }
+
+    pub fn shot_stats(&self, shot: &ShotType) -> &SpellCardCareer {
+        let idx: usize = shot.into();
+        &self.career_stats[idx]
+    }
+
+    pub fn iter_shot_stats(&self) -> impl Iterator<Item = (ShotType, &SpellCardCareer)> + '_ {
+        ShotType::iter_all().zip(self.career_stats.iter())
+    }
+}
+
+impl SpellCardRecord<Touhou10> for SpellCardData {
+    fn card(&self) -> SpellCard<Touhou10> {
+        SpellCard::new(self.card_id)
+    }
+
+    fn shot_types(&self) -> &[ShotWrapper<Touhou10>] {
+        &Touhou10::SHOT_TYPES[..]
+    }
+
+    fn attempts(&self, shot: &ShotWrapper<Touhou10>) -> u32 {
+        self.shot_stats(&shot.unwrap()).attempts()
+    }
+
+    fn captures(&self, shot: &ShotWrapper<Touhou10>) -> u32 {
+        self.shot_stats(&shot.unwrap()).captures()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PracticeScore {
+    shot_type: ShotType,
+    stage: Stage,
+    difficulty: Difficulty,
+    high_score: u32,
+    unlocked: bool,
+}
+
+impl PracticeScore {
+    /// Whether this stage has been unlocked for practice with this shot type and difficulty.
+    pub fn unlocked(&self) -> bool {
+        self.unlocked
+    }
+}
+
+impl PracticeRecord<Touhou10> for PracticeScore {
+    /// The practice high score, with the last digit (always 0) included.
+    fn high_score(&self) -> u32 {
+        self.high_score.saturating_mul(10)
+    }
+
+    fn shot_type(&self) -> ShotWrapper<Touhou10> {
+        ShotWrapper::new(self.shot_type)
+    }
+
+    fn difficulty(&self) -> DifficultyWrapper<Touhou10> {
+        DifficultyWrapper::new(self.difficulty)
+    }
+
+    fn stage(&self) -> StageWrapper<Touhou10> {
+        StageWrapper::new(self.stage)
+    }
+}
+
+/// Play data stored for a single shot type, or for all shot types combined.
+#[derive(Debug, Clone)]
+pub struct ClearData {
+    shot_type: Option<ShotType>,
+    rankings: HashMap<Difficulty, Vec<HighScore>>,
+    total_play_count: u32,
+    play_time: u32,
+    clear_counts: HashMap<Difficulty, u32>,
+    practices: Vec<PracticeScore>,
+    cards: Vec<SpellCardEntry>,
+}
+
+impl ClearData {
+    /// The shot type this data applies to, or `None` for the combined totals across all shot types.
+    pub fn shot_type(&self) -> Option<ShotType> {
+        self.shot_type
+    }
+
+    pub fn rankings(&self, difficulty: Difficulty) -> &[HighScore] {
+        self.rankings
+            .get(&difficulty)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    pub fn total_play_count(&self) -> u32 {
+        self.total_play_count
+    }
+
+    /// The total play time, in frames.
+    pub fn play_time(&self) -> u32 {
+        self.play_time
+    }
+
+    pub fn clear_count(&self, difficulty: Difficulty) -> u32 {
+        self.clear_counts.get(&difficulty).copied().unwrap_or(0)
+    }
+
+    pub fn practices(&self) -> &[PracticeScore] {
+        &self.practices[..]
+    }
+
+    pub fn cards(&self) -> &[SpellCardEntry] {
+        &self.cards[..]
+    }
+
+    pub fn read_from<R: Read>(mut src: R) -> io::Result<Self> {
+        static PRACTICE_STAGES: [Stage; 6] = [
+            Stage::One,
+            Stage::Two,
+            Stage::Three,
+            Stage::Four,
+            Stage::Five,
+            Stage::Six,
+        ];
+
+        static PRACTICE_DIFFICULTIES: [Difficulty; 4] = [
+            Difficulty::Easy,
+            Difficulty::Normal,
+            Difficulty::Hard,
+            Difficulty::Lunatic,
+        ];
+
+        let shot_type = match src.read_u32::<LittleEndian>()? {
+            6 => None,
+            value => Some(
+                u8::try_from(value)
+                    .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+                    .and_then(try_into_or_io_error(ErrorKind::InvalidData))?,
+            ),
+        };
+
+        let mut rankings = HashMap::new();
+        for difficulty in Difficulty::iter_all() {
+            let mut scores = Vec::with_capacity(10);
+            for _ in 0..10 {
+                scores.push(HighScore::read_from(&mut src)?);
+            }
+            rankings.insert(difficulty, scores);
+        }
+
+        let total_play_count = src.read_u32::<LittleEndian>()?;
+        let play_time = src.read_u32::<LittleEndian>()?;
+
+        let mut clear_counts = HashMap::new();
+        for difficulty in Difficulty::iter_all() {
+            clear_counts.insert(difficulty, src.read_u32::<LittleEndian>()?);
+        }
+
+        let keys = PRACTICE_DIFFICULTIES.into_iter().flat_map(|difficulty| {
+            PRACTICE_STAGES
+                .into_iter()
+                .map(move |stage| (stage, difficulty))
+        });
+
+        let mut practices = Vec::with_capacity(24);
+        for (stage, difficulty) in keys {
+            let high_score = src.read_u32::<LittleEndian>()?;
+            let stage_flag = src.read_u32::<LittleEndian>()?;
+
+            if let Some(shot_type) = shot_type {
+                practices.push(PracticeScore {
+                    shot_type,
+                    stage,
+                    difficulty,
+                    high_score,
+                    unlocked: stage_flag != 0,
+                });
+            }
+        }
+
+        let mut cards = Vec::with_capacity(110);
+        for _ in 0..110 {
+            cards.push(SpellCardEntry::read_from(&mut src)?);
+        }
+
+        Ok(Self {
+            shot_type,
+            rankings,
+            total_play_count,
+            play_time,
+            clear_counts,
+            practices,
+            cards,
+        })
+    }
+}
+
+#[derive(Clone)]
+pub enum Segment {
+    Clear(ClearData),
+    Unknown([u8; 2], u16, usize, Box<[u8]>),
+}
+
+impl Segment {
+    pub fn signature(&self) -> &[u8; 2] {
+        match self {
+            Self::Clear(_) => b"CR",
+            Self::Unknown(sig, _, _, _) => sig,
+        }
+    }
+
+    fn signature_string(&self) -> String {
+        let v: Vec<u8> = self
+            .signature()
+            .iter()
+            .copied()
+            .flat_map(std::ascii::escape_default)
+            .collect();
+        String::from_utf8(v).unwrap()
+    }
+
+    pub fn read_from<R: Read>(mut src: R) -> io::Result<Option<Self>> {
+        let mut signature = [0; 2];
+        return_none_on_eof!(src.read_exact(&mut signature));
+        let version = return_none_on_eof!(src.read_u16::<LittleEndian>());
+        return_none_on_eof!(src.read_u32::<LittleEndian>()); // checksum
+        let size = return_none_on_eof!(src.read_u32::<LittleEndian>()) as usize;
+
+        if size <= 12 {
+            return Self::read_from(src);
+        }
+
+        let mut data = vec![0u8; size - 12];
+        return_none_on_eof!(src.read_exact(&mut data));
+
+        let mut reader = Cursor::new(data);
+        match &signature {
+            b"CR" => ClearData::read_from(&mut reader).map(Self::Clear),
+            _ => Ok(Self::Unknown(
+                signature,
+                version,
+                size,
+                reader.into_inner().into(),
+            )),
+        }
+        .map(Some)
+    }
+}
+
+impl Debug for Segment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Clear(d) => f
+                .debug_struct("Segment::Clear")
+                .field("signature", &self.signature_string())
+                .field("data", d)
+                .finish(),
+            Self::Unknown(_, version, size, d) => f
+                .debug_struct("Segment::Unknown")
+                .field("signature", &self.signature_string())
+                .field("version", version)
+                .field("size", size)
+                .field("data", &format!("[{} bytes]", d.len()))
+                .finish(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ScoreReader<R> {
+    header: FileHeader,
+    src: StreamDecompressor<ThCrypt<R>>,
+}
+
+impl<R: Read> ScoreReader<R> {
+    pub fn new(mut src: R) -> Result<Self, io::Error> {
+        let header = FileHeader::read_from(&mut src)?;
+        let decryptor = ThCrypt::new(src, 0xAC, 0x35, 0x10, Some(header.encoded_body_sz));
+        let src = StreamDecompressor::new(decryptor);
+        Ok(Self { header, src })
+    }
+
+    pub fn header(&self) -> &FileHeader {
+        &self.header
+    }
+}
+
+impl<R: Read> Iterator for ScoreReader<R> {
+    type Item = Result<Segment, io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Segment::read_from(&mut self.src).transpose()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScoreFile {
+    cards: Vec<SpellCardData>,
+    practices: Vec<PracticeScore>,
+}
+
+impl ScoreFile {
+    pub fn new<R: Read>(src: R) -> Result<Self, io::Error> {
+        let mut cards: Vec<SpellCardData> = Vec::with_capacity(110);
+        let mut practices = Vec::new();
+
+        for segment in ScoreReader::new(src)? {
+            let data = match segment {
+                Ok(Segment::Clear(data)) => data,
+                Ok(_) => continue,
+                Err(e) => return Err(e),
+            };
+
+            // The combined clear data just duplicates the per-shot data.
+            let shot_type = match data.shot_type {
+                Some(shot_type) => shot_type,
+                None => continue,
+            };
+
+            let shot_idx: usize = shot_type.into();
+            for entry in &data.cards {
+                let idx = match cards.iter().position(|card| card.card_id == entry.card_id) {
+                    Some(idx) => idx,
+                    None => {
+                        cards.push(SpellCardData::new(entry));
+                        cards.len() - 1
+                    }
+                };
+
+                cards[idx].career_stats[shot_idx] = SpellCardCareer {
+                    attempts: entry.attempts,
+                    captures: entry.captures,
+                };
+            }
+
+            practices.extend(data.practices);
+        }
+
+        Ok(Self { cards, practices })
+    }
+}
+
+impl crate::score::ScoreFile<Touhou10> for ScoreFile {
+    type SpellCardRecord = SpellCardData;
+    type PracticeRecord = PracticeScore;
+
+    fn spell_cards(&self) -> &[SpellCardData] {
+        &self.cards[..]
+    }
+
+    fn practice_records(&self) -> &[PracticeScore] {
+        &self.practices[..]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use byteorder::{ByteOrder, WriteBytesExt};
+
+    use super::*;
+    use crate::score::ScoreFile as _;
+
+    fn write_high_score(dst: &mut Vec<u8>, score: u32, progress: u8) {
+        dst.write_u32::<LittleEndian>(score).unwrap();
+        dst.write_u8(progress).unwrap();
+        dst.write_u8(0).unwrap(); // continues
+        dst.write_all(b"TESTNAME\0\0").unwrap();
+        dst.write_u32::<LittleEndian>(1_500_000_000).unwrap(); // timestamp
+        dst.write_f32::<LittleEndian>(0.0).unwrap(); // slow
+    }
+
+    /// Builds a clear data segment where card `idx` has been attempted `idx + shot_type` times and captured once,
+    /// and every practice score is `practice_score`.
+    fn clear_segment(shot_type: u32, practice_score: u32) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.write_u32::<LittleEndian>(shot_type).unwrap();
+
+        for _ in 0..5 {
+            for rank in 0..10 {
+                write_high_score(&mut data, 1000 - rank * 100, 3);
+            }
+        }
+
+        data.write_u32::<LittleEndian>(10).unwrap(); // total play count
+        data.write_u32::<LittleEndian>(600_000).unwrap(); // play time
+        for count in [1, 2, 3, 4, 5] {
+            data.write_u32::<LittleEndian>(count).unwrap();
+        }
+
+        for _ in 0..24 {
+            data.write_u32::<LittleEndian>(practice_score).unwrap();
+            data.write_u32::<LittleEndian>(1).unwrap(); // unlocked
+        }
+
+        for idx in 0..110 {
+            data.extend_from_slice(&[0; 0x80]); // card name
+            data.write_u32::<LittleEndian>(1).unwrap(); // captures
+            data.write_u32::<LittleEndian>(idx + shot_type).unwrap(); // attempts
+            data.write_u32::<LittleEndian>(idx).unwrap(); // card ID
+            data.write_u32::<LittleEndian>(1).unwrap(); // difficulty
+        }
+
+        let mut segment = b"CR".to_vec();
+        segment.write_u16::<LittleEndian>(1).unwrap(); // version
+        segment.write_u32::<LittleEndian>(0).unwrap(); // checksum
+        segment
+            .write_u32::<LittleEndian>((data.len() + 12) as u32)
+            .unwrap();
+        segment.extend(data);
+        segment
+    }
+
+    fn encode_file(segments: &[Vec<u8>]) -> Vec<u8> {
+        let body = segments.concat();
+        let mut compressor = StreamCompressor::new(Vec::new());
+        compressor.write_all(&body).unwrap();
+        let (compressed, _) = compressor.finish().unwrap();
+
+        let mut encryptor = ThEncrypt::new(Vec::new(), 0xAC, 0x35, 0x10, None);
+        encryptor.write_all(&compressed).unwrap();
+        let encrypted = encryptor.finish().unwrap();
+
+        let mut file = SIGNATURE.to_vec();
+        file.write_u32::<LittleEndian>((encrypted.len() + 24) as u32)
+            .unwrap();
+        file.write_u32::<LittleEndian>(0).unwrap();
+        file.write_u32::<LittleEndian>(0).unwrap();
+        file.write_u32::<LittleEndian>(encrypted.len() as u32)
+            .unwrap();
+        file.write_u32::<LittleEndian>(body.len() as u32).unwrap();
+        file.extend(encrypted);
+        file
+    }
+
+    #[test]
+    fn read_segments() {
+        let file = encode_file(&[clear_segment(1, 123), clear_segment(6, 0)]);
+        let mut reader = ScoreReader::new(&file[..]).unwrap();
+
+        let data = match reader.next().unwrap().unwrap() {
+            Segment::Clear(data) => data,
+            other => panic!("unexpected segment {:?}", other),
+        };
+        assert_eq!(data.shot_type(), Some(ShotType::ReimuB));
+        assert_eq!(data.total_play_count(), 10);
+        assert_eq!(data.clear_count(Difficulty::Extra), 5);
+        assert_eq!(data.rankings(Difficulty::Hard).len(), 10);
+        assert_eq!(data.rankings(Difficulty::Hard)[0].score(), 10000);
+        assert_eq!(
+            data.rankings(Difficulty::Hard)[0].progress(),
+            StageProgress::LostAt(StageWrapper::new(Stage::Three))
+        );
+        assert_eq!(data.practices().len(), 24);
+        assert_eq!(data.cards().len(), 110);
+        assert_eq!(data.cards()[9].card_id(), SpellId::new(10).unwrap());
+        assert_eq!(data.cards()[9].attempts(), 10);
+
+        // the combined totals don't include practice scores
+        match reader.next().unwrap().unwrap() {
+            Segment::Clear(data) => {
+                assert_eq!(data.shot_type(), None);
+                assert!(data.practices().is_empty());
+            }
+            other => panic!("unexpected segment {:?}", other),
+        }
+
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn merge_shot_types() {
+        let file = encode_file(&[
+            clear_segment(0, 123),
+            clear_segment(4, 456),
+            clear_segment(6, 0),
+        ]);
+        let file = ScoreFile::new(&file[..]).unwrap();
+        let reimu = ShotWrapper::new(ShotType::ReimuA);
+        let marisa = ShotWrapper::new(ShotType::MarisaB);
+        let unplayed = ShotWrapper::new(ShotType::ReimuC);
+
+        assert_eq!(file.spell_cards().len(), 110);
+        let card = &file.spell_cards()[9];
+        assert_eq!(card.card_id(), SpellId::new(10).unwrap());
+        assert_eq!(card.attempts(&reimu), 9);
+        assert_eq!(card.attempts(&marisa), 13);
+        assert_eq!(card.attempts(&unplayed), 0);
+        assert_eq!(card.total_attempts(), 22);
+        assert_eq!(card.total_captures(), 2);
+
+        assert_eq!(file.practice_records().len(), 48);
+        let scores: Vec<_> = file
+            .practice_records()
+            .iter()
+            .filter(|practice| {
+                practice.difficulty == Difficulty::Normal && practice.stage == Stage::Two
+            })
+            .map(|practice| (practice.shot_type(), practice.high_score()))
+            .collect();
+        assert_eq!(scores, [(reimu, 1230), (marisa, 4560)]);
+    }
+
+    /// A complete score file holding a single unrecognized `XX` segment, built by hand with thtk's encryption and
+    /// LZSS algorithms rather than this crate's encoders.
+    const HAND_BUILT_FILE: [u8; 51] = [
+        0x54, 0x48, 0x31, 0x30, 0x33, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+        0x00, 0x00, 0x00, 0x00, 0x1b, 0x00, 0x00, 0x00, 0x20, 0x00, 0x00, 0x00, //
+        0x12, 0xeb, 0x36, 0xdb, 0x81, 0x21, 0xda, 0x49, 0x39, 0x99, 0xfe, 0xf3, //
+        0x3a, 0x50, 0xb2, 0x6b, 0xfc, 0x25, 0x76, 0xad, 0xf1, 0x09, 0x22, 0x0f, //
+        0xa4, 0x54, 0x00,
+    ];
+
+    /// Builds a decoded `CR` segment at its real size, with fields placed at their documented offsets.
+    fn hand_built_clear_data() -> Vec<u8> {
+        let mut data = vec![0u8; 0x437C];
+        data[0x00..0x02].copy_from_slice(b"CR");
+        LittleEndian::write_u32(&mut data[0x08..], 0x437C); // size
+        LittleEndian::write_u32(&mut data[0x0C..], 3); // shot type (Marisa A)
+
+        // Hard ranking #1; rankings start at 0x10 and hold 10 entries of 0x18 bytes per difficulty
+        LittleEndian::write_u32(&mut data[0x1F0..], 123_456);
+        data[0x1F4] = 99; // all clear
+        data[0x1F5] = 2; // continues
+        data[0x1F6..0x200].copy_from_slice(b"NITORI    ");
+        LittleEndian::write_u32(&mut data[0x200..], 1_200_000_000);
+        LittleEndian::write_f32(&mut data[0x204..], 1.5);
+
+        LittleEndian::write_u32(&mut data[0x4C0..], 42); // total play count
+        LittleEndian::write_u32(&mut data[0x4C4..], 216_000); // play time
+        LittleEndian::write_u32(&mut data[0x4D4..], 7); // Lunatic clears
+
+        // Normal stage 4 practice; practices start at 0x4DC and hold 6 stages of 8 bytes per difficulty
+        LittleEndian::write_u32(&mut data[0x524..], 5_000);
+        LittleEndian::write_u32(&mut data[0x528..], 1);
+
+        // the last card; cards start at 0x59C and are 0x90 bytes each
+        data[0x42EC..0x42F5].copy_from_slice(b"Test Sign");
+        LittleEndian::write_u32(&mut data[0x436C..], 3); // captures
+        LittleEndian::write_u32(&mut data[0x4370..], 8); // attempts
+        LittleEndian::write_u32(&mut data[0x4374..], 109); // card ID
+        LittleEndian::write_u32(&mut data[0x4378..], 4); // difficulty
+
+        let checksum = data[0x08..].iter().map(|&b| b as u32).sum();
+        LittleEndian::write_u32(&mut data[0x04..], checksum);
+        data
+    }
+
+    #[test]
+    fn decode_hand_built_file() {
+        let mut reader = ScoreReader::new(&HAND_BUILT_FILE[..]).unwrap();
+        assert_eq!(reader.header().encoded_full_sz(), HAND_BUILT_FILE.len());
+        assert_eq!(reader.header().encoded_body_sz(), 27);
+        assert_eq!(reader.header().decoded_body_sz(), 32);
+
+        match reader.next().unwrap().unwrap() {
+            Segment::Unknown(signature, version, size, data) => {
+                assert_eq!(&signature, b"XX");
+                assert_eq!(version, 1);
+                assert_eq!(size, 32);
+                assert_eq!(&data[..], b"MoF!MoF!MoF!MoF!\x00\x01\x02\x03");
+            }
+            other => panic!("unexpected segment {:?}", other),
+        }
+
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn decode_hand_built_clear_data() {
+        let data = match Segment::read_from(&hand_built_clear_data()[..]).unwrap() {
+            Some(Segment::Clear(data)) => data,
+            other => panic!("unexpected segment {:?}", other),
+        };
+
+        assert_eq!(data.shot_type(), Some(ShotType::MarisaA));
+
+        let best = &data.rankings(Difficulty::Hard)[0];
+        assert_eq!(best.score(), 1_234_562);
+        assert_eq!(best.progress(), StageProgress::AllClear);
+        assert_eq!(best.continues(), 2);
+        assert_eq!(best.name(), b"NITORI    ");
+        assert_eq!(best.timestamp(), 1_200_000_000);
+        assert_eq!(best.slow(), 1.5);
+        assert_eq!(
+            data.rankings(Difficulty::Hard)[1].progress(),
+            StageProgress::NotStarted
+        );
+
+        assert_eq!(data.total_play_count(), 42);
+        assert_eq!(data.play_time(), 216_000);
+        assert_eq!(data.clear_count(Difficulty::Lunatic), 7);
+        assert_eq!(data.clear_count(Difficulty::Extra), 0);
+
+        let unlocked: Vec<_> = data
+            .practices()
+            .iter()
+            .filter(|practice| practice.unlocked())
+            .map(|practice| (practice.difficulty, practice.stage, practice.high_score()))
+            .collect();
+        assert_eq!(unlocked, [(Difficulty::Normal, Stage::Four, 50_000)]);
+
+        let card = &data.cards()[109];
+        assert_eq!(&card.card_name()[..9], b"Test Sign");
+        assert_eq!(card.captures(), 3);
+        assert_eq!(card.attempts(), 8);
+        assert_eq!(card.card_id(), SpellId::new(110).unwrap());
+        assert_eq!(card.difficulty(), Difficulty::Extra);
+    }
+
+    #[test]
+    fn invalid_progress() {
+        let mut data = Vec::new();
+        write_high_score(&mut data, 0, 50);
+
+        let err = HighScore::read_from(&data[..]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -10,6 +10,14 @@ mod spellcards;
 #[cfg(feature = "memory")]
 pub mod memory;
 
+#[cfg(feature = "score-file")]
+pub mod score;
+
+#[cfg(feature = "memory")]
+pub use memory::*;
+#[cfg(feature = "score-file")]
+pub use score::ScoreFile;
+
 pub use spellcards::SpellId;
 
 define_game! {
@@ -52,3 +60,12 @@ define_game! {
         }
     }
 }
+
+#[cfg(feature = "score-file")]
+impl crate::score::HasScoreFile for Touhou15 {
+    type ScoreFile = ScoreFile;
+
+    fn load_score_file<R: std::io::Read>(src: R) -> Result<ScoreFile, std::io::Error> {
+        ScoreFile::new(src)
+    }
+}
@@ -0,0 +1,153 @@
+use touhou_macros::define_locations;
+
+use crate::th15::{SpellId, Stage, Touhou15};
+
+// LoLK's stage timelines are keyed by the game's chapter counter instead of ECL frames,
+// with the midboss and boss fights each taking up a chapter of their own.
+//
+// Boss nonspells aren't resolved, since LoLK's lifebar counts don't line up with the other games'.
+define_locations! {
+    #[game(Touhou15)]
+    Location {
+        One: {
+            1 => Section("Chapter 1"),
+            2 => Section("Chapter 2"),
+            3 => Midboss [
+                Spells(1..=2)
+            ],
+            4 => Section("Chapter 4"),
+            5 => Boss [
+                Spells(3..=6),
+                Spells(7..=10)
+            ]
+        },
+        Two: {
+            1 => Section("Chapter 1"),
+            2 => Section("Chapter 2"),
+            3 => Section("Chapter 3"),
+            4 => Section("Chapter 4"),
+            5 => Boss [
+                Spells(11..=14),
+                Spells(15..=18),
+                Spells(19..=22)
+            ]
+        },
+        Three: {
+            1 => Section("Chapter 1"),
+            2 => Section("Chapter 2"),
+            3 => Midboss [
+                Spells(23..=26)
+            ],
+            4 => Section("Chapter 4"),
+            5 => Boss [
+                Spells(27..=30),
+                Spells(31..=34),
+                Spells(35..=38),
+                Spells(39..=42)
+            ]
+        },
+        Four: {
+            1 => Section("Chapter 1"),
+            2 => Section("Chapter 2"),
+            3 => Midboss [
+                Spells(43..=46)
+            ],
+            4 => Section("Chapter 4"),
+            5 => Boss [
+                Spells(47..=50),
+                Spells(51..=54),
+                Spells(55..=58)
+            ]
+        },
+        Five: {
+            1 => Section("Chapter 1"),
+            2 => Section("Chapter 2"),
+            3 => Section("Chapter 3"),
+            4 => Section("Chapter 4"),
+            5 => Boss [
+                Spells(59..=62),
+                Spells(63..=66),
+                Spells(67..=70),
+                Spells(71..=74),
+                Spells(75..=78)
+            ]
+        },
+        Six: {
+            1 => Section("Chapter 1"),
+            2 => Section("Chapter 2"),
+            3 => Section("Chapter 3"),
+            4 => Section("Chapter 4"),
+            5 => Boss [
+                Spells(79..=82),
+                Spells(83..=86),
+                Spells(87..=90),
+                Spells(91..=94),
+                Spells(95..=98),
+                Spells(99..=102),
+                Spells(103..=106)
+            ]
+        },
+        Extra: {
+            1 => Section("Chapter 1"),
+            2 => Section("Chapter 2"),
+            3 => Midboss [
+                Spells(107..=107),
+                Spells(108..=108),
+                Spells(109..=109)
+            ],
+            4 => Section("Chapter 4"),
+            5 => Boss [
+                Spells(110..=110),
+                Spells(111..=111),
+                Spells(112..=112),
+                Spells(113..=113),
+                Spells(114..=114),
+                Spells(115..=115),
+                Spells(116..=116),
+                Spells(117..=117),
+                Spells(118..=118),
+                Spells(119..=119)
+            ]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::{GameLocation, HasLocations, Location as LocationWrapper};
+    use crate::types::{AllIterable, SpellCard};
+
+    #[test]
+    fn spell_locations() {
+        for spell in SpellId::iter_all().map(SpellCard::<Touhou15>::new) {
+            let location = LocationWrapper::from_spell(spell)
+                .unwrap_or_else(|| panic!("no location for {}", spell.name()));
+
+            assert_eq!(location.stage(), spell.stage());
+            assert_eq!(location.spell(), Some(spell));
+        }
+    }
+
+    #[test]
+    fn stage_boundaries() {
+        for stage in Stage::iter_all() {
+            let start = Touhou15::stage_start_location(stage);
+            let sections: Vec<_> = Location::iter_all()
+                .filter(|location| *location.stage() == stage)
+                .collect();
+
+            assert_eq!(sections[0], start);
+            assert_eq!(sections[1].name(), "Chapter 1");
+            assert!(start < sections[1]);
+
+            let end = sections.last().unwrap();
+            assert!(end.is_end());
+            assert!(end.spell().is_some());
+            let mut nonspells = sections
+                .iter()
+                .filter(|location| location.spell().is_none());
+            assert!(nonspells.all(|location| !location.is_end()));
+        }
+    }
+}
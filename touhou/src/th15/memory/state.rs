@@ -0,0 +1,386 @@
+use super::location::Location;
+use super::process::MemoryAccess;
+use crate::memory::traits::*;
+use crate::memory::{
+    define_state_struct, try_into_or_mem_error, Location as LocationWrapper, MemoryReadError,
+    SpellState,
+};
+use crate::th15::{SpellId, Touhou15};
+use crate::types::{Difficulty, ShotPower, ShotType, SpellCard, Stage};
+
+pub type ReadResult<T> = Result<T, MemoryReadError<Touhou15>>;
+
+const PRACTICE_FLAG_STAGE: u32 = 16;
+const PRACTICE_FLAG_SPELL: u32 = 32;
+const PRACTICE_FLAG_POINTDEVICE: u32 = 256;
+
+define_state_struct! {
+    PlayerState {
+        character: ShotType<Touhou15>,
+        lives: u8,
+        life_fragments: u8,
+        bombs: u8,
+        bomb_fragments: u8,
+        power: ShotPower<Touhou15>,
+        continues: u8,
+        score: u32,
+        pointdevice: bool,
+        total_retries: u32,
+        chapter_retries: u32,
+    }
+}
+
+impl PlayerState {
+    pub fn new(proc: &MemoryAccess) -> ReadResult<Self> {
+        let character = proc
+            .character()
+            .and_then(try_into_or_mem_error)
+            .map(ShotType::new)?;
+
+        let power = proc
+            .power()
+            .and_then(|power| {
+                u16::try_from(power)
+                    .map_err(|_| MemoryReadError::other_out_of_range("power", power, 0, 400))
+            })
+            .and_then(try_into_or_mem_error)
+            .map(ShotPower::new)?;
+
+        Ok(Self {
+            character,
+            lives: proc.lives()? as u8,
+            life_fragments: proc.life_fragments()? as u8,
+            bombs: proc.bombs()? as u8,
+            bomb_fragments: proc.bomb_fragments()? as u8,
+            power,
+            continues: proc.continues_used()? as u8,
+            score: proc.score()?,
+            pointdevice: (proc.practice_flags()? & PRACTICE_FLAG_POINTDEVICE) != 0,
+            total_retries: proc.total_retries()?,
+            chapter_retries: proc.chapter_retries()?,
+        })
+    }
+}
+
+impl PlayerData<Touhou15> for PlayerState {
+    fn shot(&self) -> ShotType<Touhou15> {
+        self.character
+    }
+
+    fn power(&self) -> ShotPower<Touhou15> {
+        self.power
+    }
+}
+
+impl LifeStock<Touhou15> for PlayerState {
+    fn lives(&self) -> u8 {
+        self.lives
+    }
+}
+
+impl PlayerState {
+    /// Get this state as a Pointdevice mode player state, if Pointdevice mode is enabled.
+    pub fn as_pointdevice(&self) -> Option<PointdevicePlayer> {
+        if self.pointdevice {
+            Some(PointdevicePlayer(*self))
+        } else {
+            None
+        }
+    }
+}
+
+/// The state of a player in Pointdevice mode.
+///
+/// In Pointdevice mode, every miss sends the player back to the start of the current chapter,
+/// so the game's retry counter doubles as a miss counter. Legacy mode misses can only be counted
+/// from drops in the player's [life stock](LifeStock::lives).
+#[derive(Debug, Copy, Clone)]
+pub struct PointdevicePlayer(PlayerState);
+
+impl PointdevicePlayer {
+    /// Wraps a player state from a run that is known to be in Pointdevice mode.
+    pub(super) fn new(player: PlayerState) -> Self {
+        Self(player)
+    }
+
+    pub fn player(&self) -> &PlayerState {
+        &self.0
+    }
+}
+
+impl PlayerData<Touhou15> for PointdevicePlayer {
+    fn shot(&self) -> ShotType<Touhou15> {
+        self.0.shot()
+    }
+
+    fn power(&self) -> ShotPower<Touhou15> {
+        self.0.power()
+    }
+}
+
+impl MissCount<Touhou15> for PointdevicePlayer {
+    fn total_misses(&self) -> u8 {
+        self.0.total_retries.min(u8::MAX as u32) as u8
+    }
+}
+
+impl BombStock<Touhou15> for PlayerState {
+    fn bombs(&self) -> u8 {
+        self.bombs
+    }
+}
+
+impl ContinueCount<Touhou15> for PlayerState {
+    fn continues_used(&self) -> u8 {
+        self.continues
+    }
+}
+
+impl PlayerScore<Touhou15> for PlayerState {
+    fn score(&self) -> u64 {
+        self.score as u64
+    }
+}
+
+define_state_struct! {
+    ActiveSpell {
+        spell: SpellCard<Touhou15>,
+        bonus: Option<u32>
+    }
+}
+
+impl ActiveSpell {
+    pub fn new(proc: &MemoryAccess) -> ReadResult<Option<Self>> {
+        let status = proc.active_spell_status()?;
+        if (status & 1) != 0 {
+            let id = proc.active_spell()?;
+            let bonus = if (status & 2) != 0 {
+                Some(proc.active_spell_bonus()?).filter(|&val| val > 0)
+            } else {
+                None
+            };
+
+            let spell = id
+                .checked_add(1)
+                .and_then(|id| u16::try_from(id).ok())
+                .ok_or_else(|| MemoryReadError::other(format!("invalid spell ID {}", id)))
+                .and_then(|id| SpellId::new(id).map_err(MemoryReadError::from))
+                .map(SpellCard::new)?;
+            Ok(Some(ActiveSpell { spell, bonus }))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+define_state_struct! {
+    BossState {
+        active_spell: Option<ActiveSpell>,
+        remaining_lifebars: u8
+    }
+}
+
+impl BossState {
+    pub fn new(proc: &MemoryAccess) -> ReadResult<Self> {
+        Ok(Self {
+            active_spell: ActiveSpell::new(proc)?,
+            remaining_lifebars: proc.boss_lifebars()? as u8,
+        })
+    }
+
+    pub fn spell(&self) -> Option<SpellCard<Touhou15>> {
+        self.active_spell.as_ref().map(ActiveSpell::spell)
+    }
+}
+
+impl BossData<Touhou15> for BossState {
+    fn active_spell(&self) -> Option<SpellState<Touhou15>> {
+        self.active_spell
+            .map(|active| SpellState::new(active.spell().unwrap(), active.bonus().is_some()))
+    }
+}
+
+impl BossLifebars<Touhou15> for BossState {
+    fn remaining_lifebars(&self) -> u8 {
+        self.remaining_lifebars
+    }
+}
+
+define_state_struct! {
+    StageState {
+        stage: Stage<Touhou15>,
+        chapter: u32,
+        chapter_frames: u32,
+        boss: Option<BossState>
+    }
+}
+
+impl StageState {
+    pub fn new(proc: &MemoryAccess) -> ReadResult<Self> {
+        let stage: Stage<Touhou15> = proc
+            .stage()
+            .and_then(|id| {
+                id.checked_sub(1)
+                    .ok_or_else(|| MemoryReadError::other("invalid stage id 0"))
+            })
+            .and_then(try_into_or_mem_error)
+            .map(Stage::new)?;
+
+        let boss = BossState::new(proc)?;
+        let boss = if boss.active_spell().is_some() || boss.remaining_lifebars() > 0 {
+            Some(boss)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            stage,
+            chapter: proc.chapter()?,
+            chapter_frames: proc.chapter_frames()?,
+            boss,
+        })
+    }
+}
+
+/// LoLK's locations are keyed by chapter, so the chapter number stands in for the stage's ECL time.
+impl ECLTimeline<Touhou15> for StageState {
+    fn ecl_time(&self) -> u32 {
+        self.chapter
+    }
+}
+
+impl StageData<Touhou15> for StageState {
+    type BossState = BossState;
+
+    fn stage_id(&self) -> Stage<Touhou15> {
+        self.stage
+    }
+
+    fn active_boss(&self) -> Option<&Self::BossState> {
+        self.boss.as_ref()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PracticeType {
+    None,
+    Stage,
+    Spell,
+}
+
+define_state_struct! {
+    RunState {
+        difficulty: Difficulty<Touhou15>,
+        practice: PracticeType,
+        player: PlayerState,
+        stage: StageState
+    }
+}
+
+impl RunState {
+    pub fn new(proc: &MemoryAccess) -> ReadResult<Self> {
+        let difficulty = proc
+            .difficulty()
+            .and_then(try_into_or_mem_error)
+            .map(Difficulty::new)?;
+
+        let flags = proc.practice_flags()?;
+        let practice = if (flags & PRACTICE_FLAG_SPELL) != 0 {
+            PracticeType::Spell
+        } else if (flags & PRACTICE_FLAG_STAGE) != 0 {
+            PracticeType::Stage
+        } else {
+            PracticeType::None
+        };
+
+        Ok(Self {
+            difficulty,
+            practice,
+            player: PlayerState::new(proc)?,
+            stage: StageState::new(proc)?,
+        })
+    }
+
+    pub fn pointdevice(&self) -> bool {
+        self.player.pointdevice()
+    }
+}
+
+impl RunData<Touhou15> for RunState {
+    type PlayerState = PlayerState;
+    type StageState = StageState;
+
+    fn difficulty(&self) -> Difficulty<Touhou15> {
+        self.difficulty
+    }
+
+    fn player(&self) -> &Self::PlayerState {
+        &self.player
+    }
+
+    fn stage(&self) -> &Self::StageState {
+        &self.stage
+    }
+
+    fn is_practice(&self) -> bool {
+        self.practice != PracticeType::None
+    }
+}
+
+impl ResolveLocation<Touhou15> for RunState {
+    fn resolve_location(&self) -> Option<LocationWrapper<Touhou15>> {
+        // spell practice only covers the spell card itself
+        Location::resolve(self)
+            .filter(|location| self.practice != PracticeType::Spell || location.spell().is_some())
+            .map(LocationWrapper::new)
+    }
+}
+
+/// The game mode values used by the game's supervisor (stored in the `game_type` field).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GameMode {
+    TitleScreen,
+    InGame,
+    Ending,
+    StaffRoll,
+    Unknown(u32),
+}
+
+impl From<u32> for GameMode {
+    fn from(value: u32) -> Self {
+        match value {
+            1 => Self::TitleScreen,
+            4 => Self::InGame,
+            7 => Self::Ending,
+            8 => Self::StaffRoll,
+            other => Self::Unknown(other),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum GameState {
+    TitleScreen,
+    InGame(RunState),
+    Ending(RunState),
+    StaffRoll,
+    Unknown(u32),
+}
+
+impl GameState {
+    /// Whether a run is in progress, including its ending (so that cleared runs can be told apart from exits).
+    pub fn game_is_active(proc: &MemoryAccess) -> ReadResult<bool> {
+        proc.game_type()
+            .map(|mode| matches!(GameMode::from(mode), GameMode::InGame | GameMode::Ending))
+    }
+
+    pub fn new(proc: &MemoryAccess) -> ReadResult<Self> {
+        match GameMode::from(proc.game_type()?) {
+            GameMode::TitleScreen => Ok(Self::TitleScreen),
+            GameMode::InGame => RunState::new(proc).map(Self::InGame),
+            GameMode::Ending => RunState::new(proc).map(Self::Ending),
+            GameMode::StaffRoll => Ok(Self::StaffRoll),
+            GameMode::Unknown(other) => Ok(Self::Unknown(other)),
+        }
+    }
+}
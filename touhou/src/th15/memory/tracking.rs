@@ -0,0 +1,494 @@
+use std::time::Duration;
+
+use super::process::MemoryAccess;
+use super::state::{PointdevicePlayer, PracticeType};
+use super::{GameMemory, GameState, RunState};
+use crate::memory::{Location, MemoryReadError, PlayerData, StageData};
+use crate::tracking::builder::TrackerBuilder;
+use crate::tracking::state::{ContinuesUsed, CurrentBombs, CurrentLives, NotTracked, TotalMisses};
+use crate::tracking::{
+    DriveTracker, GameTracker, IntoGameTracker, TrackRun, TrackSpellPractice, TrackStagePractice,
+    TrackableGame, TrackerState, TrackingType, UpdateResult, UpdateStatus,
+};
+use crate::types::SpellCard;
+use crate::Touhou15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Touhou15Event {
+    /// A new chapter began.
+    ChapterStarted { chapter: u32 },
+    /// A boss declared a spell card.
+    SpellDeclared(SpellCard<Touhou15>),
+}
+
+impl std::fmt::Display for Touhou15Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ChapterStarted { chapter } => write!(f, "Chapter {}", chapter),
+            Self::SpellDeclared(spell) => write!(f, "Spell: {}", spell),
+        }
+    }
+}
+
+impl TrackableGame for Touhou15 {
+    type State = RunState;
+    type Event = Touhou15Event;
+}
+
+/// Starts tracking a game of the appropriate type, using a pre-configured [`TrackerBuilder`].
+///
+/// Legacy mode and Pointdevice mode games track misses differently, and thus use differently-typed
+/// tracker states; this macro lets us share the setup logic between the two.
+macro_rules! start_tracker {
+    ($builder:expr, $state:expr, $location:expr) => {{
+        let state: RunState = $state;
+        let player = state.player();
+
+        match state.practice() {
+            PracticeType::None => $builder.start_run(
+                player.shot(),
+                state.difficulty(),
+                state,
+                Duration::from_millis(750),
+            ),
+            PracticeType::Stage => $builder.start_stage_practice(
+                player.shot(),
+                state.difficulty(),
+                state.stage().stage(),
+                state,
+                Duration::from_millis(750),
+            ),
+            PracticeType::Spell => $builder.start_spell_practice(
+                player.shot(),
+                state.difficulty(),
+                $location,
+                state,
+                Duration::from_millis(750),
+            ),
+        }
+    }};
+}
+
+macro_rules! finish_tracker {
+    ($tracker:expr, $cleared:expr, $state:expr) => {
+        match $tracker.tracking_type() {
+            TrackingType::StagePractice => $tracker.finish_stage_practice($state),
+            TrackingType::SpellPractice => $tracker.finish_spell_practice($state),
+            TrackingType::FullRun => {
+                if $cleared {
+                    $tracker.run_cleared($state)
+                } else {
+                    $tracker.run_exited($state)
+                }
+            }
+        }
+    };
+}
+
+#[derive(Debug)]
+enum RunTracker<T> {
+    Legacy(TrackerState<Touhou15, T, CurrentLives, CurrentBombs, ContinuesUsed, NotTracked>),
+    Pointdevice(TrackerState<Touhou15, T, TotalMisses, CurrentBombs, ContinuesUsed, NotTracked>),
+}
+
+#[derive(Debug)]
+pub struct ActiveRun<T> {
+    tracker: RunTracker<T>,
+    prev_state: RunState,
+}
+
+impl<T> ActiveRun<T>
+where
+    T: TrackRun<Touhou15> + TrackStagePractice<Touhou15> + TrackSpellPractice<Touhou15>,
+{
+    fn new(state: RunState) -> Option<Self> {
+        let player = state.player();
+
+        // Spell practice trackers need to know which spell is being practiced up front,
+        // so wait until the spell card actually begins before starting to track.
+        let location = match state.practice() {
+            PracticeType::Spell => Some(
+                state
+                    .stage()
+                    .active_spell()
+                    .and_then(|spell| Location::from_spell(spell.spell()))?,
+            ),
+            PracticeType::None | PracticeType::Stage => None,
+        };
+
+        let tracker = if let Some(pointdevice) = player.as_pointdevice() {
+            let builder = TrackerBuilder::new()
+                .track_total_misses(&pointdevice)
+                .track_bomb_stock(&player)
+                .track_continues(&player);
+
+            RunTracker::Pointdevice(start_tracker!(builder, state, location?))
+        } else {
+            let builder = TrackerBuilder::new()
+                .track_life_stock(&player)
+                .track_bomb_stock(&player)
+                .track_continues(&player);
+
+            RunTracker::Legacy(start_tracker!(builder, state, location?))
+        };
+
+        Some(Self {
+            tracker,
+            prev_state: state,
+        })
+    }
+
+    fn game_events(&self, state: &RunState) -> Vec<Touhou15Event> {
+        let stage = state.stage();
+        let prev_stage = self.prev_state.stage();
+        let mut events = Vec::new();
+
+        if stage.chapter() != 0
+            && (stage.stage() != prev_stage.stage() || stage.chapter() != prev_stage.chapter())
+        {
+            events.push(Touhou15Event::ChapterStarted {
+                chapter: stage.chapter(),
+            });
+        }
+
+        if let Some(spell) = stage.active_spell() {
+            let prev_spell = prev_stage.active_spell().map(|prev| prev.spell());
+            if prev_spell != Some(spell.spell()) {
+                events.push(Touhou15Event::SpellDeclared(spell.spell()));
+            }
+        }
+
+        events
+    }
+
+    fn update_state(&mut self, state: RunState) {
+        let player = state.player();
+        let events = self.game_events(&state);
+
+        match &mut self.tracker {
+            RunTracker::Legacy(tracker) => {
+                let mut update = tracker.begin_update_with_location(state, &state);
+                for event in events {
+                    update.push_game_specific_event(event);
+                }
+
+                update
+                    .update_life_stock(&player)
+                    .update_bomb_stock(&player)
+                    .update_continues_used(&player)
+                    .finish()
+            }
+            RunTracker::Pointdevice(tracker) => {
+                let mut update = tracker.begin_update_with_location(state, &state);
+                for event in events {
+                    update.push_game_specific_event(event);
+                }
+
+                // the game mode can't change partway through a run
+                update
+                    .update_total_misses(&PointdevicePlayer::new(player))
+                    .update_bomb_stock(&player)
+                    .update_continues_used(&player)
+                    .finish()
+            }
+        }
+
+        self.prev_state = state;
+    }
+
+    fn finish(mut self, cleared: bool, end_state: Option<RunState>) -> T::Output {
+        if let Some(end_state) = end_state {
+            self.update_state(end_state);
+        }
+
+        match self.tracker {
+            RunTracker::Legacy(tracker) => finish_tracker!(tracker, cleared, self.prev_state),
+            RunTracker::Pointdevice(tracker) => finish_tracker!(tracker, cleared, self.prev_state),
+        }
+    }
+}
+
+impl<T> DriveTracker<Touhou15, T> for ActiveRun<T>
+where
+    T: TrackRun<Touhou15> + TrackStagePractice<Touhou15> + TrackSpellPractice<Touhou15>,
+{
+    type Memory = GameMemory;
+
+    fn game_is_active(access: &MemoryAccess) -> Result<bool, MemoryReadError<Touhou15>> {
+        GameState::game_is_active(access)
+    }
+
+    fn init(access: &MemoryAccess) -> Result<Option<Self>, MemoryReadError<Touhou15>> {
+        GameState::new(access).map(|state| {
+            if let GameState::InGame(run) = state {
+                Self::new(run)
+            } else {
+                None
+            }
+        })
+    }
+
+    fn update(mut self, access: &MemoryAccess) -> UpdateResult<Touhou15, T, Self> {
+        let state = match GameState::new(access) {
+            Ok(state) => state,
+            Err(e) => return Err((self, e)),
+        };
+
+        match state {
+            GameState::InGame(run) => {
+                self.update_state(run);
+                Ok(UpdateStatus::Continuing(self))
+            }
+            GameState::Ending(run) => Ok(UpdateStatus::Finished(self.finish(true, Some(run)))),
+            _ => Ok(UpdateStatus::Finished(self.finish(false, None))),
+        }
+    }
+
+    fn terminate(self) -> T::Output {
+        self.finish(false, None)
+    }
+
+    fn tracker_mut(&mut self) -> &mut T {
+        match &mut self.tracker {
+            RunTracker::Legacy(tracker) => tracker.tracker_mut(),
+            RunTracker::Pointdevice(tracker) => tracker.tracker_mut(),
+        }
+    }
+}
+
+impl<T> GameTracker<Touhou15, T, ActiveRun<T>>
+where
+    T: TrackRun<Touhou15> + TrackStagePractice<Touhou15> + TrackSpellPractice<Touhou15>,
+{
+    pub fn new_th15(memory: GameMemory) -> Self {
+        Self::new(memory)
+    }
+}
+
+impl<T> IntoGameTracker<Touhou15, T> for GameMemory
+where
+    T: TrackRun<Touhou15> + TrackStagePractice<Touhou15> + TrackSpellPractice<Touhou15>,
+    ActiveRun<T>: DriveTracker<Touhou15, T, Memory = GameMemory>,
+{
+    type Driver = ActiveRun<T>;
+
+    fn track_games(self) -> GameTracker<Touhou15, T, ActiveRun<T>> {
+        GameTracker::new(self)
+    }
+}
+
+#[cfg(all(test, feature = "replay"))]
+mod tests {
+    use serde::Serialize;
+
+    use super::*;
+    use crate::memory::replay::MemoryFrame;
+    use crate::memory::ResolveLocation;
+    use crate::th15::{Difficulty, ShotType, SpellId};
+    use crate::tracking::recorder::RecordKind;
+    use crate::tracking::{Event, RunRecorder};
+
+    fn set<T: Serialize>(frame: &mut MemoryFrame, field: &str, value: T) {
+        frame.insert(field, Ok::<T, &str>(value));
+    }
+
+    /// A frame from a Legacy mode Reimu game on Normal, partway through stage 1.
+    fn in_game(chapter: u32, lives: u32) -> MemoryFrame {
+        let mut frame = MemoryFrame::new();
+        set(&mut frame, "game_type", 4u32);
+        set(&mut frame, "total_retries", 0u32);
+        set(&mut frame, "chapter_retries", 0u32);
+        set(&mut frame, "score", 1_000_000u32);
+        set(&mut frame, "continues_used", 0u32);
+        set(&mut frame, "lives", lives);
+        set(&mut frame, "life_fragments", 0u32);
+        set(&mut frame, "bombs", 2u32);
+        set(&mut frame, "bomb_fragments", 0u32);
+        set(&mut frame, "power", 100u32);
+        set(&mut frame, "character", 0u32);
+        set(&mut frame, "difficulty", 1u32);
+        set(&mut frame, "stage", 1u32);
+        set(&mut frame, "chapter", chapter);
+        set(&mut frame, "chapter_frames", 0u32);
+        set(&mut frame, "practice_flags", 0u32);
+        set(&mut frame, "active_spell", 0u32);
+        set(&mut frame, "active_spell_status", 0u32);
+        set(&mut frame, "active_spell_bonus", 0u32);
+        set(&mut frame, "boss_lifebars", 0u32);
+        frame
+    }
+
+    fn with_field(mut frame: MemoryFrame, field: &str, value: u32) -> MemoryFrame {
+        set(&mut frame, field, value);
+        frame
+    }
+
+    fn title_screen() -> MemoryFrame {
+        with_field(in_game(0, 2), "game_type", 1)
+    }
+
+    fn with_spell(frame: MemoryFrame, raw_id: u32) -> MemoryFrame {
+        let frame = with_field(frame, "active_spell", raw_id);
+        let frame = with_field(frame, "active_spell_status", 1);
+        with_field(frame, "boss_lifebars", 1)
+    }
+
+    #[test]
+    fn replay_resolves_locations() {
+        let frames = vec![
+            title_screen(),
+            in_game(0, 2),
+            in_game(1, 2),
+            in_game(2, 2),
+            with_spell(in_game(3, 2), 0),
+            with_spell(in_game(5, 2), 6),
+            with_field(in_game(2, 2), "stage", 0),
+            with_field(in_game(2, 2), "game_type", 7),
+        ];
+
+        let mut memory = GameMemory::from_frames(frames);
+        let mut locations = Vec::new();
+
+        while memory.advance() {
+            match GameState::new(memory.access().unwrap()) {
+                Ok(GameState::InGame(run)) | Ok(GameState::Ending(run)) => {
+                    locations.push(run.resolve_location())
+                }
+                Ok(_) => {}
+                Err(_) => locations.push(None),
+            }
+        }
+
+        let names: Vec<_> = locations
+            .iter()
+            .map(|location| {
+                location.as_ref().map(|location| {
+                    location
+                        .spell()
+                        .map_or(location.name(), |spell| spell.name())
+                })
+            })
+            .collect();
+        assert_eq!(
+            names,
+            [
+                Some("Start"),
+                Some("Chapter 1"),
+                Some("Chapter 2"),
+                Some("Assassin's Bullet \"Speed Strike\""),
+                Some("Gun Sign \"Lunatic Gun\""),
+                None,
+                Some("Chapter 2"),
+            ]
+        );
+
+        assert!(!locations[3].as_ref().unwrap().is_end());
+        assert!(locations[4].as_ref().unwrap().is_end());
+    }
+
+    fn game_events(run: &RunRecorder<Touhou15>) -> Vec<Touhou15Event> {
+        run.records()
+            .iter()
+            .filter_map(|record| match &record.kind {
+                RecordKind::Event(Event::GameSpecific(event)) => Some(*event),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn misses(run: &RunRecorder<Touhou15>) -> usize {
+        run.records()
+            .iter()
+            .filter(|record| matches!(record.kind, RecordKind::Event(Event::Miss)))
+            .count()
+    }
+
+    #[test]
+    fn replay_drives_tracker() {
+        let frames = vec![
+            title_screen(),
+            in_game(1, 2),
+            in_game(1, 2),
+            in_game(2, 1),
+            // a stage transition with no valid stage ID ends the run in progress
+            with_field(in_game(2, 1), "stage", 0),
+            in_game(1, 2),
+            in_game(1, 2),
+            in_game(2, 2),
+            with_spell(in_game(3, 2), 0),
+            with_field(in_game(3, 2), "game_type", 7),
+            title_screen(),
+        ];
+
+        let mut tracker: GameTracker<Touhou15, RunRecorder<Touhou15>, _> =
+            GameMemory::from_frames(frames)
+                .track_games()
+                .with_init_delay(Duration::ZERO);
+        let mut runs = Vec::new();
+        let mut errors = 0;
+
+        while tracker.is_running() {
+            match tracker.update() {
+                Ok(output) => runs.extend(output),
+                Err(_) => errors += 1,
+            }
+        }
+
+        assert_eq!(errors, 1);
+        assert_eq!(runs.len(), 2);
+
+        let interrupted = &runs[0];
+        assert_eq!(interrupted.tracking_type(), TrackingType::FullRun);
+        assert!(!interrupted.cleared());
+        assert_eq!(misses(interrupted), 1);
+        assert_eq!(
+            game_events(interrupted),
+            [Touhou15Event::ChapterStarted { chapter: 2 }]
+        );
+
+        let cleared = &runs[1];
+        assert_eq!(cleared.tracking_type(), TrackingType::FullRun);
+        assert_eq!(cleared.shot().unwrap(), ShotType::Reimu);
+        assert_eq!(cleared.difficulty().unwrap(), Difficulty::Normal);
+        assert!(cleared.cleared());
+        assert_eq!(misses(cleared), 0);
+        assert_eq!(
+            game_events(cleared),
+            [
+                Touhou15Event::ChapterStarted { chapter: 2 },
+                Touhou15Event::ChapterStarted { chapter: 3 },
+                Touhou15Event::SpellDeclared(SpellCard::new(SpellId::new(1).unwrap())),
+            ]
+        );
+    }
+
+    #[test]
+    fn pointdevice_counts_retries() {
+        let pointdevice = |retries: u32| {
+            let frame = with_field(in_game(1, 2), "practice_flags", 256);
+            with_field(frame, "total_retries", retries)
+        };
+        let frames = vec![
+            title_screen(),
+            pointdevice(0),
+            pointdevice(0),
+            pointdevice(1),
+            with_field(pointdevice(1), "lives", 1),
+            title_screen(),
+        ];
+
+        let mut tracker: GameTracker<Touhou15, RunRecorder<Touhou15>, _> =
+            GameMemory::from_frames(frames)
+                .track_games()
+                .with_init_delay(Duration::ZERO);
+        let mut runs = Vec::new();
+
+        while tracker.is_running() {
+            runs.extend(tracker.update().unwrap());
+        }
+
+        assert_eq!(runs.len(), 1);
+        assert_eq!(misses(&runs[0]), 1);
+    }
+}
@@ -0,0 +1,1172 @@
+use std::fmt::{Debug, Display};
+use std::io::{self, Cursor, ErrorKind, Read};
+
+use byteorder::{LittleEndian, ReadBytesExt};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use super::{Difficulty, ShotType, SpellId, Stage, Touhou15};
+use crate::score::*;
+use crate::types::{
+    AllIterable, Difficulty as DifficultyWrapper, ShotType as ShotWrapper, SpellCard,
+    Stage as StageWrapper, StageProgress,
+};
+
+const SIGNATURE: &[u8; 4] = b"TH51";
+const NUM_SPELL_CARDS: usize = 119;
+
+fn read_raw_buffer<const N: usize, R: Read>(mut src: R) -> io::Result<Box<[u8]>> {
+    let mut buf = vec![0u8; N];
+    src.read_exact(&mut buf[..])?;
+    Ok(buf.into())
+}
+
+fn skip_bytes<const N: usize, R: Read>(mut src: R) -> io::Result<()> {
+    let mut buf = [0u8; N];
+    src.read_exact(&mut buf[..])
+}
+
+macro_rules! return_none_on_eof {
+    ($x:expr) => {
+        match $x {
+            Ok(v) => v,
+            Err(e) => {
+                if e.kind() == std::io::ErrorKind::UnexpectedEof {
+                    return Ok(None);
+                } else {
+                    return Err(e.into());
+                }
+            }
+        }
+    };
+}
+
+fn try_into_or_io_error<T, U>(kind: ErrorKind) -> impl FnOnce(T) -> io::Result<U>
+where
+    T: TryInto<U>,
+    T::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    move |val| {
+        val.try_into()
+            .map_err(move |error| io::Error::new(kind, error))
+    }
+}
+
+/// The two game modes available in LoLK.
+///
+/// The game keeps separate records for each mode.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum PlayMode {
+    Pointdevice,
+    Legacy,
+}
+
+impl PlayMode {
+    /// The modes, in the order their records appear within the score file.
+    pub const ALL: [Self; 2] = [Self::Pointdevice, Self::Legacy];
+}
+
+impl Display for PlayMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Pointdevice => f.pad("Pointdevice"),
+            Self::Legacy => f.pad("Legacy"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FileHeader {
+    encoded_full_sz: usize,
+    encoded_body_sz: usize,
+    decoded_body_sz: usize,
+}
+
+impl FileHeader {
+    pub fn encoded_full_sz(&self) -> usize {
+        self.encoded_full_sz
+    }
+
+    pub fn encoded_body_sz(&self) -> usize {
+        self.encoded_body_sz
+    }
+
+    pub fn decoded_body_sz(&self) -> usize {
+        self.decoded_body_sz
+    }
+
+    pub fn read_from<R: ReadBytesExt>(src: &mut R) -> Result<Self, io::Error> {
+        let mut signature = [0u8; 4];
+        src.read_exact(&mut signature[..])?;
+
+        if signature != *SIGNATURE {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "invalid score file signature",
+            ));
+        }
+
+        let encoded_full_sz = src.read_u32::<LittleEndian>()? as usize;
+
+        src.read_u32::<LittleEndian>()?;
+        src.read_u32::<LittleEndian>()?;
+
+        let encoded_body_sz = src.read_u32::<LittleEndian>()? as usize;
+        let decoded_body_sz = src.read_u32::<LittleEndian>()? as usize;
+
+        Ok(Self {
+            encoded_full_sz,
+            encoded_body_sz,
+            decoded_body_sz,
+        })
+    }
+}
+
+/// An entry in one of the high score lists stored within the score file.
+#[derive(Debug, Clone)]
+pub struct HighScore {
+    score: u32,
+    progress: StageProgress<Touhou15>,
+    continues: u8,
+    name: [u8; 10],
+    timestamp: u32,
+    slow: f32,
+    retries: u32,
+}
+
+impl HighScore {
+    /// The score for this entry, as displayed in-game.
+    ///
+    /// LoLK stores scores divided by 10, since the last digit of a score is always the number of continues used.
+    pub fn score(&self) -> u64 {
+        (self.score as u64) * 10 + (self.continues as u64)
+    }
+
+    pub fn progress(&self) -> StageProgress<Touhou15> {
+        self.progress
+    }
+
+    pub fn continues(&self) -> u8 {
+        self.continues
+    }
+
+    pub fn name(&self) -> &[u8] {
+        &self.name[..]
+    }
+
+    /// The raw UNIX timestamp for when this score was achieved.
+    pub fn timestamp(&self) -> u32 {
+        self.timestamp
+    }
+
+    /// The date and time when this score was achieved.
+    pub fn date(&self) -> Option<OffsetDateTime> {
+        OffsetDateTime::from_unix_timestamp(self.timestamp as i64).ok()
+    }
+
+    pub fn slow(&self) -> f32 {
+        self.slow
+    }
+
+    /// The number of chapter retries used during the run (only meaningful in Pointdevice mode).
+    pub fn retries(&self) -> u32 {
+        self.retries
+    }
+
+    pub fn read_from<R: Read>(mut src: R) -> io::Result<Self> {
+        let score = src.read_u32::<LittleEndian>()?;
+        let progress = match src.read_u8()? {
+            0 => StageProgress::NotStarted,
+            value @ 1..=7 => (value - 1)
+                .try_into()
+                .map(|stage: Stage| StageProgress::LostAt(StageWrapper::new(stage)))
+                .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?,
+            8 => StageProgress::AllClear,
+            9 => StageProgress::StageCleared(StageWrapper::new(Stage::Extra)),
+            value => {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Invalid stage progress value {}", value),
+                ));
+            }
+        };
+
+        let continues = src.read_u8()?;
+        let mut name = [0u8; 10];
+        src.read_exact(&mut name[..])?;
+        let timestamp = src.read_u32::<LittleEndian>()?;
+        skip_bytes::<4, _>(&mut src)?;
+
+        Ok(Self {
+            score,
+            progress,
+            continues,
+            name,
+            timestamp,
+            slow: src.read_f32::<LittleEndian>()?,
+            retries: src.read_u32::<LittleEndian>()?,
+        })
+    }
+}
+
+/// A spell card entry stored within a single shot type's clear data for one game mode.
+#[derive(Debug, Clone)]
+pub struct SpellCardEntry {
+    card_id: SpellId,
+    difficulty: Difficulty,
+    card_name: Box<[u8]>, // 0x80 bytes, CP932
+    career: SpellCardCareer,
+}
+
+impl SpellCardEntry {
+    pub fn card_id(&self) -> SpellId {
+        self.card_id
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    pub fn card_name(&self) -> &[u8] {
+        &self.card_name[..]
+    }
+
+    pub fn career(&self) -> &SpellCardCareer {
+        &self.career
+    }
+
+    pub fn read_from<R: Read>(mut src: R) -> io::Result<Self> {
+        let card_name = read_raw_buffer::<0x80, _>(&mut src)?;
+        let captures = src.read_u32::<LittleEndian>()?;
+        let practice_captures = src.read_u32::<LittleEndian>()?;
+        let attempts = src.read_u32::<LittleEndian>()?;
+        let practice_attempts = src.read_u32::<LittleEndian>()?;
+
+        let card_id = src
+            .read_u32::<LittleEndian>()
+            .map(|x| x + 1)
+            .and_then(try_into_or_io_error(ErrorKind::InvalidData))?;
+
+        let difficulty = src
+            .read_u32::<LittleEndian>()
+            .and_then(try_into_or_io_error::<u32, u8>(ErrorKind::InvalidData))
+            .and_then(try_into_or_io_error(ErrorKind::InvalidData))?;
+
+        let practice_score = src.read_u32::<LittleEndian>()?;
+
+        Ok(Self {
+            card_id,
+            difficulty,
+            card_name,
+            career: SpellCardCareer {
+                attempts: (attempts, practice_attempts),
+                captures: (captures, practice_captures),
+                practice_score,
+            },
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct SpellCardCareer {
+    attempts: (u32, u32), // pairs of story/practice values
+    captures: (u32, u32),
+    practice_score: u32,
+}
+
+impl SpellCardCareer {
+    pub fn attempts(&self, practice: bool) -> u32 {
+        if practice {
+            self.attempts.1
+        } else {
+            self.attempts.0
+        }
+    }
+
+    pub fn captures(&self, practice: bool) -> u32 {
+        if practice {
+            self.captures.1
+        } else {
+            self.captures.0
+        }
+    }
+
+    /// The high score for this card in spell practice.
+    pub fn practice_score(&self) -> u64 {
+        (self.practice_score as u64) * 10
+    }
+}
+
+/// Spell card history for a single card in one game mode, collected across all shot types.
+#[derive(Debug, Clone)]
+pub struct SpellCardData {
+    card_id: SpellId,
+    mode: PlayMode,
+    difficulty: Difficulty,
+    card_name: Box<[u8]>,
+    career_stats: Vec<SpellCardCareer>,
+}
+
+impl SpellCardData {
+    fn new(mode: PlayMode, entry: &SpellCardEntry) -> Self {
+        Self {
+            card_id: entry.card_id,
+            mode,
+            difficulty: entry.difficulty,
+            card_name: entry.card_name.clone(),
+            career_stats: vec![SpellCardCareer::default(); Touhou15::SHOT_TYPES.len()],
+        }
+    }
+
+    pub fn card_id(&self) -> SpellId {
+        self.card_id
+    }
+
+    /// The game mode these records were set in.
+    pub fn mode(&self) -> PlayMode {
+        self.mode
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    pub fn card_name(&self) -> &[u8] {
+        &self.card_name[..]
+    }
+
+    pub fn shot_stats(&self, shot: &ShotType) -> &SpellCardCareer {
+        let idx: usize = shot.into();
+        &self.career_stats[idx]
+    }
+
+    pub fn iter_shot_stats(&self) -> impl Iterator<Item = (ShotType, &SpellCardCareer)> + '_ {
+        ShotType::iter_all().zip(self.career_stats.iter())
+    }
+}
+
+impl SpellCardRecord<Touhou15> for SpellCardData {
+    fn card(&self) -> SpellCard<Touhou15> {
+        SpellCard::new(self.card_id)
+    }
+
+    fn shot_types(&self) -> &[ShotWrapper<Touhou15>] {
+        &Touhou15::SHOT_TYPES[..]
+    }
+
+    fn attempts(&self, shot: &ShotWrapper<Touhou15>) -> u32 {
+        self.shot_stats(&shot.unwrap()).attempts(false)
+    }
+
+    fn captures(&self, shot: &ShotWrapper<Touhou15>) -> u32 {
+        self.shot_stats(&shot.unwrap()).captures(false)
+    }
+}
+
+impl SpellPracticeRecord<Touhou15> for SpellCardData {
+    fn practice_attempts(&self, shot: &ShotWrapper<Touhou15>) -> u32 {
+        self.shot_stats(&shot.unwrap()).attempts(true)
+    }
+
+    fn practice_captures(&self, shot: &ShotWrapper<Touhou15>) -> u32 {
+        self.shot_stats(&shot.unwrap()).captures(true)
+    }
+
+    /// LoLK doesn't keep track of spell card bonuses, so this always returns 0.
+    ///
+    /// See [`SpellCardCareer::practice_score`] for spell practice high scores.
+    fn practice_max_bonus(&self, _shot: &ShotWrapper<Touhou15>) -> u32 {
+        0
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PracticeScore {
+    shot_type: ShotType,
+    mode: PlayMode,
+    stage: Stage,
+    difficulty: Difficulty,
+    high_score: u32,
+    cleared: bool,
+    unlocked: bool,
+}
+
+impl PracticeScore {
+    /// The game mode this record was set in.
+    pub fn mode(&self) -> PlayMode {
+        self.mode
+    }
+
+    /// Whether this stage has been cleared in practice with this shot type and difficulty.
+    pub fn cleared(&self) -> bool {
+        self.cleared
+    }
+
+    /// Whether this stage has been unlocked for practice with this shot type and difficulty.
+    pub fn unlocked(&self) -> bool {
+        self.unlocked
+    }
+
+    fn read_from<R: Read>(
+        mut src: R,
+        shot_type: ShotType,
+        mode: PlayMode,
+        stage: Stage,
+        difficulty: Difficulty,
+    ) -> io::Result<Self> {
+        let high_score = src.read_u32::<LittleEndian>()?;
+        let cleared = src.read_u8()? != 0;
+        let unlocked = src.read_u8()? != 0;
+        skip_bytes::<2, _>(&mut src)?;
+
+        Ok(Self {
+            shot_type,
+            mode,
+            stage,
+            difficulty,
+            high_score,
+            cleared,
+            unlocked,
+        })
+    }
+}
+
+impl PracticeRecord<Touhou15> for PracticeScore {
+    /// The practice high score, with the last digit (always 0) included.
+    fn high_score(&self) -> u32 {
+        self.high_score.saturating_mul(10)
+    }
+
+    fn shot_type(&self) -> ShotWrapper<Touhou15> {
+        ShotWrapper::new(self.shot_type)
+    }
+
+    fn difficulty(&self) -> DifficultyWrapper<Touhou15> {
+        DifficultyWrapper::new(self.difficulty)
+    }
+
+    fn stage(&self) -> StageWrapper<Touhou15> {
+        StageWrapper::new(self.stage)
+    }
+}
+
+/// Play data for a single game mode, stored within a [`ClearData`] segment.
+///
+/// Values indexed by difficulty also include an overall total, accessed by passing `None` as the difficulty.
+#[derive(Debug, Clone)]
+pub struct ModeClearData {
+    rankings: Vec<Vec<HighScore>>,
+    total_play_count: u32,
+    play_time: u32,
+    clear_counts: [u32; 6],
+    clear_flags: [u32; 6],
+    cards: Vec<SpellCardEntry>,
+}
+
+impl ModeClearData {
+    fn difficulty_index(difficulty: Option<Difficulty>) -> usize {
+        difficulty.map(usize::from).unwrap_or(5)
+    }
+
+    pub fn rankings(&self, difficulty: Option<Difficulty>) -> &[HighScore] {
+        &self.rankings[Self::difficulty_index(difficulty)][..]
+    }
+
+    pub fn total_play_count(&self) -> u32 {
+        self.total_play_count
+    }
+
+    /// The total play time, in frames.
+    pub fn play_time(&self) -> u32 {
+        self.play_time
+    }
+
+    pub fn clear_count(&self, difficulty: Option<Difficulty>) -> u32 {
+        self.clear_counts[Self::difficulty_index(difficulty)]
+    }
+
+    pub fn clear_flag(&self, difficulty: Option<Difficulty>) -> u32 {
+        self.clear_flags[Self::difficulty_index(difficulty)]
+    }
+
+    pub fn cards(&self) -> &[SpellCardEntry] {
+        &self.cards[..]
+    }
+
+    pub fn read_from<R: Read>(mut src: R) -> io::Result<Self> {
+        let mut rankings = Vec::with_capacity(6);
+        for _ in 0..6 {
+            let mut scores = Vec::with_capacity(10);
+            for _ in 0..10 {
+                scores.push(HighScore::read_from(&mut src)?);
+            }
+            rankings.push(scores);
+        }
+
+        skip_bytes::<0x140, _>(&mut src)?;
+
+        let mut cards = Vec::with_capacity(NUM_SPELL_CARDS);
+        for _ in 0..NUM_SPELL_CARDS {
+            cards.push(SpellCardEntry::read_from(&mut src)?);
+        }
+
+        let total_play_count = src.read_u32::<LittleEndian>()?;
+        let play_time = src.read_u32::<LittleEndian>()?;
+        skip_bytes::<4, _>(&mut src)?;
+
+        let mut clear_counts = [0u32; 6];
+        for elem in clear_counts.iter_mut() {
+            *elem = src.read_u32::<LittleEndian>()?;
+        }
+        skip_bytes::<4, _>(&mut src)?;
+
+        let mut clear_flags = [0u32; 6];
+        for elem in clear_flags.iter_mut() {
+            *elem = src.read_u32::<LittleEndian>()?;
+        }
+        skip_bytes::<4, _>(&mut src)?;
+
+        Ok(Self {
+            rankings,
+            total_play_count,
+            play_time,
+            clear_counts,
+            clear_flags,
+            cards,
+        })
+    }
+}
+
+/// Play data stored for a single shot type, or for all shot types combined.
+#[derive(Debug, Clone)]
+pub struct ClearData {
+    shot_type: Option<ShotType>,
+    pointdevice: ModeClearData,
+    legacy: ModeClearData,
+    practices: Vec<PracticeScore>,
+}
+
+impl ClearData {
+    /// The shot type this data applies to, or `None` for the combined totals across all shot types.
+    pub fn shot_type(&self) -> Option<ShotType> {
+        self.shot_type
+    }
+
+    pub fn mode_data(&self, mode: PlayMode) -> &ModeClearData {
+        match mode {
+            PlayMode::Pointdevice => &self.pointdevice,
+            PlayMode::Legacy => &self.legacy,
+        }
+    }
+
+    pub fn practices(&self) -> &[PracticeScore] {
+        &self.practices[..]
+    }
+
+    pub fn read_from<R: Read>(mut src: R) -> io::Result<Self> {
+        static PRACTICE_STAGES: [Stage; 6] = [
+            Stage::One,
+            Stage::Two,
+            Stage::Three,
+            Stage::Four,
+            Stage::Five,
+            Stage::Six,
+        ];
+
+        static PRACTICE_DIFFICULTIES: [Difficulty; 4] = [
+            Difficulty::Easy,
+            Difficulty::Normal,
+            Difficulty::Hard,
+            Difficulty::Lunatic,
+        ];
+
+        let shot_type = match src.read_u32::<LittleEndian>()? {
+            4 => None,
+            value => Some(
+                u8::try_from(value)
+                    .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+                    .and_then(try_into_or_io_error(ErrorKind::InvalidData))?,
+            ),
+        };
+
+        let pointdevice = ModeClearData::read_from(&mut src)?;
+        let legacy = ModeClearData::read_from(&mut src)?;
+
+        let mut practices = Vec::with_capacity(48);
+        for mode in PlayMode::ALL {
+            for difficulty in PRACTICE_DIFFICULTIES {
+                for stage in PRACTICE_STAGES {
+                    let practice = match shot_type {
+                        Some(shot_type) => {
+                            PracticeScore::read_from(&mut src, shot_type, mode, stage, difficulty)?
+                        }
+                        None => {
+                            skip_bytes::<8, _>(&mut src)?;
+                            continue;
+                        }
+                    };
+
+                    practices.push(practice);
+                }
+            }
+        }
+
+        Ok(Self {
+            shot_type,
+            pointdevice,
+            legacy,
+            practices,
+        })
+    }
+}
+
+#[derive(Clone)]
+pub enum Segment {
+    Clear(Box<ClearData>),
+    Unknown([u8; 2], u16, usize, Box<[u8]>),
+}
+
+impl Segment {
+    pub fn signature(&self) -> &[u8; 2] {
+        match self {
+            Self::Clear(_) => b"CR",
+            Self::Unknown(sig, _, _, _) => sig,
+        }
+    }
+
+    fn signature_string(&self) -> String {
+        let v: Vec<u8> = self
+            .signature()
+            .iter()
+            .copied()
+            .flat_map(std::ascii::escape_default)
+            .collect();
+        String::from_utf8(v).unwrap()
+    }
+
+    pub fn read_from<R: Read>(mut src: R) -> io::Result<Option<Self>> {
+        let mut signature = [0; 2];
+        return_none_on_eof!(src.read_exact(&mut signature));
+        let version = return_none_on_eof!(src.read_u16::<LittleEndian>());
+        return_none_on_eof!(src.read_u32::<LittleEndian>()); // checksum
+        let size = return_none_on_eof!(src.read_u32::<LittleEndian>()) as usize;
+
+        if size <= 12 {
+            return Self::read_from(src);
+        }
+
+        let mut data = vec![0u8; size - 12];
+        return_none_on_eof!(src.read_exact(&mut data));
+
+        let mut reader = Cursor::new(data);
+        match &signature {
+            b"CR" => ClearData::read_from(&mut reader).map(|data| Self::Clear(Box::new(data))),
+            _ => Ok(Self::Unknown(
+                signature,
+                version,
+                size,
+                reader.into_inner().into(),
+            )),
+        }
+        .map(Some)
+    }
+}
+
+impl Debug for Segment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Clear(d) => f
+                .debug_struct("Segment::Clear")
+                .field("signature", &self.signature_string())
+                .field("data", d)
+                .finish(),
+            Self::Unknown(_, version, size, d) => f
+                .debug_struct("Segment::Unknown")
+                .field("signature", &self.signature_string())
+                .field("version", version)
+                .field("size", size)
+                .field("data", &format!("[{} bytes]", d.len()))
+                .finish(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ScoreReader<R> {
+    header: FileHeader,
+    src: StreamDecompressor<ThCrypt<R>>,
+}
+
+impl<R: Read> ScoreReader<R> {
+    pub fn new(mut src: R) -> Result<Self, io::Error> {
+        let header = FileHeader::read_from(&mut src)?;
+        let decryptor = ThCrypt::new(src, 0xAC, 0x35, 0x10, Some(header.encoded_body_sz));
+        let src = StreamDecompressor::new(decryptor);
+        Ok(Self { header, src })
+    }
+
+    pub fn header(&self) -> &FileHeader {
+        &self.header
+    }
+}
+
+impl<R: Read> Iterator for ScoreReader<R> {
+    type Item = Result<Segment, io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Segment::read_from(&mut self.src).transpose()
+    }
+}
+
+/// The spell card and stage practice records for a single game mode within a [`ScoreFile`].
+#[derive(Debug, Clone)]
+pub struct ModeRecords {
+    mode: PlayMode,
+    cards: Vec<SpellCardData>,
+    practices: Vec<PracticeScore>,
+}
+
+impl ModeRecords {
+    fn new(mode: PlayMode) -> Self {
+        Self {
+            mode,
+            cards: Vec::with_capacity(NUM_SPELL_CARDS),
+            practices: Vec::new(),
+        }
+    }
+
+    /// The game mode these records were set in.
+    pub fn mode(&self) -> PlayMode {
+        self.mode
+    }
+
+    fn add_cards(&mut self, shot_type: ShotType, entries: &[SpellCardEntry]) {
+        let shot_idx: usize = shot_type.into();
+        for entry in entries {
+            let idx = match self
+                .cards
+                .iter()
+                .position(|card| card.card_id == entry.card_id)
+            {
+                Some(idx) => idx,
+                None => {
+                    self.cards.push(SpellCardData::new(self.mode, entry));
+                    self.cards.len() - 1
+                }
+            };
+
+            self.cards[idx].career_stats[shot_idx] = entry.career;
+        }
+    }
+}
+
+impl crate::score::ScoreFile<Touhou15> for ModeRecords {
+    type SpellCardRecord = SpellCardData;
+    type PracticeRecord = PracticeScore;
+
+    fn spell_cards(&self) -> &[SpellCardData] {
+        &self.cards[..]
+    }
+
+    fn practice_records(&self) -> &[PracticeScore] {
+        &self.practices[..]
+    }
+}
+
+/// A loaded LoLK score file.
+///
+/// Spell card and practice records are kept separately for Pointdevice and Legacy modes,
+/// and records for the same card or stage in each mode can't be told apart by the generic score file traits.
+/// Use [`mode`](ScoreFile::mode) to get the records for a single mode; the [`ScoreFile`](crate::score::ScoreFile)
+/// trait implementation for this type returns the Legacy mode records.
+#[derive(Debug, Clone)]
+pub struct ScoreFile {
+    pointdevice: ModeRecords,
+    legacy: ModeRecords,
+}
+
+impl ScoreFile {
+    pub fn new<R: Read>(src: R) -> Result<Self, io::Error> {
+        let mut ret = Self {
+            pointdevice: ModeRecords::new(PlayMode::Pointdevice),
+            legacy: ModeRecords::new(PlayMode::Legacy),
+        };
+
+        for segment in ScoreReader::new(src)? {
+            let data = match segment {
+                Ok(Segment::Clear(data)) => data,
+                Ok(_) => continue,
+                Err(e) => return Err(e),
+            };
+
+            // The combined clear data just duplicates the per-shot data.
+            let shot_type = match data.shot_type {
+                Some(shot_type) => shot_type,
+                None => continue,
+            };
+
+            for mode in PlayMode::ALL {
+                ret.mode_mut(mode)
+                    .add_cards(shot_type, data.mode_data(mode).cards());
+            }
+
+            for practice in data.practices {
+                ret.mode_mut(practice.mode).practices.push(practice);
+            }
+        }
+
+        Ok(ret)
+    }
+
+    /// Gets the records for a single game mode.
+    pub fn mode(&self, mode: PlayMode) -> &ModeRecords {
+        match mode {
+            PlayMode::Pointdevice => &self.pointdevice,
+            PlayMode::Legacy => &self.legacy,
+        }
+    }
+
+    fn mode_mut(&mut self, mode: PlayMode) -> &mut ModeRecords {
+        match mode {
+            PlayMode::Pointdevice => &mut self.pointdevice,
+            PlayMode::Legacy => &mut self.legacy,
+        }
+    }
+}
+
+/// Returns the Legacy mode records; see [`ScoreFile::mode`] for Pointdevice mode.
+impl crate::score::ScoreFile<Touhou15> for ScoreFile {
+    type SpellCardRecord = SpellCardData;
+    type PracticeRecord = PracticeScore;
+
+    fn spell_cards(&self) -> &[SpellCardData] {
+        &self.legacy.cards[..]
+    }
+
+    fn practice_records(&self) -> &[PracticeScore] {
+        &self.legacy.practices[..]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use byteorder::{ByteOrder, WriteBytesExt};
+
+    use super::*;
+    use crate::score::diff::{compare, ScoreEvent};
+    use crate::score::ScoreFile as _;
+
+    /// Spell card attempt counts for a single (shot type, mode) pair, keyed by zero-based card index.
+    type Attempts<'a> = &'a [(u32, u32)];
+
+    fn write_mode_data(dst: &mut Vec<u8>, attempts: Attempts) {
+        for rank in 0..60u32 {
+            dst.write_u32::<LittleEndian>(rank * 1000).unwrap(); // score
+            dst.write_u8(8).unwrap(); // progress: all clear
+            dst.write_u8(0).unwrap(); // continues
+            dst.write_all(b"TESTNAME\0\0").unwrap();
+            dst.write_u32::<LittleEndian>(1_500_000_000).unwrap(); // timestamp
+            dst.write_u32::<LittleEndian>(0).unwrap();
+            dst.write_f32::<LittleEndian>(0.0).unwrap(); // slow
+            dst.write_u32::<LittleEndian>(0).unwrap(); // retries
+        }
+
+        dst.extend_from_slice(&[0; 0x140]);
+
+        for idx in 0..NUM_SPELL_CARDS as u32 {
+            let card_attempts = attempts
+                .iter()
+                .find(|(card, _)| *card == idx)
+                .map(|(_, count)| *count)
+                .unwrap_or(0);
+
+            dst.extend_from_slice(&[0; 0x80]); // card name
+            dst.write_u32::<LittleEndian>(card_attempts / 2).unwrap(); // captures
+            dst.write_u32::<LittleEndian>(0).unwrap(); // practice captures
+            dst.write_u32::<LittleEndian>(card_attempts).unwrap(); // attempts
+            dst.write_u32::<LittleEndian>(0).unwrap(); // practice attempts
+            dst.write_u32::<LittleEndian>(idx).unwrap(); // card ID
+            dst.write_u32::<LittleEndian>(1).unwrap(); // difficulty
+            dst.write_u32::<LittleEndian>(0).unwrap(); // practice score
+        }
+
+        dst.write_u32::<LittleEndian>(10).unwrap(); // total play count
+        dst.write_u32::<LittleEndian>(600_000).unwrap(); // play time
+        dst.extend_from_slice(&[0; 4]);
+        for _ in 0..2 {
+            for count in [1, 2, 3, 4, 5, 15] {
+                dst.write_u32::<LittleEndian>(count).unwrap();
+            }
+            dst.extend_from_slice(&[0; 4]);
+        }
+    }
+
+    /// Builds a clear data segment, with every practice score set to `practice_score`.
+    fn clear_segment(
+        shot_type: u32,
+        pointdevice: Attempts,
+        legacy: Attempts,
+        practice_score: [u32; 2],
+    ) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.write_u32::<LittleEndian>(shot_type).unwrap();
+        write_mode_data(&mut data, pointdevice);
+        write_mode_data(&mut data, legacy);
+
+        for score in practice_score {
+            for _ in 0..24 {
+                data.write_u32::<LittleEndian>(score).unwrap();
+                data.write_u8(1).unwrap(); // cleared
+                data.write_u8(1).unwrap(); // unlocked
+                data.extend_from_slice(&[0; 2]);
+            }
+        }
+
+        let mut segment = b"CR".to_vec();
+        segment.write_u16::<LittleEndian>(1).unwrap(); // version
+        segment.write_u32::<LittleEndian>(0).unwrap(); // checksum
+        segment
+            .write_u32::<LittleEndian>((data.len() + 12) as u32)
+            .unwrap();
+        segment.extend(data);
+        segment
+    }
+
+    fn encode_file(segments: &[Vec<u8>]) -> Vec<u8> {
+        let body = segments.concat();
+        let mut compressor = StreamCompressor::new(Vec::new());
+        compressor.write_all(&body).unwrap();
+        let (compressed, _) = compressor.finish().unwrap();
+
+        let mut encryptor = ThEncrypt::new(Vec::new(), 0xAC, 0x35, 0x10, None);
+        encryptor.write_all(&compressed).unwrap();
+        let encrypted = encryptor.finish().unwrap();
+
+        let mut file = SIGNATURE.to_vec();
+        file.write_u32::<LittleEndian>((encrypted.len() + 24) as u32)
+            .unwrap();
+        file.write_u32::<LittleEndian>(0).unwrap();
+        file.write_u32::<LittleEndian>(0).unwrap();
+        file.write_u32::<LittleEndian>(encrypted.len() as u32)
+            .unwrap();
+        file.write_u32::<LittleEndian>(body.len() as u32).unwrap();
+        file.extend(encrypted);
+        file
+    }
+
+    fn load(pointdevice_attempts: u32) -> ScoreFile {
+        let file = encode_file(&[
+            clear_segment(0, &[(0, pointdevice_attempts)], &[(0, 3)], [99, 1234]),
+            clear_segment(1, &[], &[(5, 2)], [0, 0]),
+            clear_segment(4, &[(0, 100)], &[(0, 100)], [0, 0]),
+        ]);
+
+        ScoreFile::new(&file[..]).unwrap()
+    }
+
+    fn reimu_stage_one(records: &ModeRecords) -> &PracticeScore {
+        records
+            .practice_records()
+            .iter()
+            .find(|practice| {
+                practice.shot_type == ShotType::Reimu
+                    && practice.difficulty == Difficulty::Easy
+                    && practice.stage == Stage::One
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn modes_are_separate() {
+        let file = load(7);
+        let pointdevice = file.mode(PlayMode::Pointdevice);
+        let legacy = file.mode(PlayMode::Legacy);
+        let first_card: SpellCard<Touhou15> = SpellCard::new(SpellId::new(1).unwrap());
+        let reimu = ShotWrapper::new(ShotType::Reimu);
+        let marisa = ShotWrapper::new(ShotType::Marisa);
+
+        for records in [pointdevice, legacy] {
+            assert_eq!(records.spell_cards().len(), NUM_SPELL_CARDS);
+            assert_eq!(records.practice_records().len(), 48);
+            let mode = records.mode();
+            assert!(records.spell_cards().iter().all(|card| card.mode() == mode));
+            assert!(records.practice_records().iter().all(|p| p.mode() == mode));
+        }
+
+        assert_eq!(pointdevice.spell_cards()[0].card(), first_card);
+        assert_eq!(pointdevice.spell_cards()[0].attempts(&reimu), 7);
+        assert_eq!(pointdevice.spell_cards()[0].captures(&reimu), 3);
+        assert_eq!(legacy.spell_cards()[0].attempts(&reimu), 3);
+        assert_eq!(legacy.spell_cards()[0].attempts(&marisa), 0);
+        assert_eq!(legacy.spell_cards()[5].attempts(&marisa), 2);
+
+        assert_eq!(reimu_stage_one(pointdevice).high_score(), 990);
+        assert_eq!(reimu_stage_one(legacy).high_score(), 12340);
+
+        // the trait implementation for the whole file reports Legacy mode
+        assert_eq!(file.spell_cards()[0].attempts(&reimu), 3);
+        assert_eq!(file.practice_records().len(), 48);
+    }
+
+    #[test]
+    fn diff_per_mode() {
+        let prev = load(8);
+        let cur = load(9);
+
+        assert_eq!(
+            compare(
+                prev.mode(PlayMode::Pointdevice),
+                cur.mode(PlayMode::Pointdevice)
+            ),
+            [ScoreEvent::CardAttempted {
+                card: SpellCard::new(SpellId::new(1).unwrap()),
+                shot_type: ShotWrapper::new(ShotType::Reimu),
+                prev_attempts: 8,
+                attempts: 9,
+            }]
+        );
+        assert!(compare(prev.mode(PlayMode::Legacy), cur.mode(PlayMode::Legacy)).is_empty());
+        assert!(compare(&prev, &cur).is_empty());
+    }
+
+    #[test]
+    fn invalid_signature() {
+        let mut file = encode_file(&[clear_segment(0, &[], &[], [0, 0])]);
+        file[..4].copy_from_slice(b"TH10");
+
+        let err = ScoreFile::new(&file[..]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    /// A complete score file holding a single unrecognized `XX` segment, built by hand with thtk's encryption and
+    /// LZSS algorithms rather than this crate's encoders.
+    const HAND_BUILT_FILE: [u8; 51] = [
+        0x54, 0x48, 0x35, 0x31, 0x33, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+        0x00, 0x00, 0x00, 0x00, 0x1b, 0x00, 0x00, 0x00, 0x20, 0x00, 0x00, 0x00, //
+        0x12, 0xeb, 0x36, 0xdb, 0x81, 0xc1, 0xda, 0x49, 0x31, 0x99, 0xfe, 0xf3, //
+        0x3e, 0x56, 0xb2, 0x6b, 0xfc, 0x25, 0x76, 0xad, 0x9b, 0x09, 0x22, 0x0f, //
+        0xa4, 0x40, 0x00,
+    ];
+
+    /// Builds a decoded `CR` segment at its real size, with fields placed at their documented offsets.
+    fn hand_built_clear_data() -> Vec<u8> {
+        let mut data = vec![0u8; 0xA4A0];
+        data[0x00..0x02].copy_from_slice(b"CR");
+        LittleEndian::write_u16(&mut data[0x02..], 1); // version
+        LittleEndian::write_u32(&mut data[0x08..], 0xA4A0); // size
+        LittleEndian::write_u32(&mut data[0x0C..], 3); // shot type (Reisen)
+
+        // Pointdevice data starts at 0x10 and Legacy data at 0x5198, each with 10 rankings of 0x20 bytes per
+        // difficulty, 0x140 unknown bytes, then 119 cards of 0x9C bytes each, followed by play counts
+
+        // Pointdevice Lunatic ranking #1
+        LittleEndian::write_u32(&mut data[0x3D0..], 9_876_543);
+        data[0x3D4] = 2; // lost at stage 2
+        data[0x3D5] = 1; // continues
+        data[0x3D6..0x3E0].copy_from_slice(b"CLOWNPIECE");
+        LittleEndian::write_u32(&mut data[0x3E0..], 1_440_000_000);
+        LittleEndian::write_f32(&mut data[0x3E8..], 0.25);
+        LittleEndian::write_u32(&mut data[0x3EC..], 12); // retries
+
+        // the last Legacy card
+        data[0xA240..0xA249].copy_from_slice(b"Test Sign");
+        LittleEndian::write_u32(&mut data[0xA2C0..], 2); // captures
+        LittleEndian::write_u32(&mut data[0xA2C4..], 5); // practice captures
+        LittleEndian::write_u32(&mut data[0xA2C8..], 9); // attempts
+        LittleEndian::write_u32(&mut data[0xA2CC..], 20); // practice attempts
+        LittleEndian::write_u32(&mut data[0xA2D0..], 118); // card ID
+        LittleEndian::write_u32(&mut data[0xA2D4..], 4); // difficulty
+        LittleEndian::write_u32(&mut data[0xA2D8..], 31_415); // practice score
+
+        LittleEndian::write_u32(&mut data[0xA2DC..], 77); // Legacy total play count
+        LittleEndian::write_u32(&mut data[0xA2E0..], 360_000); // Legacy play time
+        LittleEndian::write_u32(&mut data[0xA2F4..], 6); // Legacy Lunatic clears
+
+        // Legacy Normal stage 4 practice; practices start at 0xA320, with 6 stages of 8 bytes per difficulty and mode
+        LittleEndian::write_u32(&mut data[0xA428..], 2_500);
+        data[0xA42C] = 1; // cleared
+        data[0xA42D] = 1; // unlocked
+
+        let checksum = data[0x08..].iter().map(|&b| b as u32).sum();
+        LittleEndian::write_u32(&mut data[0x04..], checksum);
+        data
+    }
+
+    #[test]
+    fn decode_hand_built_file() {
+        let mut reader = ScoreReader::new(&HAND_BUILT_FILE[..]).unwrap();
+        assert_eq!(reader.header().encoded_full_sz(), HAND_BUILT_FILE.len());
+        assert_eq!(reader.header().encoded_body_sz(), 27);
+        assert_eq!(reader.header().decoded_body_sz(), 32);
+
+        match reader.next().unwrap().unwrap() {
+            Segment::Unknown(signature, version, size, data) => {
+                assert_eq!(&signature, b"XX");
+                assert_eq!(version, 1);
+                assert_eq!(size, 32);
+                assert_eq!(&data[..], b"LoLKLoLKLoLKLoLK\x00\x01\x02\x03");
+            }
+            other => panic!("unexpected segment {:?}", other),
+        }
+
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn decode_hand_built_clear_data() {
+        let data = match Segment::read_from(&hand_built_clear_data()[..]).unwrap() {
+            Some(Segment::Clear(data)) => data,
+            other => panic!("unexpected segment {:?}", other),
+        };
+
+        assert_eq!(data.shot_type(), Some(ShotType::Reisen));
+
+        let pointdevice = data.mode_data(PlayMode::Pointdevice);
+        let best = &pointdevice.rankings(Some(Difficulty::Lunatic))[0];
+        assert_eq!(best.score(), 98_765_431);
+        assert_eq!(
+            best.progress(),
+            StageProgress::LostAt(StageWrapper::new(Stage::Two))
+        );
+        assert_eq!(best.name(), b"CLOWNPIECE");
+        assert_eq!(best.timestamp(), 1_440_000_000);
+        assert_eq!(best.slow(), 0.25);
+        assert_eq!(best.retries(), 12);
+        assert_eq!(pointdevice.total_play_count(), 0);
+
+        let legacy = data.mode_data(PlayMode::Legacy);
+        assert_eq!(
+            legacy.rankings(Some(Difficulty::Lunatic))[0].progress(),
+            StageProgress::NotStarted
+        );
+        assert_eq!(legacy.total_play_count(), 77);
+        assert_eq!(legacy.play_time(), 360_000);
+        assert_eq!(legacy.clear_count(Some(Difficulty::Lunatic)), 6);
+        assert_eq!(legacy.clear_count(None), 0);
+
+        let card = &legacy.cards()[118];
+        assert_eq!(&card.card_name()[..9], b"Test Sign");
+        assert_eq!(card.card_id(), SpellId::new(119).unwrap());
+        assert_eq!(card.difficulty(), Difficulty::Extra);
+        assert_eq!(card.career().captures(false), 2);
+        assert_eq!(card.career().captures(true), 5);
+        assert_eq!(card.career().attempts(false), 9);
+        assert_eq!(card.career().attempts(true), 20);
+        assert_eq!(card.career().practice_score(), 314_150);
+        assert_eq!(pointdevice.cards()[118].career().attempts(false), 0);
+
+        let unlocked: Vec<_> = data
+            .practices()
+            .iter()
+            .filter(|practice| practice.unlocked())
+            .collect();
+        assert_eq!(unlocked.len(), 1);
+        assert_eq!(unlocked[0].mode(), PlayMode::Legacy);
+        assert_eq!(unlocked[0].difficulty, Difficulty::Normal);
+        assert_eq!(unlocked[0].stage, Stage::Four);
+        assert!(unlocked[0].cleared());
+        assert_eq!(unlocked[0].high_score(), 25_000);
+    }
+}
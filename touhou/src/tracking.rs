@@ -32,10 +32,16 @@ pub mod driver;
 
 pub mod time;
 
-pub(crate) use driver::{DriveTracker, UpdateStatus};
+pub mod recorder;
+
+pub mod livesplit;
+
+pub(crate) use driver::{DriveTracker, UpdateResult, UpdateStatus};
 #[doc(inline)]
 pub use driver::{GameTracker, IntoGameTracker};
 #[doc(inline)]
+pub use recorder::RunRecorder;
+#[doc(inline)]
 pub use state::LocationResolveFilter;
 #[doc(inline)]
 pub use time::{EventTime, GameTimeCounter};
@@ -219,4 +225,14 @@ impl<G: TrackableGame, T, L, B, C, P> TrackerState<G, T, L, B, C, P> {
     pub fn tracking_type(&self) -> TrackingType {
         self.track_type
     }
+
+    /// Get a reference to the tracker receiving events from this state.
+    pub fn tracker(&self) -> &T {
+        &self.tracker
+    }
+
+    /// Get a mutable reference to the tracker receiving events from this state.
+    pub fn tracker_mut(&mut self) -> &mut T {
+        &mut self.tracker
+    }
 }
@@ -12,6 +12,11 @@ pub enum UpdateStatus<G: TrackableGame, T: TrackGame<G>, D: DriveTracker<G, T>>
     Finished(T::Output),
 }
 
+/// The result of a [`DriveTracker::update`] call.
+///
+/// Failed updates hand the driver back alongside the error, so that the game it was tracking isn't lost.
+pub type UpdateResult<G, T, D> = Result<UpdateStatus<G, T, D>, (D, MemoryReadError<G>)>;
+
 /// Game-specific logic for driving a [`GameTracker`].
 ///
 /// This trait serves as a bridge between a game-specific memory reader type (implementing [`GameMemory`])
@@ -46,15 +51,19 @@ pub trait DriveTracker<G: TrackableGame, T: TrackGame<G>>: Sized {
     ///
     /// This method should either return itself wrapped inside of [`UpdateStatus::Continuing`] if
     /// the active game is ongoing, and the tracker's output within [`UpdateStatus::Finished`] once it has finished.
+    /// If reading the game's state fails, it should return itself alongside the error.
     fn update(
         self,
         access: &<Self::Memory as GameMemory<G>>::MemoryAccess,
-    ) -> Result<UpdateStatus<G, T, Self>, MemoryReadError<G>>;
+    ) -> UpdateResult<G, T, Self>;
 
     /// Terminate tracking for the current game.
     ///
     /// This is called if the attached process exits, or if user code calls [`close`](GameTracker::close) mid-game.
     fn terminate(self) -> T::Output;
+
+    /// Get a mutable reference to the tracker for the game being driven.
+    fn tracker_mut(&mut self) -> &mut T;
 }
 
 #[derive(Debug)]
@@ -82,11 +91,13 @@ enum GameInitState<G: TrackableGame, T: TrackGame<G>, D: DriveTracker<G, T>> {
 ///
 /// Note that the tracker update logic adds a 1-second delay from when a new game is first detected before starting
 /// to track it; this is to ensure that the game process has time to properly initialize its internal state
-/// before we begin reading values.
+/// before we begin reading values. This delay can be changed using [`with_init_delay`](GameTracker::with_init_delay).
 #[derive(Debug)]
 pub struct GameTracker<G: TrackableGame, T: TrackGame<G>, D: DriveTracker<G, T>> {
     state: GameInitState<G, T, D>,
     memory: D::Memory,
+    init_delay: Duration,
+    interrupted: Option<T::Output>,
 }
 
 impl<G: TrackableGame, T: TrackGame<G>, D: DriveTracker<G, T>> GameTracker<G, T, D> {
@@ -95,9 +106,20 @@ impl<G: TrackableGame, T: TrackGame<G>, D: DriveTracker<G, T>> GameTracker<G, T,
         Self {
             memory,
             state: GameInitState::WaitingForGame,
+            init_delay: Duration::from_millis(1000),
+            interrupted: None,
         }
     }
 
+    /// Set how long to wait after a new game is detected before starting to track it.
+    ///
+    /// Replayed memory recordings have already captured a fully-initialized game, so this can be set to zero
+    /// when driving a tracker from one.
+    pub fn with_init_delay(mut self, init_delay: Duration) -> Self {
+        self.init_delay = init_delay;
+        self
+    }
+
     /// Get a reference to the contained game memory instance.
     pub fn memory(&self) -> &D::Memory {
         &self.memory
@@ -118,11 +140,36 @@ impl<G: TrackableGame, T: TrackGame<G>, D: DriveTracker<G, T>> GameTracker<G, T,
         self.memory.is_running()
     }
 
+    /// Get a mutable reference to the tracker for the game currently in progress, if any.
+    ///
+    /// This can be used to retrieve intermediate results from trackers before a game finishes.
+    pub fn tracker_mut(&mut self) -> Option<&mut T> {
+        if let GameInitState::Active(driver, _) = &mut self.state {
+            Some(driver.tracker_mut())
+        } else {
+            None
+        }
+    }
+
     /// Update the tracker by reading new values from the attached game process.
     ///
-    /// If a game has been completed, this method will return the tracker's output.
+    /// Each call to this method counts as a single poll of the game's memory (or, for replayed recordings,
+    /// a single recorded frame). If a game has been completed, this method will return the tracker's output.
+    ///
+    /// If reading the game's state fails partway through a game, tracking for that game is terminated and
+    /// the error is returned; the terminated game's output is then returned by the next call, without polling.
     pub fn update(&mut self) -> Result<Option<T::Output>, MemoryReadError<G>> {
-        if let Some(access) = self.memory.access() {
+        if let Some(output) = self.interrupted.take() {
+            return Ok(Some(output));
+        }
+
+        let access = if self.memory.advance() {
+            self.memory.access()
+        } else {
+            None
+        };
+
+        if let Some(access) = access {
             if D::game_is_active(access)? {
                 return match std::mem::replace(&mut self.state, GameInitState::Updating) {
                     GameInitState::WaitingForGame => {
@@ -131,22 +178,27 @@ impl<G: TrackableGame, T: TrackGame<G>, D: DriveTracker<G, T>> GameTracker<G, T,
                     }
                     GameInitState::WaitingForInit(start) => {
                         self.state = GameInitState::WaitingForInit(start);
-                        if Instant::now().duration_since(start) >= Duration::from_millis(1000) {
+                        if Instant::now().duration_since(start) >= self.init_delay {
                             if let Some(driver) = D::init(access)? {
                                 self.state = GameInitState::Active(driver, PhantomData)
                             }
                         }
                         Ok(None)
                     }
-                    GameInitState::Active(driver, _) => match driver.update(access)? {
-                        UpdateStatus::Continuing(driver) => {
+                    GameInitState::Active(driver, _) => match driver.update(access) {
+                        Ok(UpdateStatus::Continuing(driver)) => {
                             self.state = GameInitState::Active(driver, PhantomData);
                             Ok(None)
                         }
-                        UpdateStatus::Finished(output) => {
+                        Ok(UpdateStatus::Finished(output)) => {
                             self.state = GameInitState::WaitingForGame;
                             Ok(Some(output))
                         }
+                        Err((driver, e)) => {
+                            self.interrupted = Some(driver.terminate());
+                            self.state = GameInitState::WaitingForGame;
+                            Err(e)
+                        }
                     },
                     GameInitState::Updating => unreachable!(), // shouldn't happen
                 };
@@ -165,12 +217,12 @@ impl<G: TrackableGame, T: TrackGame<G>, D: DriveTracker<G, T>> GameTracker<G, T,
     /// Close this tracker, terminating tracking for any games currently in progress.
     ///
     /// Returns the contained memory instance, as well as tracker output for the current
-    /// game if one was in progress.
+    /// game if one was in progress (or was interrupted by a read error and not yet returned).
     pub fn close(self) -> (D::Memory, Option<T::Output>) {
         if let GameInitState::Active(driver, _) = self.state {
             (self.memory, Some(driver.terminate()))
         } else {
-            (self.memory, None)
+            (self.memory, self.interrupted)
         }
     }
 }
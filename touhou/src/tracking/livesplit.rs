@@ -0,0 +1,489 @@
+//! Autosplitting support for [LiveSplit](https://livesplit.org/), via the LiveSplit Server text protocol.
+//!
+//! This is split into two halves: [`LiveSplitTracker`] is a tracker that collects timer events during a game,
+//! and [`LiveSplitClient`] turns those events into commands according to a [`SplitConfig`] and sends
+//! them to a LiveSplit Server instance.
+//!
+//! The client should be kept around between games, and updated with the tracker from
+//! [`GameTracker::tracker_mut`](super::GameTracker::tracker_mut) while a game is in progress,
+//! as well as with the tracker output from [`GameTracker::update`](super::GameTracker::update) once
+//! a game ends.
+
+use std::fmt::{self, Display};
+use std::io::{self, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use super::{
+    Event, EventTime, TrackGame, TrackRun, TrackSpellPractice, TrackStagePractice, TrackableGame,
+    TrackingType, UpdateTracker,
+};
+use crate::{Difficulty, Location, ShotType, Stage};
+
+/// The address that LiveSplit Server listens on by default.
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:16834";
+
+/// A command in the LiveSplit Server text protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    StartTimer,
+    Split,
+    Pause,
+    Resume,
+    Reset,
+    InitGameTime,
+    PauseGameTime,
+    SetGameTime(Duration),
+}
+
+impl Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::StartTimer => f.write_str("starttimer"),
+            Self::Split => f.write_str("split"),
+            Self::Pause => f.write_str("pause"),
+            Self::Resume => f.write_str("resume"),
+            Self::Reset => f.write_str("reset"),
+            Self::InitGameTime => f.write_str("initgametime"),
+            Self::PauseGameTime => f.write_str("pausegametime"),
+            Self::SetGameTime(time) => {
+                let secs = time.as_secs();
+                write!(
+                    f,
+                    "setgametime {}:{:02}:{:02}.{:03}",
+                    secs / 3600,
+                    (secs / 60) % 60,
+                    secs % 60,
+                    time.subsec_millis()
+                )
+            }
+        }
+    }
+}
+
+/// Configures which location changes are mapped to splits.
+///
+/// A split is made whenever the player first moves past one of the configured boundaries
+/// within a game, as well as when a full game is cleared.
+#[derive(Debug, Clone)]
+pub struct SplitConfig<G: TrackableGame> {
+    stages: bool,
+    bosses: bool,
+    locations: Vec<Location<G>>,
+    pause_timer: bool,
+    reset_on_exit: bool,
+}
+
+impl<G: TrackableGame> Default for SplitConfig<G> {
+    fn default() -> Self {
+        Self {
+            stages: true,
+            bosses: false,
+            locations: Vec::new(),
+            pause_timer: true,
+            reset_on_exit: true,
+        }
+    }
+}
+
+impl<G: TrackableGame> SplitConfig<G> {
+    /// Create a new config that splits at the end of each stage.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set whether to split when the player reaches a new stage.
+    pub fn stage_splits(mut self, value: bool) -> Self {
+        self.stages = value;
+        self
+    }
+
+    /// Set whether to split at the start of each boss fight.
+    pub fn boss_splits(mut self, value: bool) -> Self {
+        self.bosses = value;
+        self
+    }
+
+    /// Split when the player first reaches the given location (or any location after it).
+    pub fn with_split_at(mut self, location: Location<G>) -> Self {
+        self.locations.push(location);
+        self
+    }
+
+    /// Set whether to pause the timer while the game is paused.
+    ///
+    /// Game time is always paused while the game is paused.
+    pub fn pause_timer(mut self, value: bool) -> Self {
+        self.pause_timer = value;
+        self
+    }
+
+    /// Set whether to reset the timer when a game is exited without clearing it.
+    pub fn reset_on_exit(mut self, value: bool) -> Self {
+        self.reset_on_exit = value;
+        self
+    }
+
+    /// Count the number of split boundaries between two locations.
+    fn splits_between(&self, prev: Location<G>, cur: Location<G>) -> usize {
+        if cur <= prev {
+            return 0;
+        }
+
+        let mut ret = self
+            .locations
+            .iter()
+            .filter(|&&location| prev < location && location <= cur)
+            .count();
+
+        if self.stages && cur.stage() > prev.stage() {
+            ret += 1;
+        }
+
+        if self.bosses && cur.is_boss_start() {
+            ret += 1;
+        }
+
+        ret
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum TimerEvent<G: TrackableGame> {
+    Start,
+    Location(Location<G>),
+    Pause,
+    Unpause,
+    Finish { cleared: bool },
+}
+
+/// A tracker that collects timer events for a [`LiveSplitClient`].
+///
+/// Spell practice games are ignored by this tracker.
+#[derive(Debug)]
+pub struct LiveSplitTracker<G: TrackableGame> {
+    tracking_type: TrackingType,
+    time: EventTime,
+    events: Vec<(EventTime, TimerEvent<G>)>,
+}
+
+impl<G: TrackableGame> LiveSplitTracker<G> {
+    fn new(tracking_type: TrackingType, time: EventTime) -> Self {
+        let mut ret = Self {
+            tracking_type,
+            time,
+            events: Vec::new(),
+        };
+
+        ret.push(time, TimerEvent::Start);
+        ret
+    }
+
+    fn push(&mut self, time: EventTime, event: TimerEvent<G>) {
+        if self.tracking_type != TrackingType::SpellPractice {
+            self.events.push((time, event));
+        }
+    }
+
+    fn finish(mut self, time: EventTime, cleared: bool) -> Self {
+        self.time = time;
+        self.push(time, TimerEvent::Finish { cleared });
+        self
+    }
+
+    pub fn tracking_type(&self) -> TrackingType {
+        self.tracking_type
+    }
+
+    /// Get the time of the most recent update to this tracker.
+    pub fn time(&self) -> EventTime {
+        self.time
+    }
+}
+
+impl<G: TrackableGame> TrackGame<G> for LiveSplitTracker<G> {
+    type Output = Self;
+    type Update<'a> = LiveSplitUpdate<'a, G>
+    where
+        Self: 'a;
+
+    fn begin_update(&mut self, time: EventTime, _state: G::State) -> Self::Update<'_> {
+        self.time = time;
+        LiveSplitUpdate {
+            tracker: self,
+            time,
+        }
+    }
+}
+
+/// Update type for [`LiveSplitTracker`].
+#[derive(Debug)]
+pub struct LiveSplitUpdate<'a, G: TrackableGame> {
+    tracker: &'a mut LiveSplitTracker<G>,
+    time: EventTime,
+}
+
+impl<'a, G: TrackableGame> UpdateTracker<G> for LiveSplitUpdate<'a, G> {
+    fn push_event(&mut self, event: Event<G>) {
+        match event {
+            Event::Pause => self.tracker.push(self.time, TimerEvent::Pause),
+            Event::Unpause => self.tracker.push(self.time, TimerEvent::Unpause),
+            _ => {}
+        }
+    }
+
+    fn change_location(&mut self, location: Option<Location<G>>) {
+        if let Some(location) = location {
+            self.tracker.push(self.time, TimerEvent::Location(location));
+        }
+    }
+}
+
+impl<G: TrackableGame> TrackRun<G> for LiveSplitTracker<G> {
+    fn start_run(
+        time: EventTime,
+        _shot: ShotType<G>,
+        _difficulty: Difficulty<G>,
+        _state: G::State,
+    ) -> Self {
+        Self::new(TrackingType::FullRun, time)
+    }
+
+    fn run_cleared(self, time: EventTime, _state: G::State) -> Self {
+        self.finish(time, true)
+    }
+
+    fn run_exited(self, time: EventTime, _state: G::State) -> Self {
+        self.finish(time, false)
+    }
+}
+
+impl<G: TrackableGame> TrackStagePractice<G> for LiveSplitTracker<G> {
+    fn start_stage_practice(
+        time: EventTime,
+        _shot: ShotType<G>,
+        _difficulty: Difficulty<G>,
+        _stage: Stage<G>,
+        _state: G::State,
+    ) -> Self {
+        Self::new(TrackingType::StagePractice, time)
+    }
+
+    fn finish_stage_practice(self, time: EventTime, _state: G::State) -> Self {
+        self.finish(time, false)
+    }
+}
+
+impl<G: TrackableGame> TrackSpellPractice<G> for LiveSplitTracker<G> {
+    fn start_spell_practice(
+        time: EventTime,
+        _shot: ShotType<G>,
+        _difficulty: Difficulty<G>,
+        _location: Location<G>,
+        _state: G::State,
+    ) -> Self {
+        Self::new(TrackingType::SpellPractice, time)
+    }
+
+    fn finish_spell_practice(self, time: EventTime, _state: G::State) -> Self {
+        self.finish(time, false)
+    }
+}
+
+/// Sends commands to a LiveSplit Server instance based on events from [`LiveSplitTracker`]s.
+#[derive(Debug)]
+pub struct LiveSplitClient<G: TrackableGame, W: Write = TcpStream> {
+    dst: W,
+    config: SplitConfig<G>,
+    furthest: Option<Location<G>>,
+    running: bool,
+}
+
+impl<G: TrackableGame> LiveSplitClient<G, TcpStream> {
+    /// Connect to a LiveSplit Server instance.
+    pub fn connect<A: ToSocketAddrs>(addr: A, config: SplitConfig<G>) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        Ok(Self::new(stream, config))
+    }
+}
+
+impl<G: TrackableGame, W: Write> LiveSplitClient<G, W> {
+    /// Create a new client that writes commands to the given destination.
+    pub fn new(dst: W, config: SplitConfig<G>) -> Self {
+        Self {
+            dst,
+            config,
+            furthest: None,
+            running: false,
+        }
+    }
+
+    pub fn config(&self) -> &SplitConfig<G> {
+        &self.config
+    }
+
+    /// Get whether this client has started the timer for a game that has not yet finished.
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Send commands for all events collected by a tracker since the last update.
+    ///
+    /// This also updates the timer's game time to the tracker's current play time.
+    pub fn update(&mut self, tracker: &mut LiveSplitTracker<G>) -> io::Result<()> {
+        let mut commands = Vec::new();
+
+        for (time, event) in tracker.events.drain(..) {
+            self.handle_event(&mut commands, time, event);
+        }
+
+        if self.running {
+            commands.push(Command::SetGameTime(tracker.time.play_time()));
+        }
+
+        self.send(&commands)
+    }
+
+    /// Send commands for the remaining events from a finished game.
+    pub fn finish(&mut self, mut tracker: LiveSplitTracker<G>) -> io::Result<()> {
+        self.update(&mut tracker)
+    }
+
+    /// Reset the timer, if it is running.
+    pub fn reset(&mut self) -> io::Result<()> {
+        if self.running {
+            self.running = false;
+            self.furthest = None;
+            self.send(&[Command::Reset])
+        } else {
+            Ok(())
+        }
+    }
+
+    fn handle_event(&mut self, commands: &mut Vec<Command>, time: EventTime, event: TimerEvent<G>) {
+        match event {
+            TimerEvent::Start => {
+                if self.running {
+                    commands.push(Command::Reset);
+                }
+
+                commands.extend([
+                    Command::StartTimer,
+                    Command::InitGameTime,
+                    Command::PauseGameTime,
+                ]);
+
+                self.running = true;
+                self.furthest = None;
+            }
+            _ if !self.running => {}
+            TimerEvent::Location(location) => {
+                if let Some(furthest) = self.furthest {
+                    let splits = self.config.splits_between(furthest, location);
+                    if splits > 0 {
+                        commands.push(Command::SetGameTime(time.play_time()));
+                        commands.extend(std::iter::repeat_n(Command::Split, splits));
+                    }
+                }
+
+                if self.furthest.is_none_or(|furthest| location > furthest) {
+                    self.furthest = Some(location);
+                }
+            }
+            TimerEvent::Pause => {
+                if self.config.pause_timer {
+                    commands.push(Command::Pause);
+                }
+            }
+            TimerEvent::Unpause => {
+                if self.config.pause_timer {
+                    commands.push(Command::Resume);
+                }
+            }
+            TimerEvent::Finish { cleared } => {
+                commands.push(Command::SetGameTime(time.play_time()));
+
+                if cleared {
+                    commands.push(Command::Split);
+                } else if self.config.reset_on_exit {
+                    commands.push(Command::Reset);
+                }
+
+                self.running = false;
+                self.furthest = None;
+            }
+        }
+    }
+
+    fn send(&mut self, commands: &[Command]) -> io::Result<()> {
+        if commands.is_empty() {
+            return Ok(());
+        }
+
+        let mut buf = String::new();
+        for command in commands {
+            buf.push_str(&command.to_string());
+            buf.push_str("\r\n");
+        }
+
+        self.dst.write_all(buf.as_bytes())?;
+        self.dst.flush()
+    }
+}
+
+#[cfg(all(test, feature = "th07"))]
+mod tests {
+    use super::*;
+    use crate::th07::{Location as Th07Location, Stage as Th07Stage};
+    use crate::tracking::GameTimeCounter;
+    use crate::types::AllIterable;
+    use crate::Touhou7;
+
+    #[test]
+    fn command_format() {
+        assert_eq!(
+            Command::SetGameTime(Duration::from_millis(3_723_045)).to_string(),
+            "setgametime 1:02:03.045"
+        );
+    }
+
+    #[test]
+    fn stage_splits() {
+        let counter = GameTimeCounter::new(false);
+        let mut locations = Th07Location::iter_all().map(Location::<Touhou7>::new);
+        let stage_1 = locations.next().unwrap();
+        let stage_2 = Stage::<Touhou7>::new(Th07Stage::Two).start_location();
+        let stage_3 = Stage::<Touhou7>::new(Th07Stage::Three).start_location();
+
+        let mut tracker = LiveSplitTracker::new(TrackingType::FullRun, counter.start_time());
+        for location in [stage_1, stage_2, stage_1, stage_2, stage_3] {
+            tracker.push(counter.now(), TimerEvent::Location(location));
+        }
+
+        let mut client = LiveSplitClient::new(Vec::new(), SplitConfig::new());
+        client.update(&mut tracker).unwrap();
+        assert!(client.is_running());
+
+        client.finish(tracker.finish(counter.now(), false)).unwrap();
+        assert!(!client.is_running());
+
+        let output = String::from_utf8(client.dst).unwrap();
+        let commands: Vec<_> = output
+            .lines()
+            .filter(|line| !line.starts_with("setgametime"))
+            .collect();
+
+        assert_eq!(
+            commands,
+            [
+                "starttimer",
+                "initgametime",
+                "pausegametime",
+                "split",
+                "split",
+                "reset"
+            ]
+        );
+    }
+}
@@ -0,0 +1,347 @@
+//! A built-in tracker that records everything that happens during a game.
+
+use super::{
+    Event, EventTime, TrackGame, TrackRun, TrackSpellPractice, TrackStagePractice, TrackableGame,
+    TrackingType, UpdateTracker,
+};
+use crate::{Difficulty, Location, ShotType, Stage};
+
+/// Something that happened during a recorded game.
+#[derive(Debug)]
+pub enum RecordKind<G: TrackableGame> {
+    /// A game event was pushed to the tracker.
+    Event(Event<G>),
+    /// The player's location changed.
+    ///
+    /// A location of `None` indicates that the player's location could not be determined.
+    Location(Option<Location<G>>),
+}
+
+/// A single entry in a [`RunRecorder`]'s log, along with the time it was recorded.
+#[derive(Debug)]
+pub struct Record<G: TrackableGame> {
+    pub time: EventTime,
+    pub kind: RecordKind<G>,
+}
+
+/// A span of time spent at a single location during a recorded game.
+#[derive(Debug)]
+pub struct LocationSegment<G: TrackableGame> {
+    pub location: Location<G>,
+    pub start_time: EventTime,
+    pub end_time: EventTime,
+    pub misses: u32,
+    pub bombs: u32,
+    pub continues: u32,
+}
+
+impl<G: TrackableGame> LocationSegment<G> {
+    /// Whether the player got through this segment without missing, bombing, or continuing.
+    pub fn is_clean(&self) -> bool {
+        self.misses == 0 && self.bombs == 0 && self.continues == 0
+    }
+}
+
+/// A tracker that keeps a log of every event and location change during a game.
+///
+/// This tracker works with every game supported by the tracking framework, and is used as its own output;
+/// once a game finishes, the recorder can be inspected for the full log of the game.
+///
+/// While a game is in progress, new log entries can be retrieved by calling [`take_new_records`](Self::take_new_records)
+/// on the recorder returned from [`GameTracker::tracker_mut`](super::GameTracker::tracker_mut).
+#[derive(Debug)]
+pub struct RunRecorder<G: TrackableGame> {
+    tracking_type: TrackingType,
+    shot: ShotType<G>,
+    difficulty: Difficulty<G>,
+    stage: Option<Stage<G>>,
+    start_time: EventTime,
+    end_time: Option<EventTime>,
+    cleared: bool,
+    records: Vec<Record<G>>,
+    cursor: usize,
+}
+
+impl<G: TrackableGame> RunRecorder<G> {
+    fn new(
+        tracking_type: TrackingType,
+        time: EventTime,
+        shot: ShotType<G>,
+        difficulty: Difficulty<G>,
+        stage: Option<Stage<G>>,
+    ) -> Self {
+        Self {
+            tracking_type,
+            shot,
+            difficulty,
+            stage,
+            start_time: time,
+            end_time: None,
+            cleared: false,
+            records: Vec::new(),
+            cursor: 0,
+        }
+    }
+
+    fn finish(mut self, time: EventTime, cleared: bool) -> Self {
+        self.end_time = Some(time);
+        self.cleared = cleared;
+        self
+    }
+
+    pub fn tracking_type(&self) -> TrackingType {
+        self.tracking_type
+    }
+
+    pub fn shot(&self) -> ShotType<G> {
+        self.shot
+    }
+
+    pub fn difficulty(&self) -> Difficulty<G> {
+        self.difficulty
+    }
+
+    /// Get the stage being practiced, for stage practice games.
+    pub fn stage(&self) -> Option<Stage<G>> {
+        self.stage
+    }
+
+    pub fn start_time(&self) -> EventTime {
+        self.start_time
+    }
+
+    /// Get the time at which the game ended, or `None` if it is still in progress.
+    pub fn end_time(&self) -> Option<EventTime> {
+        self.end_time
+    }
+
+    /// Whether the game was cleared.
+    ///
+    /// This is always `false` for practice games and for games still in progress.
+    pub fn cleared(&self) -> bool {
+        self.cleared
+    }
+
+    /// Get every record logged during the game so far.
+    pub fn records(&self) -> &[Record<G>] {
+        &self.records
+    }
+
+    /// Get the records logged since the last call to this method.
+    pub fn take_new_records(&mut self) -> &[Record<G>] {
+        let start = self.cursor;
+        self.cursor = self.records.len();
+        &self.records[start..]
+    }
+
+    /// Split the recorded game into segments by location.
+    ///
+    /// Time spent at unknown locations is not included in any segment.
+    pub fn segments(&self) -> Vec<LocationSegment<G>> {
+        let mut segments: Vec<LocationSegment<G>> = Vec::new();
+        let mut current: Option<LocationSegment<G>> = None;
+
+        for record in &self.records {
+            match &record.kind {
+                RecordKind::Location(location) => {
+                    if let Some(mut segment) = current.take() {
+                        segment.end_time = record.time;
+                        segments.push(segment);
+                    }
+
+                    current = location.map(|location| LocationSegment {
+                        location,
+                        start_time: record.time,
+                        end_time: record.time,
+                        misses: 0,
+                        bombs: 0,
+                        continues: 0,
+                    });
+                }
+                RecordKind::Event(event) => {
+                    if let Some(segment) = &mut current {
+                        match event {
+                            Event::Miss => segment.misses += 1,
+                            Event::Bomb => segment.bombs += 1,
+                            Event::Continue => segment.continues += 1,
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(mut segment) = current {
+            segment.end_time = self
+                .end_time
+                .or_else(|| self.records.last().map(|record| record.time))
+                .unwrap_or(segment.start_time);
+            segments.push(segment);
+        }
+
+        segments
+    }
+}
+
+impl<G: TrackableGame> TrackGame<G> for RunRecorder<G> {
+    type Output = Self;
+    type Update<'a> = RecorderUpdate<'a, G>
+    where
+        Self: 'a;
+
+    fn begin_update(&mut self, time: EventTime, _state: G::State) -> Self::Update<'_> {
+        RecorderUpdate {
+            recorder: self,
+            time,
+        }
+    }
+}
+
+/// Update type for [`RunRecorder`].
+#[derive(Debug)]
+pub struct RecorderUpdate<'a, G: TrackableGame> {
+    recorder: &'a mut RunRecorder<G>,
+    time: EventTime,
+}
+
+impl<'a, G: TrackableGame> UpdateTracker<G> for RecorderUpdate<'a, G> {
+    fn push_event(&mut self, event: Event<G>) {
+        self.recorder.records.push(Record {
+            time: self.time,
+            kind: RecordKind::Event(event),
+        });
+    }
+
+    fn change_location(&mut self, location: Option<Location<G>>) {
+        self.recorder.records.push(Record {
+            time: self.time,
+            kind: RecordKind::Location(location),
+        });
+    }
+}
+
+impl<G: TrackableGame> TrackRun<G> for RunRecorder<G> {
+    fn start_run(
+        time: EventTime,
+        shot: ShotType<G>,
+        difficulty: Difficulty<G>,
+        _state: G::State,
+    ) -> Self {
+        Self::new(TrackingType::FullRun, time, shot, difficulty, None)
+    }
+
+    fn run_cleared(self, time: EventTime, _state: G::State) -> Self {
+        self.finish(time, true)
+    }
+
+    fn run_exited(self, time: EventTime, _state: G::State) -> Self {
+        self.finish(time, false)
+    }
+}
+
+impl<G: TrackableGame> TrackStagePractice<G> for RunRecorder<G> {
+    fn start_stage_practice(
+        time: EventTime,
+        shot: ShotType<G>,
+        difficulty: Difficulty<G>,
+        stage: Stage<G>,
+        _state: G::State,
+    ) -> Self {
+        Self::new(
+            TrackingType::StagePractice,
+            time,
+            shot,
+            difficulty,
+            Some(stage),
+        )
+    }
+
+    fn finish_stage_practice(self, time: EventTime, _state: G::State) -> Self {
+        self.finish(time, false)
+    }
+}
+
+impl<G: TrackableGame> TrackSpellPractice<G> for RunRecorder<G> {
+    fn start_spell_practice(
+        time: EventTime,
+        shot: ShotType<G>,
+        difficulty: Difficulty<G>,
+        location: Location<G>,
+        _state: G::State,
+    ) -> Self {
+        let mut ret = Self::new(
+            TrackingType::SpellPractice,
+            time,
+            shot,
+            difficulty,
+            Some(location.stage()),
+        );
+
+        ret.records.push(Record {
+            time,
+            kind: RecordKind::Location(Some(location)),
+        });
+
+        ret
+    }
+
+    fn finish_spell_practice(self, time: EventTime, _state: G::State) -> Self {
+        self.finish(time, false)
+    }
+}
+
+#[cfg(all(test, feature = "th07"))]
+mod tests {
+    use super::*;
+    use crate::th07::{
+        Difficulty as Th07Difficulty, Location as Th07Location, ShotType as Th07Shot,
+    };
+    use crate::tracking::GameTimeCounter;
+    use crate::types::AllIterable;
+    use crate::Touhou7;
+
+    #[test]
+    fn segments() {
+        let counter = GameTimeCounter::new(false);
+        let mut locations = Th07Location::iter_all().map(Location::<Touhou7>::new);
+        let first = locations.next().unwrap();
+        let second = locations.next().unwrap();
+
+        let mut recorder = RunRecorder::new(
+            TrackingType::FullRun,
+            counter.start_time(),
+            ShotType::new(Th07Shot::ReimuA),
+            Difficulty::new(Th07Difficulty::Normal),
+            None,
+        );
+
+        let kinds = [
+            RecordKind::Location(Some(first)),
+            RecordKind::Event(Event::Miss),
+            RecordKind::Event(Event::Bomb),
+            RecordKind::Event(Event::Pause),
+            RecordKind::Location(None),
+            RecordKind::Event(Event::Miss),
+            RecordKind::Location(Some(second)),
+        ];
+
+        for kind in kinds {
+            recorder.records.push(Record {
+                time: counter.now(),
+                kind,
+            });
+        }
+
+        assert_eq!(recorder.take_new_records().len(), 7);
+        assert!(recorder.take_new_records().is_empty());
+
+        let recorder = recorder.finish(counter.now(), true);
+        let segments = recorder.segments();
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].location, first);
+        assert_eq!((segments[0].misses, segments[0].bombs), (1, 1));
+        assert_eq!(segments[1].location, second);
+        assert!(segments[1].is_clean());
+        assert_eq!(segments[1].end_time, recorder.end_time().unwrap());
+    }
+}
@@ -28,6 +28,7 @@ impl<G: Game> Stage<G> {
     }
 }
 
+#[cfg(feature = "memory")]
 impl<G: HasLocations> Stage<G> {
     pub fn start_location(&self) -> Location<G> {
         Location::new(G::stage_start_location(self.unwrap()))